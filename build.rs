@@ -0,0 +1,12 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Builds don't require a system protoc
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    let includes = ["proto".into(), protoc_bin_vendored::include_path()?];
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/supply/v1/supply.proto"], &includes)?;
+    println!("cargo:rerun-if-changed=proto");
+    Ok(())
+}
@@ -0,0 +1,7 @@
+CREATE TABLE supplies (
+    id VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    prices JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ
+);
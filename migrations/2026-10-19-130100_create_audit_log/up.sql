@@ -0,0 +1,14 @@
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    aggregate_type VARCHAR NOT NULL,
+    aggregate_id VARCHAR NOT NULL,
+    before JSONB,
+    after JSONB,
+    request_id VARCHAR,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_aggregate_idx ON audit_log (aggregate_type, aggregate_id, occurred_at DESC);
+CREATE INDEX audit_log_actor_idx ON audit_log (actor, occurred_at DESC);
@@ -0,0 +1,19 @@
+DROP POLICY tenant_isolation ON audit_log;
+ALTER TABLE audit_log NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE audit_log DISABLE ROW LEVEL SECURITY;
+
+DROP POLICY tenant_isolation ON posts;
+ALTER TABLE posts NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE posts DISABLE ROW LEVEL SECURITY;
+
+DROP POLICY tenant_isolation ON supplies;
+ALTER TABLE supplies NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE supplies DISABLE ROW LEVEL SECURITY;
+
+DROP INDEX audit_log_tenant_idx;
+DROP INDEX posts_tenant_idx;
+DROP INDEX supplies_tenant_idx;
+
+ALTER TABLE audit_log DROP COLUMN tenant_id;
+ALTER TABLE posts DROP COLUMN tenant_id;
+ALTER TABLE supplies DROP COLUMN tenant_id;
@@ -0,0 +1,31 @@
+ALTER TABLE supplies ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT 'default';
+ALTER TABLE posts ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT 'default';
+ALTER TABLE audit_log ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT 'default';
+
+ALTER TABLE supplies ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE posts ALTER COLUMN tenant_id DROP DEFAULT;
+ALTER TABLE audit_log ALTER COLUMN tenant_id DROP DEFAULT;
+
+CREATE INDEX supplies_tenant_idx ON supplies (tenant_id, name);
+CREATE INDEX posts_tenant_idx ON posts (tenant_id, id);
+CREATE INDEX audit_log_tenant_idx ON audit_log (tenant_id, occurred_at DESC);
+
+-- Rows are only visible to connections that ran
+-- `SELECT set_config('app.tenant_id', '<tenant>', true)` in their transaction.
+ALTER TABLE supplies ENABLE ROW LEVEL SECURITY;
+ALTER TABLE supplies FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON supplies
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
+
+ALTER TABLE posts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE posts FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON posts
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
+
+ALTER TABLE audit_log ENABLE ROW LEVEL SECURITY;
+ALTER TABLE audit_log FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON audit_log
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
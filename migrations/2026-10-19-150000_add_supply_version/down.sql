@@ -0,0 +1 @@
+ALTER TABLE supplies DROP COLUMN version;
@@ -0,0 +1 @@
+ALTER TABLE supplies ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
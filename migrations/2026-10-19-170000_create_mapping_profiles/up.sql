@@ -0,0 +1,13 @@
+CREATE TABLE mapping_profiles (
+    tenant_id VARCHAR NOT NULL,
+    supplier VARCHAR NOT NULL,
+    profile JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (tenant_id, supplier)
+);
+
+ALTER TABLE mapping_profiles ENABLE ROW LEVEL SECURITY;
+ALTER TABLE mapping_profiles FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON mapping_profiles
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
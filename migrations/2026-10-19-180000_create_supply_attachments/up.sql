@@ -0,0 +1,19 @@
+CREATE TABLE supply_attachments (
+    id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL,
+    supply_id VARCHAR NOT NULL REFERENCES supplies (id) ON DELETE CASCADE,
+    file_name VARCHAR NOT NULL,
+    content_type VARCHAR NOT NULL,
+    size BIGINT NOT NULL,
+    checksum VARCHAR NOT NULL,
+    storage_key VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX supply_attachments_supply_idx ON supply_attachments (tenant_id, supply_id, created_at);
+
+ALTER TABLE supply_attachments ENABLE ROW LEVEL SECURITY;
+ALTER TABLE supply_attachments FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON supply_attachments
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
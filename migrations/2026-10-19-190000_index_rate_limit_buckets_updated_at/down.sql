@@ -0,0 +1 @@
+DROP INDEX rate_limit_buckets_updated_at;
@@ -0,0 +1 @@
+CREATE INDEX rate_limit_buckets_updated_at ON rate_limit_buckets (updated_at);
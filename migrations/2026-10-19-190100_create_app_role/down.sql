@@ -0,0 +1,10 @@
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+    REVOKE USAGE, SELECT ON SEQUENCES FROM app;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+    REVOKE SELECT, INSERT, UPDATE, DELETE ON TABLES FROM app;
+
+REVOKE ALL ON ALL SEQUENCES IN SCHEMA public FROM app;
+REVOKE ALL ON ALL TABLES IN SCHEMA public FROM app;
+REVOKE USAGE ON SCHEMA public FROM app;
+
+DROP ROLE app;
@@ -0,0 +1,12 @@
+CREATE TABLE supply_list_items (
+    tenant_id VARCHAR NOT NULL,
+    list VARCHAR NOT NULL,
+    supply_id VARCHAR NOT NULL REFERENCES supplies (id) ON DELETE CASCADE,
+    PRIMARY KEY (tenant_id, list, supply_id)
+);
+
+ALTER TABLE supply_list_items ENABLE ROW LEVEL SECURITY;
+ALTER TABLE supply_list_items FORCE ROW LEVEL SECURITY;
+CREATE POLICY tenant_isolation ON supply_list_items
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
@@ -0,0 +1,202 @@
+use crate::application::supply::SupplyOutput;
+use crate::domain::supply::price::Price;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use crate::problem::FieldError;
+use rocket::FromForm;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Empty price rows offered below the filled ones.
+const BLANK_PRICE_ROWS: usize = 2;
+
+#[derive(Debug, Clone, Default, FromForm, Serialize)]
+pub struct PriceField {
+    pub unit: String,
+    pub value: String,
+}
+
+impl PriceField {
+    fn is_blank(&self) -> bool {
+        self.unit.trim().is_empty() && self.value.trim().is_empty()
+    }
+}
+
+/// The supply form, sent as `name`, `prices[0].unit`, `prices[0].value`…
+/// and, when editing, the `version` the form was opened at.
+#[derive(Debug, Clone, Default, FromForm)]
+pub struct SupplyForm {
+    pub name: String,
+    pub prices: Vec<PriceField>,
+    pub version: Option<i64>,
+}
+
+impl SupplyForm {
+    /// The filled rows as prices. Values take a dot or a comma before the
+    /// cents.
+    pub fn prices(&self) -> Result<Vec<Price>, Notification> {
+        let mut errors = Vec::new();
+        let prices = self
+            .prices
+            .iter()
+            .filter(|row| !row.is_blank())
+            .filter_map(|row| {
+                let value = row.value.trim().replace(',', ".");
+                match Decimal::from_str_exact(&value) {
+                    Ok(mut value) => {
+                        value.rescale(2);
+                        Some(Price::from_dec(row.unit.trim(), value))
+                    }
+                    Err(_) => {
+                        errors.push(CustomError::Validation(
+                            Message::new("price-not-a-number")
+                                .arg("unit", row.unit.trim())
+                                .for_field("price"),
+                        ));
+                        None
+                    }
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(prices)
+        } else {
+            Err(Notification::with_errors(errors))
+        }
+    }
+
+    /// The form as sent, to show again next to its errors.
+    pub fn view(&self) -> SupplyFormView {
+        SupplyFormView::new(&self.name, self.prices.clone(), self.version)
+    }
+}
+
+/// What the form template shows in its fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupplyFormView {
+    pub name: String,
+    pub prices: Vec<PriceField>,
+    pub version: Option<i64>,
+}
+
+impl SupplyFormView {
+    fn new(name: &str, prices: Vec<PriceField>, version: Option<i64>) -> Self {
+        let mut prices = prices
+            .into_iter()
+            .filter(|row| !row.is_blank())
+            .collect::<Vec<_>>();
+        prices.extend(std::iter::repeat_n(PriceField::default(), BLANK_PRICE_ROWS));
+        Self {
+            name: name.to_string(),
+            prices,
+            version,
+        }
+    }
+
+    pub fn blank() -> Self {
+        Self::new("", Vec::new(), None)
+    }
+}
+
+impl From<&SupplyOutput> for SupplyFormView {
+    fn from(supply: &SupplyOutput) -> Self {
+        let prices = supply
+            .prices
+            .iter()
+            .map(|price| PriceField {
+                unit: price.get_unit().to_string(),
+                value: price.get_value_formatted(),
+            })
+            .collect();
+        Self::new(&supply.name, prices, Some(supply.version))
+    }
+}
+
+/// Errors of a form, next to the field they are about. Errors about no
+/// field are shown above the form.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FormErrors {
+    pub fields: BTreeMap<String, Vec<String>>,
+    pub form: Vec<String>,
+}
+
+impl FormErrors {
+    /// The errors of `notification`, in `locale`.
+    pub fn new(notification: &Notification, locale: Locale) -> Self {
+        let mut errors = FormErrors::default();
+        for error in &notification.errors {
+            errors.add(FieldError::new(error, locale));
+        }
+        errors
+    }
+
+    fn add(&mut self, error: FieldError) {
+        match error.field {
+            Some(field) => self.fields.entry(field).or_default().push(error.message),
+            None => self.form.push(error.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod forms_tests {
+    use super::*;
+
+    fn row(unit: &str, value: &str) -> PriceField {
+        PriceField {
+            unit: unit.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_the_filled_price_rows() {
+        let form = SupplyForm {
+            name: "cimento".to_string(),
+            prices: vec![row("sc", "27,5"), row(" ", ""), row("kg", "1.20")],
+            version: None,
+        };
+        assert_eq!(
+            form.prices().unwrap(),
+            vec![Price::new("sc", 2750), Price::new("kg", 120)]
+        );
+        assert_eq!(form.view().prices.len(), 2 + BLANK_PRICE_ROWS);
+
+        let form = SupplyForm {
+            prices: vec![row("sc", "caro")],
+            ..form
+        };
+        let notification = form.prices().unwrap_err();
+        assert_eq!(
+            FormErrors::new(&notification, Locale::En).fields["price"],
+            vec!["'price' of 'sc' must be a number, like 12.50"]
+        );
+        assert_eq!(
+            FormErrors::new(&notification, Locale::PtBr).fields["price"],
+            vec!["'price' de 'sc' deve ser um número, como 12,50"]
+        );
+    }
+
+    #[test]
+    fn puts_errors_next_to_the_fields_they_name() {
+        let notification = Notification::with_errors(vec![
+            CustomError::Validation(Message::new("name-empty").for_field("name")),
+            CustomError::Error("'name' must be between 1 and 255 characters".to_string()),
+            CustomError::VersionConflict(
+                Message::new("supply-version-conflict")
+                    .arg("id", "s1")
+                    .arg("current", 3i64)
+                    .arg("expected", 2i64),
+            ),
+        ]);
+        let errors = FormErrors::new(&notification, Locale::En);
+        assert_eq!(errors.fields["name"].len(), 2);
+        assert_eq!(
+            errors.form,
+            vec!["VersionConflict: supply 's1' is at version 3, not 2"]
+        );
+    }
+}
@@ -0,0 +1,793 @@
+pub mod forms;
+pub mod session;
+
+use self::forms::FormErrors;
+use self::forms::SupplyForm;
+use self::forms::SupplyFormView;
+use self::session::Admin;
+use self::session::AdminConfig;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::create_supply::CreateSupplyUseCase;
+use crate::application::supply::delete_supply::DeleteSupplyUseCase;
+use crate::application::supply::get_supply::GetSupplyUseCase;
+use crate::application::supply::price_history::PriceHistoryUseCase;
+use crate::application::supply::search_supplies::SearchSuppliesUseCase;
+use crate::application::supply::search_supplies::SupplySearch;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::update_supply::UpdateSupplyUseCase;
+use crate::application::supply::SupplyOutput;
+use crate::domain::supply::price::Price;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::domain::validation::validation_handler::ValidationHandler;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use crate::infra::db::DbSqlx;
+use crate::middler::csrf::CsrfToken;
+use crate::middler::locale;
+use crate::middler::request_id::correlate;
+use crate::problem;
+use crate::problem::Problem;
+use crate::supplies::audit_gateway;
+use crate::supplies::supply_gateway;
+use crate::supply_events::SupplyEventStream;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket::catch;
+use rocket::catchers;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::form::Form;
+use rocket::get;
+use rocket::http::ContentType;
+use rocket::http::CookieJar;
+use rocket::http::Status;
+use rocket::post;
+use rocket::request::FlashMessage;
+use rocket::response;
+use rocket::response::Flash;
+use rocket::response::Redirect;
+use rocket::response::Responder;
+use rocket::routes;
+use rocket::tokio::task;
+use rocket::uri;
+use rocket::Build;
+use rocket::FromForm;
+use rocket::Request;
+use rocket::Rocket;
+use rocket::State;
+use rocket_dyn_templates::context;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+use tracing::error;
+use tracing::warn;
+
+pub const BASE: &str = "/admin/ui";
+
+/// Mounts the server-rendered admin UI under `/admin/ui`, with the accounts
+/// of `[admin]` in Rocket.toml. Its pages are HTML, so they stay out of the
+/// API routes and their OpenAPI document.
+pub struct AdminUi;
+
+#[rocket::async_trait]
+impl Fairing for AdminUi {
+    fn info(&self) -> Info {
+        Info {
+            name: "Admin UI",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket.figment().extract_inner::<AdminConfig>("admin") {
+            Ok(config) => config,
+            Err(e) if e.missing() => AdminConfig::default(),
+            Err(e) => {
+                error!("invalid admin config: {}", e);
+                return Err(rocket);
+            }
+        };
+        if let Err(e) = config.validate() {
+            error!("invalid admin config: {}", e);
+            return Err(rocket);
+        }
+        if config.users.is_empty() {
+            warn!("no [admin.users] configured, nobody can log into the admin UI");
+        }
+
+        Ok(rocket
+            .manage(config)
+            .mount(BASE, correlate(routes()))
+            .register(BASE, catchers![unauthorized, failed]))
+    }
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        stylesheet,
+        switch_locale,
+        login_page,
+        login,
+        logout,
+        list,
+        new,
+        create,
+        find,
+        edit,
+        update,
+        delete
+    ]
+}
+
+/// How a failed admin action is shown: the form again, with its errors next
+/// to its fields, or an error page.
+pub enum Failure {
+    Form(Status, Template),
+    Error(Problem),
+}
+
+impl From<Notification> for Failure {
+    fn from(notification: Notification) -> Self {
+        Failure::Error(Problem::from(notification))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Failure {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            Failure::Form(status, template) => (status, template).respond_to(req),
+            Failure::Error(problem) => {
+                let locale = locale::detect(req);
+                let problem = problem.localize(locale);
+                error_page(locale, problem.status(), &problem.detail).respond_to(req)
+            }
+        }
+    }
+}
+
+type Result<T, E = Failure> = std::result::Result<T, E>;
+
+fn error_page(locale: Locale, status: Status, detail: &str) -> (Status, Template) {
+    (
+        status,
+        Template::render(
+            "admin/error",
+            context! {
+                locale,
+                title: status.reason_lossy(),
+                status: status.code,
+                detail,
+            },
+        ),
+    )
+}
+
+#[catch(401)]
+fn unauthorized() -> Redirect {
+    Redirect::to(uri!("/admin/ui", login_page))
+}
+
+#[catch(default)]
+fn failed(status: Status, req: &Request) -> (Status, Template) {
+    error_page(
+        locale::detect(req),
+        status,
+        &problem::catcher_detail(status, req),
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct FlashView {
+    kind: String,
+    message: String,
+}
+
+fn flash_view(flash: Option<FlashMessage<'_>>) -> Option<FlashView> {
+    flash.map(|flash| FlashView {
+        kind: flash.kind().to_string(),
+        message: flash.message().to_string(),
+    })
+}
+
+/// A supply as the list and detail pages show it.
+#[derive(Debug, Serialize)]
+struct SupplyView {
+    id: String,
+    name: String,
+    prices: Vec<PriceView>,
+    version: i64,
+    created_at: String,
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PriceView {
+    unit: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PriceChangeView {
+    prices: Vec<PriceView>,
+    actor: String,
+    occurred_at: String,
+}
+
+fn timestamp(at: &DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+fn price_views(prices: &[Price]) -> Vec<PriceView> {
+    prices
+        .iter()
+        .map(|price| PriceView {
+            unit: price.get_unit().to_string(),
+            value: price.get_value_formatted(),
+        })
+        .collect()
+}
+
+impl From<&SupplyOutput> for SupplyView {
+    fn from(supply: &SupplyOutput) -> Self {
+        Self {
+            id: supply.id.to_owned(),
+            name: supply.name.to_owned(),
+            prices: price_views(&supply.prices),
+            version: supply.version,
+            created_at: timestamp(&supply.created_at),
+            updated_at: supply.updated_at.as_ref().map(timestamp),
+        }
+    }
+}
+
+#[get("/admin.css")]
+fn stylesheet() -> (ContentType, &'static str) {
+    (ContentType::CSS, include_str!("admin.css"))
+}
+
+/// Remembers the language picked in the header of every page, then shows the
+/// supplies, or the login page to visitors.
+#[post("/locale", data = "<form>")]
+fn switch_locale(cookies: &CookieJar<'_>, form: Form<LocaleForm>) -> Redirect {
+    if let Some(locale) = Locale::parse(&form.locale) {
+        locale::remember(cookies, locale);
+    }
+    Redirect::to(uri!("/admin/ui", list(_, _)))
+}
+
+#[derive(Debug, FromForm)]
+pub struct LocaleForm {
+    locale: String,
+}
+
+#[derive(Debug, FromForm)]
+pub struct LoginForm {
+    username: String,
+    password: String,
+}
+
+#[get("/login")]
+fn login_page(locale: Locale, csrf: CsrfToken, flash: Option<FlashMessage<'_>>) -> Template {
+    Template::render(
+        "admin/login",
+        context! {
+            locale,
+            title: locale.text("admin-log-in"),
+            csrf_token: csrf.value(),
+            flash: flash_view(flash),
+        },
+    )
+}
+
+#[post("/login", data = "<form>")]
+async fn login(
+    config: &State<AdminConfig>,
+    cookies: &CookieJar<'_>,
+    locale: Locale,
+    csrf: CsrfToken,
+    form: Form<LoginForm>,
+) -> Result<Redirect> {
+    let LoginForm { username, password } = form.into_inner();
+    let accounts = config.inner().clone();
+    let name = username.clone();
+    let user = task::spawn_blocking(move || accounts.authenticate(&name, &password).cloned())
+        .await
+        .ok()
+        .flatten();
+
+    match user {
+        Some(user) => {
+            session::start(cookies, config, &username, &user);
+            Ok(Redirect::to(uri!("/admin/ui", list(_, _))))
+        }
+        None => Err(Failure::Form(
+            Status::Unauthorized,
+            Template::render(
+                "admin/login",
+                context! {
+                    locale,
+                    title: locale.text("admin-log-in"),
+                    csrf_token: csrf.value(),
+                    username,
+                    error: locale.text("admin-login-failed"),
+                },
+            ),
+        )),
+    }
+}
+
+#[post("/logout")]
+fn logout(cookies: &CookieJar<'_>, locale: Locale) -> Flash<Redirect> {
+    session::end(cookies);
+    Flash::success(
+        Redirect::to(uri!("/admin/ui", login_page)),
+        locale.text("admin-logged-out"),
+    )
+}
+
+#[get("/supplies?<q>&<page>")]
+async fn list(
+    db: &State<DbSqlx>,
+    admin: Admin,
+    locale: Locale,
+    csrf: CsrfToken,
+    flash: Option<FlashMessage<'_>>,
+    q: Option<String>,
+    page: Option<usize>,
+) -> Result<Template> {
+    let search = SupplySearch {
+        query: q.clone(),
+        page: page.unwrap_or(1),
+        ..SupplySearch::default()
+    };
+    let found = SearchSuppliesUseCase::new(supply_gateway(db, &admin.context.tenant_id))
+        .execute(&search)
+        .await?;
+
+    let link = |page: usize| uri!("/admin/ui", list(q.as_deref(), Some(page))).to_string();
+    Ok(Template::render(
+        "admin/supplies",
+        context! {
+            locale,
+            title: locale.text("admin-supplies"),
+            user: &admin.user,
+            csrf_token: csrf.value(),
+            flash: flash_view(flash),
+            q: &q,
+            supplies: found.supplies.iter().map(SupplyView::from).collect::<Vec<_>>(),
+            total: found.total,
+            page: found.page,
+            pages: found.pages,
+            previous: (found.page > 1).then(|| link(found.page - 1)),
+            next: (found.page < found.pages).then(|| link(found.page + 1)),
+        },
+    ))
+}
+
+fn form_page(
+    admin: &Admin,
+    locale: Locale,
+    csrf: &CsrfToken,
+    id: Option<&str>,
+    form: SupplyFormView,
+    errors: FormErrors,
+) -> Template {
+    let action = match id {
+        Some(id) => uri!("/admin/ui", update(id)).to_string(),
+        None => uri!("/admin/ui", create).to_string(),
+    };
+    Template::render(
+        "admin/form",
+        context! {
+            locale,
+            title: locale.text(if id.is_some() {
+                "admin-edit-supply"
+            } else {
+                "admin-new-supply"
+            }),
+            user: &admin.user,
+            csrf_token: csrf.value(),
+            id,
+            action,
+            form,
+            errors,
+        },
+    )
+}
+
+/// The form again with the errors of `notification`, or the error page when
+/// they are not about the form.
+fn rejected(
+    admin: &Admin,
+    locale: Locale,
+    csrf: &CsrfToken,
+    id: Option<&str>,
+    form: &SupplyForm,
+    notification: Notification,
+) -> Failure {
+    match notification.get_first_error() {
+        Some(CustomError::Error(_))
+        | Some(CustomError::Validation(_))
+        | Some(CustomError::VersionConflict(_)) => Failure::Form(
+            Status::UnprocessableEntity,
+            form_page(
+                admin,
+                locale,
+                csrf,
+                id,
+                form.view(),
+                FormErrors::new(&notification, locale),
+            ),
+        ),
+        _ => Failure::from(notification),
+    }
+}
+
+#[get("/supplies/new")]
+fn new(admin: Admin, locale: Locale, csrf: CsrfToken) -> Template {
+    form_page(
+        &admin,
+        locale,
+        &csrf,
+        None,
+        SupplyFormView::blank(),
+        FormErrors::default(),
+    )
+}
+
+#[post("/supplies", data = "<form>")]
+async fn create(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    admin: Admin,
+    locale: Locale,
+    csrf: CsrfToken,
+    form: Form<SupplyForm>,
+) -> Result<Flash<Redirect>> {
+    let prices = form
+        .prices()
+        .map_err(|notification| rejected(&admin, locale, &csrf, None, &form, notification))?;
+    let tenant_id = &admin.context.tenant_id;
+    let created = CreateSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(
+            &admin.context,
+            CreateSupplyInput {
+                name: form.name.to_owned(),
+                prices,
+            },
+        )
+        .await
+        .map_err(|notification| rejected(&admin, locale, &csrf, None, &form, notification))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!("/admin/ui", find(&created.id))),
+        Message::new("admin-created")
+            .arg("name", created.name)
+            .render(locale),
+    ))
+}
+
+#[get("/supplies/<id>")]
+async fn find(
+    db: &State<DbSqlx>,
+    admin: Admin,
+    locale: Locale,
+    csrf: CsrfToken,
+    flash: Option<FlashMessage<'_>>,
+    id: &str,
+) -> Result<Template> {
+    let tenant_id = &admin.context.tenant_id;
+    let supply = GetSupplyUseCase::new(supply_gateway(db, tenant_id))
+        .execute(id)
+        .await?;
+    let mut history = PriceHistoryUseCase::new(audit_gateway(db, tenant_id))
+        .execute(&[supply.id.to_owned()])
+        .await?
+        .remove(&supply.id)
+        .unwrap_or_default();
+    history.reverse();
+
+    Ok(Template::render(
+        "admin/supply",
+        context! {
+            locale,
+            title: &supply.name,
+            user: &admin.user,
+            csrf_token: csrf.value(),
+            flash: flash_view(flash),
+            supply: SupplyView::from(&supply),
+            history: history
+                .iter()
+                .map(|change| PriceChangeView {
+                    prices: price_views(&change.prices),
+                    actor: change.actor.to_owned(),
+                    occurred_at: timestamp(&change.occurred_at),
+                })
+                .collect::<Vec<_>>(),
+        },
+    ))
+}
+
+#[get("/supplies/<id>/edit")]
+async fn edit(
+    db: &State<DbSqlx>,
+    admin: Admin,
+    locale: Locale,
+    csrf: CsrfToken,
+    id: &str,
+) -> Result<Template> {
+    let supply = GetSupplyUseCase::new(supply_gateway(db, &admin.context.tenant_id))
+        .execute(id)
+        .await?;
+    Ok(form_page(
+        &admin,
+        locale,
+        &csrf,
+        Some(id),
+        SupplyFormView::from(&supply),
+        FormErrors::default(),
+    ))
+}
+
+/// Saves the form only over the version it was opened at, so a change made
+/// meanwhile is reported instead of overwritten.
+#[post("/supplies/<id>", data = "<form>")]
+async fn update(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    admin: Admin,
+    locale: Locale,
+    csrf: CsrfToken,
+    id: &str,
+    form: Form<SupplyForm>,
+) -> Result<Flash<Redirect>> {
+    let prices = form
+        .prices()
+        .map_err(|notification| rejected(&admin, locale, &csrf, Some(id), &form, notification))?;
+    let tenant_id = &admin.context.tenant_id;
+    let updated = UpdateSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(
+            &admin.context,
+            id,
+            form.version,
+            UpdateSupplyInput {
+                name: Some(form.name.to_owned()),
+                prices: Some(prices),
+            },
+        )
+        .await
+        .map_err(|notification| rejected(&admin, locale, &csrf, Some(id), &form, notification))?;
+
+    Ok(Flash::success(
+        Redirect::to(uri!("/admin/ui", find(id))),
+        Message::new("admin-saved")
+            .arg("name", updated.name)
+            .render(locale),
+    ))
+}
+
+#[post("/supplies/<id>/delete")]
+async fn delete(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    admin: Admin,
+    locale: Locale,
+    id: &str,
+) -> Result<Flash<Redirect>> {
+    let tenant_id = &admin.context.tenant_id;
+    DeleteSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(&admin.context, id)
+        .await?;
+    Ok(Flash::success(
+        Redirect::to(uri!("/admin/ui", list(_, _))),
+        locale.text("admin-deleted"),
+    ))
+}
+
+#[cfg(test)]
+mod admin_tests {
+    use super::session::hash_password;
+    use super::session::AdminUser;
+    use super::*;
+    use crate::domain::identifier::Identifier;
+    use crate::i18n;
+    use crate::middler::csrf;
+    use crate::middler::csrf::CsrfProtection;
+    use crate::middler::csrf::CSRF_FIELD;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+
+    #[get("/whoami")]
+    fn whoami(admin: Admin) -> String {
+        format!("{} {}", admin.user, admin.context.tenant_id.get_value())
+    }
+
+    /// The supplies page of a search matching nothing, without Postgres.
+    #[get("/no-matches?<q>")]
+    fn no_matches(admin: Admin, locale: Locale, csrf: CsrfToken, q: String) -> Template {
+        Template::render(
+            "admin/supplies",
+            context! {
+                locale,
+                title: locale.text("admin-supplies"),
+                user: &admin.user,
+                csrf_token: csrf.value(),
+                q: &q,
+                supplies: Vec::<SupplyView>::new(),
+                total: 0,
+                page: 1,
+                pages: 1,
+            },
+        )
+    }
+
+    /// The session routes and a page behind the `Admin` guard; the supply
+    /// pages need Postgres.
+    fn client() -> Client {
+        let mut config = AdminConfig::default();
+        config.users.insert(
+            "alice".to_string(),
+            AdminUser {
+                password: hash_password("secret").unwrap(),
+                tenant: "acme".to_string(),
+            },
+        );
+        let rocket = rocket::build()
+            .manage(config)
+            .attach(CsrfProtection)
+            .attach(Template::custom(|engines| {
+                csrf::register_helpers(&mut engines.handlebars);
+                i18n::register_helpers(&mut engines.handlebars);
+            }))
+            .mount(
+                BASE,
+                routes![switch_locale, login_page, login, logout, whoami, no_matches],
+            )
+            .register(BASE, catchers![unauthorized, failed]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn csrf_token(page: &str) -> String {
+        let start = page.find(r#"name="csrf_token" value=""#).unwrap() + 25;
+        page[start..].split('"').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn sends_visitors_to_the_login_page() {
+        let client = client();
+        let response = client.get("/admin/ui/whoami").dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("/admin/ui/login")
+        );
+
+        let response = client.get("/admin/ui/missing").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+    }
+
+    #[test]
+    fn logs_in_and_out_with_a_session_cookie() {
+        let client = client();
+        let page = client
+            .get("/admin/ui/login")
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let token = csrf_token(&page);
+
+        let response = client
+            .post("/admin/ui/login")
+            .header(ContentType::Form)
+            .body(format!(
+                "{}={}&username=alice&password=wrong",
+                CSRF_FIELD, token
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains("Unknown user or wrong password"));
+        assert!(client.cookies().get_private("admin_session").is_none());
+
+        let response = client
+            .post("/admin/ui/login")
+            .header(ContentType::Form)
+            .body(format!(
+                "{}={}&username=alice&password=secret",
+                CSRF_FIELD, token
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("/admin/ui/supplies")
+        );
+        assert!(client.cookies().get_private("admin_session").is_some());
+        let response = client.get("/admin/ui/whoami").dispatch();
+        assert_eq!(response.into_string().unwrap(), "alice acme");
+
+        let response = client
+            .post("/admin/ui/logout")
+            .header(Header::new(csrf::CSRF_HEADER, token))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert!(client.cookies().get_private("admin_session").is_none());
+        let response = client.get("/admin/ui/whoami").dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+    }
+
+    #[test]
+    fn speaks_the_language_of_the_visitor() {
+        let client = client();
+        let page = client
+            .get("/admin/ui/login")
+            .header(Header::new("Accept-Language", "pt-BR,pt;q=0.9"))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert!(page.contains(r#"<html lang="pt-BR">"#));
+        assert!(page.contains("<h1>Entrar</h1>"));
+        let token = csrf_token(&page);
+
+        let response = client
+            .post("/admin/ui/locale")
+            .header(ContentType::Form)
+            .body(format!("{}={}&locale=pt-BR", CSRF_FIELD, token))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(
+            client
+                .cookies()
+                .get(locale::LOCALE_COOKIE)
+                .map(|c| c.value()),
+            Some("pt-BR")
+        );
+
+        let response = client
+            .post("/admin/ui/login")
+            .header(ContentType::Form)
+            .header(Header::new("Accept-Language", "en"))
+            .body(format!(
+                "{}={}&username=alice&password=wrong",
+                CSRF_FIELD, token
+            ))
+            .dispatch();
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains("Usuário desconhecido ou senha incorreta"));
+    }
+
+    #[test]
+    fn escapes_the_search_query() {
+        let client = client();
+        let page = client
+            .get("/admin/ui/login")
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let response = client
+            .post("/admin/ui/login")
+            .header(ContentType::Form)
+            .body(format!(
+                "{}={}&username=alice&password=secret",
+                CSRF_FIELD,
+                csrf_token(&page)
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+
+        let page = client
+            .get("/admin/ui/no-matches?q=%3Cb%3Ex%3C%2Fb%3E")
+            .dispatch()
+            .into_string()
+            .unwrap();
+
+        assert!(page.contains("No supplies match &quot;&lt;b&gt;x&lt;/b&gt;&quot;."));
+        assert!(!page.contains("<b>x</b>"));
+    }
+}
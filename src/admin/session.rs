@@ -0,0 +1,200 @@
+use crate::application::audit::AuditContext;
+use crate::domain::tenant_id::TenantId;
+use crate::middler::request_id::RequestId;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::Argon2;
+use argon2::PasswordHash;
+use argon2::PasswordHasher;
+use argon2::PasswordVerifier;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use rocket::http::Cookie;
+use rocket::http::CookieJar;
+use rocket::http::SameSite;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::Request;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const SESSION_COOKIE: &str = "admin_session";
+
+/*
+[default.admin]
+session_minutes = 480
+
+[default.admin.users.alice]
+password = "$argon2id$v=19$m=19456,t=2,p=1$..."   # from `solution hash-password`
+tenant = "acme"
+*/
+
+/// An account of the admin UI. It manages the supplies of one tenant.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminUser {
+    /// Argon2 hash of the password, in the PHC string format.
+    pub password: String,
+    pub tenant: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub users: HashMap<String, AdminUser>,
+    #[serde(default = "default_session_minutes")]
+    pub session_minutes: i64,
+}
+
+fn default_session_minutes() -> i64 {
+    480
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            users: HashMap::new(),
+            session_minutes: default_session_minutes(),
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Rejects unreadable hashes and tenants, so a typo fails the launch
+    /// instead of every login.
+    pub fn validate(&self) -> Result<(), String> {
+        for (username, user) in &self.users {
+            PasswordHash::new(&user.password)
+                .map_err(|e| format!("the password of '{}' is not a hash: {}", username, e))?;
+            if !TenantId::is_valid(&user.tenant) {
+                return Err(format!(
+                    "'{}' of '{}' is not a valid tenant id",
+                    user.tenant, username
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The account of `username` when `password` matches its hash. Slow on
+    /// purpose, so call it off the async runtime.
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<&AdminUser> {
+        let user = self.users.get(username)?;
+        let hash = PasswordHash::new(&user.password).ok()?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .ok()
+            .map(|_| user)
+    }
+}
+
+/// An Argon2id hash of `password` with a random salt, for `[admin.users]`.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// What the private session cookie holds. Rocket encrypts and signs it with
+/// the `secret_key`, so clients can neither read nor forge it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Session {
+    user: String,
+    tenant: String,
+    expires_at: DateTime<Utc>,
+}
+
+pub fn start(cookies: &CookieJar<'_>, config: &AdminConfig, username: &str, user: &AdminUser) {
+    let session = Session {
+        user: username.to_string(),
+        tenant: user.tenant.to_owned(),
+        expires_at: Utc::now() + Duration::minutes(config.session_minutes),
+    };
+    let Ok(value) = serde_json::to_string(&session) else {
+        return;
+    };
+    let cookie = Cookie::build((SESSION_COOKIE, value))
+        .path("/admin/ui")
+        .http_only(true)
+        .same_site(SameSite::Lax);
+    cookies.add_private(cookie);
+}
+
+pub fn end(cookies: &CookieJar<'_>) {
+    cookies.remove_private(Cookie::build(SESSION_COOKIE).path("/admin/ui"));
+}
+
+/// The admin signed in, acting on the tenant of its account. Fails with 401
+/// without a live session, which the admin catcher turns into a redirect to
+/// the login page. Sessions of accounts removed from the config or moved to
+/// another tenant end at once.
+#[derive(Debug, Clone)]
+pub struct Admin {
+    pub user: String,
+    pub context: AuditContext,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let session = req
+            .cookies()
+            .get_private(SESSION_COOKIE)
+            .and_then(|cookie| serde_json::from_str::<Session>(cookie.value()).ok());
+        let live = session.filter(|session| {
+            session.expires_at > Utc::now()
+                && req
+                    .rocket()
+                    .state::<AdminConfig>()
+                    .and_then(|config| config.users.get(&session.user))
+                    .is_some_and(|user| user.tenant == session.tenant)
+        });
+        match live {
+            Some(session) => Outcome::Success(Admin {
+                context: AuditContext::new(
+                    &session.user,
+                    TenantId::from_str(&session.tenant),
+                    Some(RequestId::of(req).as_str()),
+                ),
+                user: session.user,
+            }),
+            None => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    #[test]
+    fn authenticates_against_argon2_hashes() {
+        let mut config = AdminConfig::default();
+        config.users.insert(
+            "alice".to_string(),
+            AdminUser {
+                password: hash_password("correct horse").unwrap(),
+                tenant: "acme".to_string(),
+            },
+        );
+        assert!(config.validate().is_ok());
+
+        assert_eq!(
+            config
+                .authenticate("alice", "correct horse")
+                .map(|user| user.tenant.as_str()),
+            Some("acme")
+        );
+        assert!(config.authenticate("alice", "wrong").is_none());
+        assert!(config.authenticate("bob", "correct horse").is_none());
+
+        config.users.get_mut("alice").unwrap().password = "correct horse".to_string();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use super::derivative::Derivative;
+use super::not_found;
+use super::Attachment;
+use super::AttachmentGateway;
+use super::ObjectStorage;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+use tracing::error;
+
+pub struct DeleteAttachmentUseCase {
+    gateway: Arc<dyn AttachmentGateway>,
+    storage: Arc<dyn ObjectStorage>,
+}
+
+impl DeleteAttachmentUseCase {
+    pub fn new(gateway: Arc<dyn AttachmentGateway>, storage: Arc<dyn ObjectStorage>) -> Self {
+        Self { gateway, storage }
+    }
+
+    /// Removes the metadata along with its audit entry, then the content and its derivatives. Files left
+    /// behind by a storage failure are only logged, as the attachment is
+    /// already gone.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        supply_id: &str,
+        id: &str,
+    ) -> Result<(), Notification> {
+        let current = self
+            .gateway
+            .find(supply_id, id)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supply_id, id)))?;
+
+        let entry = AuditEntry::new(
+            context,
+            AuditAction::Delete,
+            AGGREGATE_TYPE,
+            id,
+            Some(&current),
+            None::<&Attachment>,
+        );
+        self.gateway
+            .delete(id, &entry)
+            .await
+            .map_err(Notification::with_one_error)?;
+        let keys = std::iter::once(current.storage_key.clone()).chain(
+            Derivative::ALL
+                .iter()
+                .map(|derivative| derivative.storage_key(&current)),
+        );
+        for key in keys {
+            if let Err(e) = self.storage.delete(&key).await {
+                error!(error = %e, key = %key, "failed to remove attachment content");
+            }
+        }
+        Ok(())
+    }
+}
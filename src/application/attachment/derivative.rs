@@ -0,0 +1,199 @@
+use super::metadata::orientation;
+use super::Attachment;
+use super::AttachmentType;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use image::ImageFormat;
+use image::ImageReader;
+use image::Limits;
+use std::io::Cursor;
+
+/// Larger photos are refused rather than decoded.
+const MAX_DIMENSION: u32 = 10_000;
+
+const JPEG_QUALITY: u8 = 82;
+
+/// A resized copy of a photo, served in place of the original in listings.
+/// Derivatives are encoded from the decoded pixels only, so the EXIF, XMP
+/// and ICC metadata of the original is never copied into them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Derivative {
+    ThumbnailJpeg,
+    ThumbnailWebp,
+    DisplayJpeg,
+    DisplayWebp,
+}
+
+impl Derivative {
+    pub const ALL: [Derivative; 4] = [
+        Derivative::ThumbnailJpeg,
+        Derivative::ThumbnailWebp,
+        Derivative::DisplayJpeg,
+        Derivative::DisplayWebp,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|derivative| derivative.name() == name)
+    }
+
+    /// The name derivatives are requested by, e.g. `thumbnail.webp`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ThumbnailJpeg => "thumbnail.jpg",
+            Self::ThumbnailWebp => "thumbnail.webp",
+            Self::DisplayJpeg => "display.jpg",
+            Self::DisplayWebp => "display.webp",
+        }
+    }
+
+    /// The longest side in pixels. Smaller photos keep their size.
+    pub fn max_dimension(&self) -> u32 {
+        match self {
+            Self::ThumbnailJpeg | Self::ThumbnailWebp => 320,
+            Self::DisplayJpeg | Self::DisplayWebp => 1280,
+        }
+    }
+
+    pub fn attachment_type(&self) -> AttachmentType {
+        match self {
+            Self::ThumbnailJpeg | Self::DisplayJpeg => AttachmentType::Jpeg,
+            Self::ThumbnailWebp | Self::DisplayWebp => AttachmentType::Webp,
+        }
+    }
+
+    /// Stored next to the original, so they share its tenant prefix.
+    pub fn storage_key(&self, attachment: &Attachment) -> String {
+        format!("{}.{}", attachment.storage_key, self.name())
+    }
+}
+
+/// Decodes a photo, turns it upright as its EXIF orientation says, and
+/// encodes every derivative of it.
+pub fn render(
+    attachment_type: AttachmentType,
+    content: &[u8],
+) -> Result<Vec<(Derivative, Vec<u8>)>, String> {
+    let format = match attachment_type {
+        AttachmentType::Jpeg => ImageFormat::Jpeg,
+        AttachmentType::Png => ImageFormat::Png,
+        AttachmentType::Webp => ImageFormat::WebP,
+        AttachmentType::Pdf => return Err("a PDF has no image derivatives".to_string()),
+    };
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    let mut reader = ImageReader::with_format(Cursor::new(content), format);
+    reader.limits(limits);
+    let mut image = reader
+        .decode()
+        .map_err(|e| format!("failed to decode the image: {}", e))?;
+    if let Some(orientation) = orientation(content) {
+        image.apply_orientation(orientation);
+    }
+
+    let mut rendered = Vec::with_capacity(Derivative::ALL.len());
+    for derivative in Derivative::ALL {
+        let resized = resize(&image, derivative.max_dimension());
+        rendered.push((derivative, encode(&resized, derivative)?));
+    }
+    Ok(rendered)
+}
+
+fn resize(image: &DynamicImage, max_dimension: u32) -> DynamicImage {
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return image.clone();
+    }
+    image.resize(max_dimension, max_dimension, FilterType::Lanczos3)
+}
+
+/// JPEG has no alpha channel, so transparent pixels are flattened; WebP keeps
+/// them. The `image` crate only encodes lossless WebP.
+fn encode(image: &DynamicImage, derivative: Derivative) -> Result<Vec<u8>, String> {
+    let mut encoded = Vec::new();
+    let result = match derivative.attachment_type() {
+        AttachmentType::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY)),
+        _ if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut encoded)),
+        _ => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut encoded)),
+    };
+    result.map_err(|e| format!("failed to encode {}: {}", derivative.name(), e))?;
+    Ok(encoded)
+}
+
+#[cfg(test)]
+pub(crate) mod derivative_tests {
+    use super::*;
+    use image::metadata::Orientation;
+    use image::GenericImageView;
+    use image::RgbImage;
+
+    /// A 400x200 JPEG whose EXIF says to rotate it 90° clockwise and which
+    /// carries a GPS tag.
+    pub(crate) fn rotated_photo() -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_fn(400, 200, |x, _| {
+            image::Rgb([(x % 256) as u8, 80, 160])
+        }))
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 90))
+        .unwrap();
+
+        // A little endian TIFF with Orientation = 6 and a GPSInfo pointer.
+        let mut tiff = b"II*\0\x08\0\0\0\x02\0".to_vec();
+        tiff.extend_from_slice(b"\x12\x01\x03\0\x01\0\0\0\x06\0\0\0");
+        tiff.extend_from_slice(b"\x25\x88\x04\0\x01\0\0\0\x26\0\0\0");
+        tiff.extend_from_slice(b"\0\0\0\0");
+        tiff.extend_from_slice(b"\0\0\0\0\0\0");
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&tiff);
+
+        let mut photo = jpeg[..2].to_vec();
+        photo.extend_from_slice(b"\xff\xe1");
+        photo.extend_from_slice(&((app1.len() + 2) as u16).to_be_bytes());
+        photo.extend_from_slice(&app1);
+        photo.extend_from_slice(&jpeg[2..]);
+        photo
+    }
+
+    #[test]
+    fn renders_upright_derivatives_without_metadata() {
+        let photo = rotated_photo();
+        assert_eq!(orientation(&photo), Some(Orientation::Rotate90));
+
+        let rendered = render(AttachmentType::Jpeg, &photo).unwrap();
+        assert_eq!(
+            rendered.iter().map(|(d, _)| *d).collect::<Vec<_>>(),
+            Derivative::ALL
+        );
+        for (derivative, bytes) in rendered {
+            assert_eq!(
+                AttachmentType::sniff(&bytes),
+                Some(derivative.attachment_type())
+            );
+            assert!(!bytes.windows(6).any(|window| window == b"Exif\0\0"));
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            let expected = match derivative.max_dimension() {
+                320 => (160, 320),
+                _ => (200, 400),
+            };
+            assert_eq!(decoded.dimensions(), expected, "{}", derivative.name());
+        }
+    }
+
+    #[test]
+    fn refuses_content_that_is_not_an_image() {
+        assert!(render(AttachmentType::Png, b"\x89PNG\r\n\x1a\n").is_err());
+        assert!(render(AttachmentType::Pdf, b"%PDF-1.7").is_err());
+        assert_eq!(
+            Derivative::parse("thumbnail.webp"),
+            Some(Derivative::ThumbnailWebp)
+        );
+        assert_eq!(Derivative::parse("original.png"), None);
+    }
+}
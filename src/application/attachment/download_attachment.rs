@@ -0,0 +1,105 @@
+use super::checksum;
+use super::not_found;
+use super::Attachment;
+use super::AttachmentGateway;
+use super::ObjectStorage;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct DownloadAttachmentUseCase {
+    gateway: Arc<dyn AttachmentGateway>,
+    storage: Arc<dyn ObjectStorage>,
+}
+
+impl DownloadAttachmentUseCase {
+    pub fn new(gateway: Arc<dyn AttachmentGateway>, storage: Arc<dyn ObjectStorage>) -> Self {
+        Self { gateway, storage }
+    }
+
+    /// The attachment and its content, which must still match the checksum
+    /// taken on upload.
+    pub async fn execute(
+        &self,
+        supply_id: &str,
+        id: &str,
+    ) -> Result<(Attachment, Vec<u8>), Notification> {
+        let attachment = self
+            .gateway
+            .find(supply_id, id)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supply_id, id)))?;
+
+        let content = self
+            .storage
+            .get(&attachment.storage_key)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| {
+                Notification::with_one_error(CustomError::RepositoryError(format!(
+                    "the content of attachment '{}' is missing from the storage",
+                    id
+                )))
+            })?;
+        if checksum(&content) != attachment.checksum {
+            return Err(Notification::with_one_error(CustomError::RepositoryError(
+                format!(
+                    "the content of attachment '{}' does not match its checksum",
+                    id
+                ),
+            )));
+        }
+
+        Ok((attachment, content))
+    }
+}
+
+#[cfg(test)]
+mod download_attachment_tests {
+    use super::*;
+    use crate::application::testing::InMemoryAttachmentGateway;
+    use crate::application::testing::InMemoryObjectStorage;
+    use chrono::Utc;
+
+    #[rocket::async_test]
+    async fn refuses_content_that_changed_since_the_upload() {
+        let content = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let attachment = Attachment {
+            id: "a1".to_string(),
+            supply_id: "s1".to_string(),
+            file_name: "cimento.png".to_string(),
+            content_type: "image/png".to_string(),
+            size: content.len() as i64,
+            checksum: checksum(&content),
+            storage_key: "acme/attachments/a1".to_string(),
+            created_at: Utc::now(),
+        };
+        let gateway = Arc::new(InMemoryAttachmentGateway::with(vec![attachment.clone()]));
+        let storage = Arc::new(InMemoryObjectStorage::default());
+        storage
+            .put(&attachment.storage_key, &content, "image/png")
+            .await
+            .unwrap();
+        let use_case = DownloadAttachmentUseCase::new(gateway, storage.clone());
+
+        let (found, bytes) = use_case.execute("s1", "a1").await.unwrap();
+        assert_eq!((found, bytes), (attachment.clone(), content));
+
+        let error = use_case.execute("s2", "a1").await.unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["NotFound: attachment with id 'a1' was not found for supply 's2'"]
+        );
+
+        storage
+            .put(&attachment.storage_key, b"\x89PNG\r\n\x1a\n", "image/png")
+            .await
+            .unwrap();
+        let error = use_case.execute("s1", "a1").await.unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["RepositoryError: the content of attachment 'a1' does not match its checksum"]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use super::derivative::Derivative;
+use super::not_found;
+use super::Attachment;
+use super::AttachmentGateway;
+use super::AttachmentType;
+use super::ObjectStorage;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use std::sync::Arc;
+
+pub struct DownloadDerivativeUseCase {
+    gateway: Arc<dyn AttachmentGateway>,
+    storage: Arc<dyn ObjectStorage>,
+}
+
+impl DownloadDerivativeUseCase {
+    pub fn new(gateway: Arc<dyn AttachmentGateway>, storage: Arc<dyn ObjectStorage>) -> Self {
+        Self { gateway, storage }
+    }
+
+    /// A derivative of a photo by its name. Derivatives are rendered after
+    /// the upload, so a missing one may just not be ready yet.
+    pub async fn execute(
+        &self,
+        supply_id: &str,
+        id: &str,
+        name: &str,
+    ) -> Result<(Attachment, Derivative, Vec<u8>), Notification> {
+        let attachment = self
+            .gateway
+            .find(supply_id, id)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supply_id, id)))?;
+        let derivative = Derivative::parse(name)
+            .filter(|_| {
+                AttachmentType::from_content_type(&attachment.content_type)
+                    .is_some_and(|attachment_type| attachment_type.is_image())
+            })
+            .ok_or_else(|| {
+                Notification::with_one_error(CustomError::NotFound(
+                    Message::new("derivative-not-found")
+                        .arg("id", id)
+                        .arg("name", name),
+                ))
+            })?;
+
+        let content = self
+            .storage
+            .get(&derivative.storage_key(&attachment))
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| {
+                Notification::with_one_error(CustomError::NotFound(
+                    Message::new("derivative-not-ready")
+                        .arg("id", id)
+                        .arg("name", name),
+                ))
+            })?;
+        Ok((attachment, derivative, content))
+    }
+}
+
+#[cfg(test)]
+mod download_derivative_tests {
+    use super::*;
+    use crate::application::attachment::checksum;
+    use crate::application::attachment::derivative::derivative_tests::rotated_photo;
+    use crate::application::attachment::generate_derivatives::GenerateDerivativesUseCase;
+    use crate::application::testing::InMemoryAttachmentGateway;
+    use crate::application::testing::InMemoryObjectStorage;
+    use chrono::Utc;
+
+    fn attachment(id: &str, content_type: &str, content: &[u8]) -> Attachment {
+        Attachment {
+            id: id.to_string(),
+            supply_id: "s1".to_string(),
+            file_name: format!("{}.bin", id),
+            content_type: content_type.to_string(),
+            size: content.len() as i64,
+            checksum: checksum(content),
+            storage_key: format!("acme/attachments/{}", id),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn serves_derivatives_once_they_are_generated() {
+        let photo = rotated_photo();
+        let image = attachment("a1", "image/jpeg", &photo);
+        let datasheet = attachment("a2", "application/pdf", b"%PDF-1.7");
+        let gateway = Arc::new(InMemoryAttachmentGateway::with(vec![
+            image.clone(),
+            datasheet.clone(),
+        ]));
+        let storage = Arc::new(InMemoryObjectStorage::default());
+        let use_case = DownloadDerivativeUseCase::new(gateway, storage.clone());
+
+        let error = use_case
+            .execute("s1", "a1", "thumbnail.webp")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["NotFound: derivative 'thumbnail.webp' of attachment 'a1' is not ready yet"]
+        );
+
+        let generate = GenerateDerivativesUseCase::new(storage.clone());
+        assert_eq!(generate.execute(&image, photo).await.unwrap(), 4);
+        assert_eq!(
+            generate
+                .execute(&datasheet, b"%PDF-1.7".to_vec())
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(storage.len(), 4);
+
+        let (_, derivative, content) = use_case
+            .execute("s1", "a1", "thumbnail.webp")
+            .await
+            .unwrap();
+        assert_eq!(derivative, Derivative::ThumbnailWebp);
+        assert_eq!(AttachmentType::sniff(&content), Some(AttachmentType::Webp));
+
+        let error = use_case
+            .execute("s1", "a2", "thumbnail.webp")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["NotFound: attachment 'a2' has no derivative 'thumbnail.webp'"]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use super::derivative;
+use super::Attachment;
+use super::AttachmentType;
+use super::ObjectStorage;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use rocket::tokio::task;
+use std::sync::Arc;
+
+pub struct GenerateDerivativesUseCase {
+    storage: Arc<dyn ObjectStorage>,
+}
+
+impl GenerateDerivativesUseCase {
+    pub fn new(storage: Arc<dyn ObjectStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Renders the derivatives of a photo off the async runtime and stores
+    /// them next to it. Datasheets have none, so nothing is stored for them.
+    pub async fn execute(
+        &self,
+        attachment: &Attachment,
+        content: Vec<u8>,
+    ) -> Result<usize, Notification> {
+        let attachment_type = match AttachmentType::from_content_type(&attachment.content_type) {
+            Some(attachment_type) if attachment_type.is_image() => attachment_type,
+            _ => return Ok(0),
+        };
+
+        let rendered = task::spawn_blocking(move || derivative::render(attachment_type, &content))
+            .await
+            .map_err(|e| {
+                Notification::with_one_error(CustomError::Error(format!(
+                    "rendering the derivatives of attachment '{}' stopped: {}",
+                    attachment.id, e
+                )))
+            })?
+            .map_err(|e| Notification::with_one_error(CustomError::Error(e)))?;
+
+        for (derivative, bytes) in &rendered {
+            self.storage
+                .put(
+                    &derivative.storage_key(attachment),
+                    bytes,
+                    derivative.attachment_type().content_type(),
+                )
+                .await
+                .map_err(Notification::with_one_error)?;
+        }
+        Ok(rendered.len())
+    }
+}
@@ -0,0 +1,21 @@
+use super::Attachment;
+use super::AttachmentGateway;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct ListAttachmentsUseCase {
+    gateway: Arc<dyn AttachmentGateway>,
+}
+
+impl ListAttachmentsUseCase {
+    pub fn new(gateway: Arc<dyn AttachmentGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(&self, supply_id: &str) -> Result<Vec<Attachment>, Notification> {
+        self.gateway
+            .find_by_supply(supply_id)
+            .await
+            .map_err(Notification::with_one_error)
+    }
+}
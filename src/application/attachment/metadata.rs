@@ -0,0 +1,217 @@
+use super::AttachmentType;
+use exif::In;
+use exif::Tag;
+use image::metadata::Orientation;
+use std::io::Cursor;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// PNG chunks that describe the photo rather than its pixels.
+const PNG_METADATA: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Flags of the WebP VP8X header telling EXIF and XMP chunks follow.
+const WEBP_EXIF_FLAG: u8 = 0x08;
+const WEBP_XMP_FLAG: u8 = 0x04;
+
+/// Removes the EXIF, XMP, IPTC and text metadata of a photo, where cameras
+/// and phones leave GPS coordinates, serial numbers and owner names. The
+/// pixels and ICC profile are copied untouched; the EXIF orientation is kept
+/// in an EXIF block of its own, so the photo still shows upright. `None` when
+/// the file is malformed.
+pub fn strip(attachment_type: AttachmentType, content: &[u8]) -> Option<Vec<u8>> {
+    let orientation = orientation(content).filter(|o| *o != Orientation::NoTransforms);
+    let tiff = orientation.map(|o| orientation_tiff(o.to_exif()));
+    match attachment_type {
+        AttachmentType::Jpeg => strip_jpeg(content, tiff.as_deref()),
+        AttachmentType::Png => strip_png(content, tiff.as_deref()),
+        AttachmentType::Webp => strip_webp(content, tiff.as_deref()),
+        AttachmentType::Pdf => Some(content.to_vec()),
+    }
+}
+
+/// The EXIF orientation of the photo, `None` when it has no EXIF or the tag
+/// holds an unknown value.
+pub fn orientation(content: &[u8]) -> Option<Orientation> {
+    let exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(content))
+        .ok()?;
+    let value = exif
+        .get_field(Tag::Orientation, In::PRIMARY)?
+        .value
+        .get_uint(0)?;
+    Orientation::from_exif(u8::try_from(value).ok()?)
+}
+
+/// A big endian TIFF holding a single IFD with the Orientation tag.
+fn orientation_tiff(orientation: u8) -> Vec<u8> {
+    let mut tiff = b"MM\0*\0\0\0\x08\0\x01".to_vec();
+    tiff.extend_from_slice(b"\x01\x12\0\x03\0\0\0\x01");
+    tiff.extend_from_slice(&[0, orientation, 0, 0]);
+    tiff.extend_from_slice(b"\0\0\0\0");
+    tiff
+}
+
+/// Keeps every segment but APP1 and APP3 to APP15, where EXIF, XMP and IPTC
+/// live, and comments. Everything from the start of scan on is image data.
+fn strip_jpeg(content: &[u8], tiff: Option<&[u8]>) -> Option<Vec<u8>> {
+    if !content.starts_with(b"\xff\xd8") {
+        return None;
+    }
+    let mut stripped = content[..2].to_vec();
+    if let Some(tiff) = tiff {
+        let length = u16::try_from(tiff.len() + 8).ok()?;
+        stripped.extend_from_slice(b"\xff\xe1");
+        stripped.extend_from_slice(&length.to_be_bytes());
+        stripped.extend_from_slice(b"Exif\0\0");
+        stripped.extend_from_slice(tiff);
+    }
+
+    let mut position = 2;
+    loop {
+        if *content.get(position)? != 0xff {
+            return None;
+        }
+        let marker = *content.get(position + 1)?;
+        if marker == 0xff {
+            position += 1;
+            continue;
+        }
+        if marker == 0xda {
+            stripped.extend_from_slice(&content[position..]);
+            return Some(stripped);
+        }
+        if marker == 0x01 || (0xd0..=0xd7).contains(&marker) {
+            stripped.extend_from_slice(&content[position..position + 2]);
+            position += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([*content.get(position + 2)?, *content.get(position + 3)?]);
+        let end = position + 2 + length as usize;
+        let segment = content.get(position..end)?;
+        let metadata = marker == 0xfe || (0xe1..=0xef).contains(&marker) && marker != 0xe2;
+        if !metadata {
+            stripped.extend_from_slice(segment);
+        }
+        position = end;
+    }
+}
+
+fn strip_png(content: &[u8], tiff: Option<&[u8]>) -> Option<Vec<u8>> {
+    let mut chunks = content.strip_prefix(PNG_SIGNATURE)?;
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    while !chunks.is_empty() {
+        let length = u32::from_be_bytes(chunks.get(..4)?.try_into().ok()?) as usize;
+        let chunk = chunks.get(..12 + length)?;
+        let kind = &chunk[4..8];
+        if !PNG_METADATA.iter().any(|metadata| metadata[..] == *kind) {
+            stripped.extend_from_slice(chunk);
+        }
+        // The EXIF chunk must come before the image data
+        if let (b"IHDR", Some(tiff)) = (kind, tiff) {
+            let mut exif = b"eXIf".to_vec();
+            exif.extend_from_slice(tiff);
+            stripped.extend_from_slice(&u32::try_from(tiff.len()).ok()?.to_be_bytes());
+            stripped.extend_from_slice(&exif);
+            stripped.extend_from_slice(&crc32fast::hash(&exif).to_be_bytes());
+        }
+        chunks = &chunks[12 + length..];
+    }
+    Some(stripped)
+}
+
+/// Only the extended format, whose VP8X header flags them, carries metadata.
+fn strip_webp(content: &[u8], tiff: Option<&[u8]>) -> Option<Vec<u8>> {
+    if content.get(..4)? != b"RIFF" || content.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let mut chunks = &content[12..];
+    let mut stripped = content[..12].to_vec();
+    let mut extended = false;
+    while !chunks.is_empty() {
+        let length = u32::from_le_bytes(chunks.get(4..8)?.try_into().ok()?) as usize;
+        let padded = (8 + length + 1) & !1;
+        let chunk = chunks.get(..padded.min(chunks.len()))?;
+        match &chunk[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                extended = true;
+                let flags = stripped.len() + 8;
+                stripped.extend_from_slice(chunk);
+                *stripped.get_mut(flags)? &= !(WEBP_EXIF_FLAG | WEBP_XMP_FLAG);
+                if tiff.is_some() {
+                    stripped[flags] |= WEBP_EXIF_FLAG;
+                }
+            }
+            _ => stripped.extend_from_slice(chunk),
+        }
+        chunks = &chunks[chunk.len()..];
+    }
+    if let (true, Some(tiff)) = (extended, tiff) {
+        stripped.extend_from_slice(b"EXIF");
+        stripped.extend_from_slice(&u32::try_from(tiff.len()).ok()?.to_le_bytes());
+        stripped.extend_from_slice(tiff);
+        if tiff.len() % 2 == 1 {
+            stripped.push(0);
+        }
+    }
+    let size = u32::try_from(stripped.len() - 8).ok()?;
+    stripped[4..8].copy_from_slice(&size.to_le_bytes());
+    Some(stripped)
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+    use crate::application::attachment::derivative::derivative_tests::rotated_photo;
+    use image::codecs::png::PngEncoder;
+    use image::DynamicImage;
+    use image::RgbImage;
+
+    /// The GPSInfo tag of the little endian TIFF in `rotated_photo`.
+    const GPS_POINTER: &[u8] = b"\x25\x88\x04\0";
+
+    fn tags(content: &[u8]) -> Vec<Tag> {
+        exif::Reader::new()
+            .read_from_container(&mut Cursor::new(content))
+            .map(|exif| exif.fields().map(|field| field.tag).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn keeps_only_the_orientation_of_a_jpeg() {
+        let photo = rotated_photo();
+        assert!(photo.windows(4).any(|window| window == GPS_POINTER));
+
+        let stripped = strip(AttachmentType::Jpeg, &photo).unwrap();
+
+        assert!(!stripped.windows(4).any(|window| window == GPS_POINTER));
+        assert_eq!(tags(&stripped), vec![Tag::Orientation]);
+        assert_eq!(orientation(&stripped), Some(Orientation::Rotate90));
+        assert_eq!(strip(AttachmentType::Jpeg, &stripped).unwrap(), stripped);
+        let decoded = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (400, 200));
+        assert!(strip(AttachmentType::Jpeg, &photo[..200]).is_none());
+    }
+
+    #[test]
+    fn removes_text_chunks_from_a_png() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(4, 4))
+            .write_with_encoder(PngEncoder::new(&mut png))
+            .unwrap();
+        let mut text = b"tEXtGPS\x000.0,0.0".to_vec();
+        let crc = crc32fast::hash(&text);
+        let mut chunk = ((text.len() - 4) as u32).to_be_bytes().to_vec();
+        chunk.append(&mut text);
+        chunk.extend_from_slice(&crc.to_be_bytes());
+        // Right after the IHDR chunk, which is 25 bytes long
+        let mut photo = png[..33].to_vec();
+        photo.extend_from_slice(&chunk);
+        photo.extend_from_slice(&png[33..]);
+
+        let stripped = strip(AttachmentType::Png, &photo).unwrap();
+
+        assert_eq!(stripped, png);
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+pub mod delete_attachment;
+pub mod derivative;
+pub mod download_attachment;
+pub mod download_derivative;
+pub mod generate_derivatives;
+pub mod list_attachments;
+pub mod metadata;
+pub mod upload_attachment;
+
+use crate::application::audit::AuditEntry;
+use crate::domain::audit::Audit;
+use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use crate::i18n::Message;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use utoipa::ToSchema;
+
+pub const AGGREGATE_TYPE: &str = "supply_attachment";
+
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// The kinds of file a supply accepts: datasheets and product photos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentType {
+    Pdf,
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl AttachmentType {
+    /// Recognizes the type from the first bytes of the content, whatever the
+    /// client says it is.
+    pub fn sniff(content: &[u8]) -> Option<Self> {
+        match infer::get(content)?.mime_type() {
+            "application/pdf" => Some(Self::Pdf),
+            "image/jpeg" => Some(Self::Jpeg),
+            "image/png" => Some(Self::Png),
+            "image/webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// The type of a stored attachment, from the content type it was saved
+    /// with.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        [Self::Pdf, Self::Jpeg, Self::Png, Self::Webp]
+            .into_iter()
+            .find(|attachment_type| attachment_type.content_type() == content_type)
+    }
+
+    /// Photos get derivatives; datasheets don't.
+    pub fn is_image(&self) -> bool {
+        !matches!(self, Self::Pdf)
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Pdf => "application/pdf",
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// A file attached to a supply. The content lives in the `ObjectStorage`
+/// under `storage_key`; Postgres keeps the rest.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Attachment {
+    pub id: String,
+    pub supply_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    /// Size of the content in bytes.
+    pub size: i64,
+    /// Hex encoded SHA-256 of the content.
+    pub checksum: String,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    /// Keys are namespaced by tenant, so tenants can share a bucket.
+    pub fn storage_key(tenant_id: &TenantId, id: &str) -> String {
+        format!("{}/attachments/{}", tenant_id.get_value(), id)
+    }
+}
+
+pub fn checksum(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/// The file name sent by the client without its directories, or one made
+/// from the id when it has none.
+pub fn file_name(
+    name: Option<&str>,
+    id: &str,
+    attachment_type: AttachmentType,
+) -> Result<String, CustomError> {
+    let name = name
+        .and_then(|name| name.rsplit(['/', '\\']).next())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .collect::<String>();
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Ok(format!("{}.{}", id, attachment_type.extension()));
+    }
+    if name.chars().count() > MAX_FILE_NAME_LENGTH {
+        return Err(CustomError::Validation(
+            Message::new("file-name-too-long")
+                .arg("max", MAX_FILE_NAME_LENGTH)
+                .for_field("name"),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+pub fn not_found(supply_id: &str, id: &str) -> CustomError {
+    CustomError::NotFound(
+        Message::new("attachment-not-found")
+            .arg("id", id)
+            .arg("supply_id", supply_id),
+    )
+}
+
+/// Attachment metadata of a single tenant. Creating and deleting write the
+/// audit entry in the same transaction.
+#[async_trait]
+pub trait AttachmentGateway: Send + Sync {
+    async fn create(
+        &self,
+        attachment: &Attachment,
+        audit: Audit<'_, Attachment>,
+    ) -> Result<Attachment, CustomError>;
+    async fn find(&self, supply_id: &str, id: &str) -> Result<Option<Attachment>, CustomError>;
+    /// Attachments of the supply, oldest first.
+    async fn find_by_supply(&self, supply_id: &str) -> Result<Vec<Attachment>, CustomError>;
+    async fn delete(&self, id: &str, audit: &AuditEntry) -> Result<(), CustomError>;
+}
+
+/// Where attachment contents are kept, by key.
+#[async_trait]
+pub trait ObjectStorage: Send + Sync {
+    async fn put(&self, key: &str, content: &[u8], content_type: &str) -> Result<(), CustomError>;
+    /// `None` when nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CustomError>;
+    /// Succeeds when nothing is stored under `key`.
+    async fn delete(&self, key: &str) -> Result<(), CustomError>;
+}
+
+#[cfg(test)]
+mod attachment_tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_the_accepted_types_from_their_content() {
+        assert_eq!(
+            AttachmentType::sniff(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n"),
+            Some(AttachmentType::Pdf)
+        );
+        assert_eq!(
+            AttachmentType::sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some(AttachmentType::Png)
+        );
+        assert_eq!(
+            AttachmentType::sniff(b"\xff\xd8\xff\xe0\0\x10JFIF\0"),
+            Some(AttachmentType::Jpeg)
+        );
+        assert_eq!(
+            AttachmentType::sniff(b"RIFF\x24\0\0\0WEBPVP8 "),
+            Some(AttachmentType::Webp)
+        );
+        assert_eq!(AttachmentType::sniff(b"GIF89a\x01\0\x01\0"), None);
+        assert_eq!(AttachmentType::sniff(b"name,unit,price\n"), None);
+    }
+
+    #[test]
+    fn keeps_only_the_base_name_of_files() {
+        let id = "4f1c";
+        assert_eq!(
+            file_name(Some("C:\\fotos\\cimento.jpg"), id, AttachmentType::Jpeg).unwrap(),
+            "cimento.jpg"
+        );
+        assert_eq!(
+            file_name(Some("../../etc/\"ficha\".pdf"), id, AttachmentType::Pdf).unwrap(),
+            "ficha.pdf"
+        );
+        assert_eq!(
+            file_name(Some("dir/"), id, AttachmentType::Png).unwrap(),
+            "4f1c.png"
+        );
+        assert_eq!(
+            file_name(None, id, AttachmentType::Pdf).unwrap(),
+            "4f1c.pdf"
+        );
+        assert!(file_name(Some(&"a".repeat(256)), id, AttachmentType::Pdf).is_err());
+    }
+}
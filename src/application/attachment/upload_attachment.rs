@@ -0,0 +1,347 @@
+use super::checksum;
+use super::file_name;
+use super::metadata;
+use super::Attachment;
+use super::AttachmentGateway;
+use super::AttachmentType;
+use super::ObjectStorage;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::application::supply::get_supply::not_found;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use chrono::Utc;
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+/// Content types clients send when they don't know the type of a file.
+const UNTYPED: [&str; 2] = ["application/octet-stream", "binary/octet-stream"];
+
+pub struct UploadAttachmentInput {
+    pub name: Option<String>,
+    /// The `Content-Type` the file was sent with, without parameters.
+    pub content_type: Option<String>,
+    pub content: Vec<u8>,
+}
+
+pub struct UploadAttachmentUseCase {
+    supplies: Arc<dyn SupplyGateway>,
+    gateway: Arc<dyn AttachmentGateway>,
+    storage: Arc<dyn ObjectStorage>,
+}
+
+impl UploadAttachmentUseCase {
+    pub fn new(
+        supplies: Arc<dyn SupplyGateway>,
+        gateway: Arc<dyn AttachmentGateway>,
+        storage: Arc<dyn ObjectStorage>,
+    ) -> Self {
+        Self {
+            supplies,
+            gateway,
+            storage,
+        }
+    }
+
+    /// Stores the content, then its metadata and audit entry. The content is
+    /// removed again when they can't be saved. Photos are stored without
+    /// their EXIF and other metadata, which may locate the supplier.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        supply_id: &str,
+        input: &UploadAttachmentInput,
+    ) -> Result<Attachment, Notification> {
+        self.supplies
+            .find_by_id(&SupplyId::from_str(supply_id))
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supply_id)))?;
+
+        let attachment_type = sniff(input)?;
+        let content = if attachment_type.is_image() {
+            metadata::strip(attachment_type, &input.content).ok_or_else(|| {
+                Notification::with_one_error(CustomError::Validation(
+                    Message::new("file-malformed")
+                        .arg("type", attachment_type.extension())
+                        .for_field("file"),
+                ))
+            })?
+        } else {
+            input.content.clone()
+        };
+        let id = Uuid::new_v4().to_string();
+        let attachment = Attachment {
+            file_name: file_name(input.name.as_deref(), &id, attachment_type)
+                .map_err(Notification::with_one_error)?,
+            storage_key: Attachment::storage_key(&context.tenant_id, &id),
+            supply_id: supply_id.to_string(),
+            content_type: attachment_type.content_type().to_string(),
+            size: content.len() as i64,
+            checksum: checksum(&content),
+            created_at: Utc::now(),
+            id,
+        };
+
+        self.storage
+            .put(&attachment.storage_key, &content, &attachment.content_type)
+            .await
+            .map_err(Notification::with_one_error)?;
+        let audit = |created: &Attachment| {
+            AuditEntry::new(
+                context,
+                AuditAction::Create,
+                AGGREGATE_TYPE,
+                &created.id,
+                None::<&Attachment>,
+                Some(created),
+            )
+        };
+        match self.gateway.create(&attachment, &audit).await {
+            Ok(saved) => Ok(saved),
+            Err(e) => {
+                if let Err(cleanup) = self.storage.delete(&attachment.storage_key).await {
+                    error!(error = %cleanup, key = %attachment.storage_key, "failed to remove unsaved attachment");
+                }
+                Err(Notification::with_one_error(e))
+            }
+        }
+    }
+}
+
+fn sniff(input: &UploadAttachmentInput) -> Result<AttachmentType, Notification> {
+    let invalid = |message: Message| {
+        Notification::with_one_error(CustomError::Validation(message.for_field("file")))
+    };
+    if input.content.is_empty() {
+        return Err(invalid(Message::new("file-empty")));
+    }
+    let attachment_type = AttachmentType::sniff(&input.content)
+        .ok_or_else(|| invalid(Message::new("file-type-unsupported")))?;
+    match input.content_type.as_deref() {
+        Some(declared)
+            if !UNTYPED.contains(&declared) && declared != attachment_type.content_type() =>
+        {
+            Err(invalid(
+                Message::new("file-type-mismatch")
+                    .arg("detected", attachment_type.content_type())
+                    .arg("declared", declared),
+            ))
+        }
+        _ => Ok(attachment_type),
+    }
+}
+
+#[cfg(test)]
+mod upload_attachment_tests {
+    use super::*;
+    use crate::application::attachment::derivative::derivative_tests::rotated_photo;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::supply::SupplyOutput;
+    use crate::application::testing::InMemoryAttachmentGateway;
+    use crate::application::testing::InMemoryAuditGateway;
+    use crate::application::testing::InMemoryObjectStorage;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::supply::price::Price;
+    use crate::domain::supply::Supply;
+    use crate::domain::tenant_id::TenantId;
+
+    const PDF: &[u8] = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj\n";
+
+    struct Fixture {
+        supply_id: String,
+        gateway: Arc<InMemoryAttachmentGateway>,
+        storage: Arc<InMemoryObjectStorage>,
+        audit: Arc<InMemoryAuditGateway>,
+        use_case: UploadAttachmentUseCase,
+    }
+
+    fn fixture() -> Fixture {
+        let supply = Supply::new(
+            TenantId::from_str("acme"),
+            "cimento",
+            vec![Price::new("sc", 3290)],
+        )
+        .unwrap();
+        let supply_id = SupplyOutput::from(&supply).id;
+        let gateway = Arc::new(InMemoryAttachmentGateway::default());
+        let storage = Arc::new(InMemoryObjectStorage::default());
+        let audit = gateway.audit();
+        let use_case = UploadAttachmentUseCase::new(
+            Arc::new(InMemorySupplyGateway::with(vec![supply])),
+            gateway.clone(),
+            storage.clone(),
+        );
+        Fixture {
+            supply_id,
+            gateway,
+            storage,
+            audit,
+            use_case,
+        }
+    }
+
+    fn context() -> AuditContext {
+        AuditContext::new("alice", TenantId::from_str("acme"), None)
+    }
+
+    #[rocket::async_test]
+    async fn stores_the_content_and_its_metadata() {
+        let fixture = fixture();
+
+        let attachment = fixture
+            .use_case
+            .execute(
+                &context(),
+                &fixture.supply_id,
+                &UploadAttachmentInput {
+                    name: Some("ficha técnica.pdf".to_string()),
+                    content_type: Some("application/octet-stream".to_string()),
+                    content: PDF.to_vec(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(attachment.file_name, "ficha técnica.pdf");
+        assert_eq!(attachment.content_type, "application/pdf");
+        assert_eq!(attachment.size, PDF.len() as i64);
+        assert_eq!(attachment.checksum, checksum(PDF));
+        assert_eq!(
+            attachment.storage_key,
+            format!("acme/attachments/{}", attachment.id)
+        );
+        assert_eq!(
+            fixture.storage.get(&attachment.storage_key).await.unwrap(),
+            Some(PDF.to_vec())
+        );
+        assert_eq!(
+            fixture
+                .gateway
+                .find(&fixture.supply_id, &attachment.id)
+                .await
+                .unwrap(),
+            Some(attachment)
+        );
+        let entries = fixture.audit.find(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries[0].aggregate_type, AGGREGATE_TYPE);
+        assert!(entries[0].after.as_ref().unwrap()["storage_key"].is_null());
+    }
+
+    #[rocket::async_test]
+    async fn stores_photos_without_their_exif() {
+        let fixture = fixture();
+        let photo = rotated_photo();
+
+        let attachment = fixture
+            .use_case
+            .execute(
+                &context(),
+                &fixture.supply_id,
+                &UploadAttachmentInput {
+                    name: Some("cimento.jpg".to_string()),
+                    content_type: Some("image/jpeg".to_string()),
+                    content: photo.clone(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let stored = fixture
+            .storage
+            .get(&attachment.storage_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored,
+            metadata::strip(AttachmentType::Jpeg, &photo).unwrap()
+        );
+        assert_eq!(attachment.checksum, checksum(&stored));
+        assert_eq!(attachment.size, stored.len() as i64);
+    }
+
+    #[rocket::async_test]
+    async fn removes_the_content_when_the_audit_entry_cannot_be_written() {
+        let fixture = fixture();
+        let gateway = Arc::new(
+            InMemoryAttachmentGateway::default()
+                .auditing_to(Arc::new(InMemoryAuditGateway::failing())),
+        );
+        let supplies = fixture.use_case.supplies.clone();
+        let use_case =
+            UploadAttachmentUseCase::new(supplies, gateway.clone(), fixture.storage.clone());
+
+        let error = use_case
+            .execute(
+                &context(),
+                &fixture.supply_id,
+                &UploadAttachmentInput {
+                    name: None,
+                    content_type: None,
+                    content: PDF.to_vec(),
+                },
+            )
+            .await;
+
+        assert!(error.is_err());
+        assert_eq!(fixture.storage.len(), 0);
+        assert!(gateway
+            .find_by_supply(&fixture.supply_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn rejects_unsupported_or_mislabeled_files() {
+        let fixture = fixture();
+        let upload = |content_type: &str, content: &[u8]| UploadAttachmentInput {
+            name: None,
+            content_type: Some(content_type.to_string()),
+            content: content.to_vec(),
+        };
+
+        let errors = [
+            upload("image/png", PDF),
+            upload("text/csv", b"name,unit,price\n"),
+            upload("application/pdf", b""),
+        ];
+        let mut messages = Vec::new();
+        for input in errors {
+            let error = fixture
+                .use_case
+                .execute(&context(), &fixture.supply_id, &input)
+                .await
+                .unwrap_err();
+            messages.extend(error.format_errors());
+        }
+
+        assert_eq!(
+            messages,
+            vec![
+                "'file' is application/pdf but was sent as image/png",
+                "'file' must be a PDF, JPEG, PNG or WebP file",
+                "'file' should not be empty",
+            ]
+        );
+        assert_eq!(fixture.storage.len(), 0);
+
+        let missing = fixture
+            .use_case
+            .execute(&context(), "missing", &upload("application/pdf", PDF))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            missing.format_errors(),
+            vec!["NotFound: supply with id 'missing' was not found"]
+        );
+    }
+}
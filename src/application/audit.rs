@@ -0,0 +1,90 @@
+pub use crate::domain::audit::AuditAction;
+pub use crate::domain::audit::AuditEntry;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use crate::metrics;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Serialize;
+
+/// Who is performing a mutation, for which tenant and on behalf of which
+/// request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditContext {
+    pub actor: String,
+    pub tenant_id: TenantId,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    pub fn new(actor: &str, tenant_id: TenantId, request_id: Option<&str>) -> Self {
+        Self {
+            actor: actor.to_string(),
+            tenant_id,
+            request_id: request_id.map(str::to_string),
+        }
+    }
+}
+
+impl AuditEntry {
+    pub fn new<T: Serialize>(
+        context: &AuditContext,
+        action: AuditAction,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Self {
+        let snapshot = |value: Option<&T>| value.and_then(|v| serde_json::to_value(v).ok());
+        Self {
+            id: None,
+            actor: context.actor.to_owned(),
+            action,
+            aggregate_type: aggregate_type.to_string(),
+            aggregate_id: aggregate_id.to_string(),
+            before: snapshot(before),
+            after: snapshot(after),
+            request_id: context.request_id.to_owned(),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub aggregate_type: Option<String>,
+    pub aggregate_id: Option<String>,
+    /// Any of these aggregates; empty for all of them.
+    pub aggregate_ids: Vec<String>,
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[async_trait]
+pub trait AuditGateway: Send + Sync {
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, CustomError>;
+}
+
+/// Counts entries written to the audit log, or that failed to be.
+pub fn count(entries: &[AuditEntry], recorded: bool) {
+    let counter = metrics::counter(
+        "audit_entries_total",
+        "Audit entries recorded by aggregate, action and result",
+        &["aggregate_type", "action", "result"],
+    );
+    for entry in entries {
+        counter
+            .with_label_values(&[
+                entry.aggregate_type.as_str(),
+                entry.action.as_str(),
+                if recorded { "success" } else { "failure" },
+            ])
+            .inc();
+    }
+}
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use rocket::futures::future::join_all;
+use rocket::tokio::time::timeout;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// A dependency the service needs to serve traffic.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn check(&self) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, ComponentHealth>,
+}
+
+impl HealthReport {
+    pub fn is_up(&self) -> bool {
+        self.status == HealthStatus::Up
+    }
+}
+
+async fn run_one(check: &dyn HealthCheck, limit: Duration) -> ComponentHealth {
+    let started = Instant::now();
+    let result = match timeout(limit, check.check()).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}ms", limit.as_millis())),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(()) => ComponentHealth {
+            status: HealthStatus::Up,
+            latency_ms,
+            error: None,
+        },
+        Err(error) => ComponentHealth {
+            status: HealthStatus::Down,
+            latency_ms,
+            error: Some(error),
+        },
+    }
+}
+
+/// Runs the checks concurrently, each bounded by `limit`. The report is up
+/// only when every check is.
+pub async fn run(checks: &[Arc<dyn HealthCheck>], limit: Duration) -> HealthReport {
+    let results = join_all(checks.iter().map(|check| run_one(check.as_ref(), limit))).await;
+
+    let checks = checks
+        .iter()
+        .map(|check| check.name().to_string())
+        .zip(results)
+        .collect::<BTreeMap<_, _>>();
+    let status = if checks.values().all(|c| c.status == HealthStatus::Up) {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+
+    HealthReport { status, checks }
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::*;
+
+    struct Fixed(&'static str, Result<(), String>, Duration);
+
+    #[async_trait]
+    impl HealthCheck for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            rocket::tokio::time::sleep(self.2).await;
+            self.1.clone()
+        }
+    }
+
+    #[rocket::async_test]
+    async fn reports_up_when_every_check_passes() {
+        let checks: Vec<Arc<dyn HealthCheck>> = vec![
+            Arc::new(Fixed("a", Ok(()), Duration::ZERO)),
+            Arc::new(Fixed("b", Ok(()), Duration::ZERO)),
+        ];
+
+        let report = run(&checks, Duration::from_secs(1)).await;
+
+        assert!(report.is_up());
+        assert_eq!(report.checks.len(), 2);
+        assert_eq!(report.checks["a"].error, None);
+    }
+
+    #[rocket::async_test]
+    async fn reports_down_on_failures_and_timeouts() {
+        let checks: Vec<Arc<dyn HealthCheck>> = vec![
+            Arc::new(Fixed("ok", Ok(()), Duration::ZERO)),
+            Arc::new(Fixed("broken", Err("refused".to_string()), Duration::ZERO)),
+            Arc::new(Fixed("slow", Ok(()), Duration::from_secs(5))),
+        ];
+
+        let report = run(&checks, Duration::from_millis(50)).await;
+
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(report.checks["ok"].status, HealthStatus::Up);
+        assert_eq!(report.checks["broken"].error.as_deref(), Some("refused"));
+        assert_eq!(
+            report.checks["slow"].error.as_deref(),
+            Some("timed out after 50ms")
+        );
+    }
+}
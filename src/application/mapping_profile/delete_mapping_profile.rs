@@ -0,0 +1,45 @@
+use super::not_found;
+use super::MappingProfileGateway;
+use super::SupplierProfile;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct DeleteMappingProfileUseCase {
+    gateway: Arc<dyn MappingProfileGateway>,
+}
+
+impl DeleteMappingProfileUseCase {
+    pub fn new(gateway: Arc<dyn MappingProfileGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        supplier: &str,
+    ) -> Result<(), Notification> {
+        let current = self
+            .gateway
+            .find(supplier)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supplier)))?;
+
+        let entry = AuditEntry::new(
+            context,
+            AuditAction::Delete,
+            AGGREGATE_TYPE,
+            supplier,
+            Some(&current),
+            None::<&SupplierProfile>,
+        );
+        self.gateway
+            .delete(supplier, &entry)
+            .await
+            .map_err(Notification::with_one_error)
+    }
+}
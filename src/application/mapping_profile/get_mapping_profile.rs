@@ -0,0 +1,23 @@
+use super::not_found;
+use super::MappingProfileGateway;
+use super::SupplierProfile;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct GetMappingProfileUseCase {
+    gateway: Arc<dyn MappingProfileGateway>,
+}
+
+impl GetMappingProfileUseCase {
+    pub fn new(gateway: Arc<dyn MappingProfileGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(&self, supplier: &str) -> Result<SupplierProfile, Notification> {
+        self.gateway
+            .find(supplier)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(supplier)))
+    }
+}
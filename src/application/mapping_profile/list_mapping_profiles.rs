@@ -0,0 +1,21 @@
+use super::MappingProfileGateway;
+use super::SupplierProfile;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct ListMappingProfilesUseCase {
+    gateway: Arc<dyn MappingProfileGateway>,
+}
+
+impl ListMappingProfilesUseCase {
+    pub fn new(gateway: Arc<dyn MappingProfileGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(&self) -> Result<Vec<SupplierProfile>, Notification> {
+        self.gateway
+            .find_all()
+            .await
+            .map_err(Notification::with_one_error)
+    }
+}
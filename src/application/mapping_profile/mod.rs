@@ -0,0 +1,466 @@
+pub mod delete_mapping_profile;
+pub mod get_mapping_profile;
+pub mod list_mapping_profiles;
+pub mod save_mapping_profile;
+
+use crate::application::audit::AuditEntry;
+use crate::application::supply::import_supplies::CsvFormat;
+use crate::domain::audit::Audit;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+pub const AGGREGATE_TYPE: &str = "mapping_profile";
+
+/// The header of each column a price list row is read from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ColumnMapping {
+    /// Matches rows to supplies by id instead of by name when present.
+    pub id: Option<String>,
+    pub name: String,
+    pub unit: String,
+    pub price: String,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            id: Some("id".to_string()),
+            name: "name".to_string(),
+            unit: "unit".to_string(),
+            price: "price".to_string(),
+        }
+    }
+}
+
+/// A change made to a value before it is read. Text transforms apply to the
+/// cell in order; `multiply` applies to the price once it is a number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ValueTransform {
+    Trim,
+    Lowercase,
+    Uppercase,
+    Replace {
+        from: String,
+        to: String,
+    },
+    StripPrefix {
+        value: String,
+    },
+    StripSuffix {
+        value: String,
+    },
+    Multiply {
+        #[serde(with = "rust_decimal::serde::float")]
+        #[schema(value_type = f64, example = 0.02)]
+        factor: Decimal,
+    },
+}
+
+impl ValueTransform {
+    fn apply_text(&self, value: String) -> String {
+        match self {
+            Self::Trim => value.trim().to_string(),
+            Self::Lowercase => value.to_lowercase(),
+            Self::Uppercase => value.to_uppercase(),
+            Self::Replace { from, to } => value.replace(from.as_str(), to),
+            Self::StripPrefix { value: prefix } => value
+                .trim()
+                .strip_prefix(prefix.as_str())
+                .map(str::to_string)
+                .unwrap_or(value),
+            Self::StripSuffix { value: suffix } => value
+                .trim()
+                .strip_suffix(suffix.as_str())
+                .map(str::to_string)
+                .unwrap_or(value),
+            Self::Multiply { .. } => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct FieldTransforms {
+    pub name: Vec<ValueTransform>,
+    pub unit: Vec<ValueTransform>,
+    pub price: Vec<ValueTransform>,
+}
+
+/// Skips the rows whose `column` contains `contains`, in any case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SkipWhen {
+    pub column: String,
+    pub contains: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SkipRules {
+    /// Lines above the header, such as titles.
+    pub leading_rows: usize,
+    /// Rows without any value, such as spacing between sections.
+    pub blank_rows: bool,
+    /// Rows such as subtotals and section titles.
+    pub matching: Vec<SkipWhen>,
+}
+
+impl Default for SkipRules {
+    fn default() -> Self {
+        Self {
+            leading_rows: 0,
+            blank_rows: true,
+            matching: Vec::new(),
+        }
+    }
+}
+
+/// How the price list of a supplier maps onto supplies and prices. The
+/// default profile reads the columns `POST /supplies/import` documents.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct MappingProfile {
+    pub format: CsvFormat,
+    pub columns: ColumnMapping,
+    /// Units of the supplier, in any case, and the unit they stand for.
+    pub unit_aliases: BTreeMap<String, String>,
+    pub transforms: FieldTransforms,
+    pub skip: SkipRules,
+}
+
+/// A row of a price list as the profile reads it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedRow {
+    pub id: Option<String>,
+    pub name: String,
+    pub unit: String,
+    pub price: Decimal,
+}
+
+/// Positions of the mapped columns in the header.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    id: Option<usize>,
+    name: usize,
+    unit: usize,
+    price: usize,
+    skip: Vec<(usize, String)>,
+}
+
+impl MappingProfile {
+    pub fn validate(&self) -> Result<(), Notification> {
+        let mut errors = Vec::new();
+        if self.format.delimiter_byte().is_none() {
+            errors.push(Message::new("delimiter-invalid").for_field("format"));
+        }
+        let columns = [
+            Some(&self.columns.name),
+            Some(&self.columns.unit),
+            Some(&self.columns.price),
+            self.columns.id.as_ref(),
+        ];
+        if columns
+            .iter()
+            .flatten()
+            .any(|column| column.trim().is_empty())
+        {
+            errors.push(Message::new("columns-empty").for_field("columns"));
+        }
+        if self
+            .unit_aliases
+            .iter()
+            .any(|(alias, unit)| alias.trim().is_empty() || unit.trim().is_empty())
+        {
+            errors.push(Message::new("unit-aliases-empty").for_field("unit_aliases"));
+        }
+        let multiplies = |transforms: &[ValueTransform]| {
+            transforms
+                .iter()
+                .any(|transform| matches!(transform, ValueTransform::Multiply { .. }))
+        };
+        if multiplies(&self.transforms.name) || multiplies(&self.transforms.unit) {
+            errors.push(Message::new("transforms-multiply-prices-only").for_field("transforms"));
+        }
+        let factors = self
+            .transforms
+            .price
+            .iter()
+            .filter_map(|transform| match transform {
+                ValueTransform::Multiply { factor } => Some(factor),
+                _ => None,
+            });
+        if factors.into_iter().any(|factor| factor <= &Decimal::ZERO) {
+            errors.push(Message::new("transforms-factor-not-positive").for_field("transforms"));
+        }
+        if self
+            .skip
+            .matching
+            .iter()
+            .any(|skip| skip.column.trim().is_empty())
+        {
+            errors.push(Message::new("skip-columns-empty").for_field("skip"));
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(Notification::with_errors(
+            errors.into_iter().map(CustomError::Validation).collect(),
+        ))
+    }
+
+    /// Finds the mapped columns in the header, in any case.
+    pub fn columns(&self, header: &csv::StringRecord) -> Result<Columns, Notification> {
+        let position = |column: &str| {
+            header
+                .iter()
+                .position(|name| name.trim().eq_ignore_ascii_case(column.trim()))
+        };
+        let mut missing = Vec::new();
+        let mut required = |field: &'static str, column: &str| {
+            let found = position(column);
+            if found.is_none() {
+                let message = if field == column {
+                    Message::new("column-missing").arg("field", field)
+                } else {
+                    Message::new("column-missing-named")
+                        .arg("field", field)
+                        .arg("column", column)
+                };
+                missing.push(CustomError::Validation(message.for_field(field)));
+            }
+            found.unwrap_or_default()
+        };
+        let mut columns = Columns {
+            name: required("name", &self.columns.name),
+            unit: required("unit", &self.columns.unit),
+            price: required("price", &self.columns.price),
+            id: self.columns.id.as_deref().and_then(position),
+            skip: Vec::new(),
+        };
+        for skip in &self.skip.matching {
+            match position(&skip.column) {
+                Some(index) => columns.skip.push((index, skip.contains.to_lowercase())),
+                None => missing.push(CustomError::Validation(
+                    Message::new("skip-column-missing")
+                        .arg("column", skip.column.as_str())
+                        .for_field("skip"),
+                )),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(Notification::with_errors(missing));
+        }
+        Ok(columns)
+    }
+
+    /// Whether the row is left out of the import.
+    pub fn skips(&self, record: &csv::StringRecord, columns: &Columns) -> bool {
+        let blank = record.iter().all(|field| field.trim().is_empty());
+        (self.skip.blank_rows && blank)
+            || columns.skip.iter().any(|(index, contains)| {
+                record
+                    .get(*index)
+                    .is_some_and(|field| field.to_lowercase().contains(contains.as_str()))
+            })
+    }
+
+    /// Reads a row, returning the errors of each of its invalid values.
+    pub fn map_row(
+        &self,
+        record: &csv::StringRecord,
+        columns: &Columns,
+    ) -> Result<MappedRow, Notification> {
+        let field = |index: usize, transforms: &[ValueTransform]| {
+            let value = record.get(index).unwrap_or_default().to_string();
+            transforms
+                .iter()
+                .fold(value, |value, transform| transform.apply_text(value))
+                .trim()
+                .to_string()
+        };
+        let mut errors = Vec::new();
+
+        let unit = field(columns.unit, &self.transforms.unit);
+        let unit = self
+            .unit_aliases
+            .iter()
+            .find(|(alias, _)| alias.trim().eq_ignore_ascii_case(&unit))
+            .map(|(_, canonical)| canonical.trim().to_string())
+            .unwrap_or(unit);
+        if unit.is_empty() {
+            errors.push(CustomError::Validation(
+                Message::new("unit-empty").for_field("unit"),
+            ));
+        }
+
+        let raw_price = field(columns.price, &self.transforms.price);
+        let price = self.format.parse_price(&raw_price).map(|price| {
+            let mut price = self
+                .transforms
+                .price
+                .iter()
+                .fold(price, |price, transform| match transform {
+                    ValueTransform::Multiply { factor } => price * factor,
+                    _ => price,
+                });
+            price.rescale(2);
+            price
+        });
+        if price.is_none() {
+            errors.push(CustomError::Validation(
+                Message::new("price-invalid")
+                    .arg("value", raw_price)
+                    .for_field("price"),
+            ));
+        }
+        if !errors.is_empty() {
+            return Err(Notification::with_errors(errors));
+        }
+
+        Ok(MappedRow {
+            id: columns
+                .id
+                .map(|index| field(index, &[]))
+                .filter(|id| !id.is_empty()),
+            name: field(columns.name, &self.transforms.name),
+            unit,
+            price: price.unwrap_or_default(),
+        })
+    }
+}
+
+/// The mapping profile saved for a supplier of a tenant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SupplierProfile {
+    pub supplier: String,
+    #[serde(flatten)]
+    pub profile: MappingProfile,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Suppliers are named like tenants, as they appear in URLs.
+pub fn is_valid_supplier(supplier: &str) -> bool {
+    TenantId::is_valid(supplier)
+}
+
+pub fn not_found(supplier: &str) -> CustomError {
+    CustomError::NotFound(Message::new("mapping-profile-not-found").arg("supplier", supplier))
+}
+
+/// Mapping profiles of a single tenant. Every change is written together
+/// with its audit entry, and fails when the entry can't be written.
+#[async_trait]
+pub trait MappingProfileGateway: Send + Sync {
+    async fn save(
+        &self,
+        profile: &SupplierProfile,
+        audit: Audit<'_, SupplierProfile>,
+    ) -> Result<SupplierProfile, CustomError>;
+    async fn find(&self, supplier: &str) -> Result<Option<SupplierProfile>, CustomError>;
+    async fn find_all(&self) -> Result<Vec<SupplierProfile>, CustomError>;
+    async fn delete(&self, supplier: &str, audit: &AuditEntry) -> Result<(), CustomError>;
+}
+
+#[cfg(test)]
+mod mapping_profile_tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn profile() -> MappingProfile {
+        serde_json::from_value(serde_json::json!({
+            "format": { "delimiter": ";", "decimal_comma": true },
+            "columns": { "id": null, "name": "Descrição", "unit": "Un.", "price": "Preço R$" },
+            "unit_aliases": { "SACO": "sc", "M³": "m3" },
+            "transforms": {
+                "name": [{ "op": "trim" }, { "op": "lowercase" }],
+                "price": [{ "op": "strip_prefix", "value": "R$" }, { "op": "multiply", "factor": 0.5 }]
+            },
+            "skip": { "leading_rows": 1, "matching": [{ "column": "Descrição", "contains": "total" }] }
+        }))
+        .unwrap()
+    }
+
+    fn record(fields: &[&str]) -> csv::StringRecord {
+        csv::StringRecord::from(fields.to_vec())
+    }
+
+    #[test]
+    fn maps_rows_through_columns_aliases_and_transforms() {
+        let profile = profile();
+        assert!(profile.validate().is_ok());
+        assert!(profile.skip.blank_rows);
+        let columns = profile
+            .columns(&record(&["Código", "preço r$", "Un.", "Descrição"]))
+            .unwrap();
+
+        let row = profile
+            .map_row(
+                &record(&["7", "R$ 1.234,56", "saco", "  Cimento CP2 "]),
+                &columns,
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            MappedRow {
+                id: None,
+                name: "cimento cp2".to_string(),
+                unit: "sc".to_string(),
+                price: dec!(617.28),
+            }
+        );
+
+        let errors = profile
+            .map_row(&record(&["8", "consulte", "", "Areia"]), &columns)
+            .unwrap_err();
+        assert_eq!(
+            errors.format_errors(),
+            vec![
+                "'unit' should not be empty",
+                "'price' is not a valid number: 'consulte'"
+            ]
+        );
+
+        assert!(profile.skips(&record(&["", "", "", "TOTAL GERAL"]), &columns));
+        assert!(profile.skips(&record(&["", " ", "", ""]), &columns));
+        assert!(!profile.skips(&record(&["9", "10", "sc", "Cal"]), &columns));
+    }
+
+    #[test]
+    fn reports_missing_columns_and_invalid_profiles() {
+        let profile = profile();
+        let error = profile
+            .columns(&record(&["Descrição", "Unidade", "Preço R$"]))
+            .unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["'unit' column 'Un.' is missing from the header"]
+        );
+
+        let invalid: MappingProfile = serde_json::from_value(serde_json::json!({
+            "format": { "delimiter": "->" },
+            "columns": { "name": "" },
+            "transforms": { "unit": [{ "op": "multiply", "factor": 2 }] }
+        }))
+        .unwrap();
+        assert_eq!(
+            invalid.validate().unwrap_err().format_errors(),
+            vec![
+                "'format' delimiter must be a single ASCII character",
+                "'columns' should not be empty",
+                "'transforms' can only multiply prices"
+            ]
+        );
+    }
+}
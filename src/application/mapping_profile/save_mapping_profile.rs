@@ -0,0 +1,136 @@
+use super::is_valid_supplier;
+use super::MappingProfile;
+use super::MappingProfileGateway;
+use super::SupplierProfile;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use chrono::Utc;
+use std::sync::Arc;
+
+pub struct SaveMappingProfileUseCase {
+    gateway: Arc<dyn MappingProfileGateway>,
+}
+
+impl SaveMappingProfileUseCase {
+    pub fn new(gateway: Arc<dyn MappingProfileGateway>) -> Self {
+        Self { gateway }
+    }
+
+    /// Creates or replaces the profile of `supplier`.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        supplier: &str,
+        profile: MappingProfile,
+    ) -> Result<SupplierProfile, Notification> {
+        if !is_valid_supplier(supplier) {
+            return Err(Notification::with_one_error(CustomError::Validation(
+                Message::new("supplier-invalid").for_field("supplier"),
+            )));
+        }
+        profile.validate()?;
+
+        let before = self
+            .gateway
+            .find(supplier)
+            .await
+            .map_err(Notification::with_one_error)?;
+        let action = match before {
+            Some(_) => AuditAction::Update,
+            None => AuditAction::Create,
+        };
+        let audit = |saved: &SupplierProfile| {
+            AuditEntry::new(
+                context,
+                action,
+                AGGREGATE_TYPE,
+                supplier,
+                before.as_ref(),
+                Some(saved),
+            )
+        };
+        self.gateway
+            .save(
+                &SupplierProfile {
+                    supplier: supplier.to_string(),
+                    profile,
+                    updated_at: Utc::now(),
+                },
+                &audit,
+            )
+            .await
+            .map_err(Notification::with_one_error)
+    }
+}
+
+#[cfg(test)]
+mod save_mapping_profile_tests {
+    use super::*;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::testing::InMemoryAuditGateway;
+    use crate::application::testing::InMemoryMappingProfileGateway;
+    use crate::domain::tenant_id::TenantId;
+
+    #[rocket::async_test]
+    async fn creates_then_replaces_a_profile_and_audits_both() {
+        let gateway = Arc::new(InMemoryMappingProfileGateway::default());
+        let audit = gateway.audit();
+        let use_case = SaveMappingProfileUseCase::new(gateway.clone());
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+        let mut profile = MappingProfile::default();
+
+        use_case
+            .execute(&context, "votorantim", profile.clone())
+            .await
+            .unwrap();
+        profile.skip.leading_rows = 2;
+        let saved = use_case
+            .execute(&context, "votorantim", profile)
+            .await
+            .unwrap();
+
+        assert_eq!(saved.profile.skip.leading_rows, 2);
+        assert_eq!(gateway.find_all().await.unwrap().len(), 1);
+        let mut actions = audit
+            .find(&AuditFilter::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect::<Vec<_>>();
+        actions.sort_by_key(|action| action.as_str());
+        assert_eq!(actions, vec![AuditAction::Create, AuditAction::Update]);
+
+        let error = use_case
+            .execute(&context, "Votorantim S.A.", MappingProfile::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec!["'supplier' must be a non-empty lowercase identifier"]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn saves_nothing_when_the_audit_entry_cannot_be_written() {
+        let gateway = Arc::new(
+            InMemoryMappingProfileGateway::default()
+                .auditing_to(Arc::new(InMemoryAuditGateway::failing())),
+        );
+        let use_case = SaveMappingProfileUseCase::new(gateway.clone());
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+
+        let error = use_case
+            .execute(&context, "votorantim", MappingProfile::default())
+            .await;
+
+        assert!(error.is_err());
+        assert!(gateway.find_all().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod attachment;
+pub mod audit;
+pub mod health;
+pub mod mapping_profile;
+pub mod supply;
+
+#[cfg(test)]
+pub mod testing;
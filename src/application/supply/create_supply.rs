@@ -0,0 +1,172 @@
+use super::SupplyOutput;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::application::supply::events;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::Supply;
+use crate::domain::validation::notification::Notification;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateSupplyInput {
+    pub name: String,
+    pub prices: Vec<Price>,
+}
+
+pub struct CreateSupplyUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+    events: Arc<dyn SupplyEventPublisher>,
+}
+
+impl CreateSupplyUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>, events: Arc<dyn SupplyEventPublisher>) -> Self {
+        Self { gateway, events }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        input: CreateSupplyInput,
+    ) -> Result<SupplyOutput, Notification> {
+        let supply = Supply::new(context.tenant_id.to_owned(), &input.name, input.prices)?;
+        let audit = |created: &Supply| {
+            let output = SupplyOutput::from(created);
+            AuditEntry::new(
+                context,
+                AuditAction::Create,
+                AGGREGATE_TYPE,
+                &output.id,
+                None,
+                Some(&output),
+            )
+        };
+        let created = self
+            .gateway
+            .create(&supply, &audit)
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        let output = SupplyOutput::from(&created);
+        events::publish(self.events.as_ref(), SupplyEvent::created(&created)).await;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod create_supply_tests {
+    use super::*;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::testing::InMemoryAuditGateway;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::tenant_id::TenantId;
+    use crate::domain::validation::error::CustomError;
+    use crate::domain::validation::validation_handler::ValidationHandler;
+
+    fn context(actor: &str, request_id: Option<&str>) -> AuditContext {
+        AuditContext::new(actor, TenantId::from_str("acme"), request_id)
+    }
+
+    #[rocket::async_test]
+    async fn create_a_valid_supply_and_audit_it() {
+        let gateway = Arc::new(InMemorySupplyGateway::default());
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = CreateSupplyUseCase::new(gateway.clone(), events.clone());
+        let context = context("alice", Some("req-1"));
+
+        let output = use_case
+            .execute(
+                &context,
+                CreateSupplyInput {
+                    name: "cimento".to_string(),
+                    prices: vec![Price::new("sc", 25000)],
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.name, "cimento");
+        assert_eq!(output.tenant_id, "acme");
+        assert_eq!(gateway.len(), 1);
+
+        let entries = audit.find(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, "alice");
+        assert_eq!(entries[0].action, AuditAction::Create);
+        assert_eq!(entries[0].aggregate_id, output.id);
+        assert_eq!(entries[0].request_id.as_deref(), Some("req-1"));
+        assert!(entries[0].before.is_none());
+        assert_eq!(entries[0].after.as_ref().unwrap()["name"], "cimento");
+
+        let events = events.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "SupplyCreated");
+        assert_eq!(events[0].supply_id(), output.id);
+    }
+
+    #[rocket::async_test]
+    async fn create_an_invalid_supply_returns_notification() {
+        let gateway = Arc::new(InMemorySupplyGateway::default());
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = CreateSupplyUseCase::new(gateway, events.clone());
+
+        let error = use_case
+            .execute(
+                &context("alice", None),
+                CreateSupplyInput {
+                    name: "cimento".to_string(),
+                    prices: vec![],
+                },
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.format_errors(), vec!["'price' should not be empty"]);
+        assert!(error.has_errors());
+        assert!(audit
+            .find(&AuditFilter::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(events.events().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn create_fails_without_the_audit_entry() {
+        let gateway = Arc::new(
+            InMemorySupplyGateway::default().auditing_to(Arc::new(InMemoryAuditGateway::failing())),
+        );
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = CreateSupplyUseCase::new(gateway.clone(), events.clone());
+
+        let error = use_case
+            .execute(
+                &context("alice", None),
+                CreateSupplyInput {
+                    name: "cimento".to_string(),
+                    prices: vec![Price::new("sc", 25000)],
+                },
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.get_first_error(),
+            Some(CustomError::RepositoryError(_))
+        ));
+        assert_eq!(gateway.len(), 0);
+        assert!(events.events().is_empty());
+    }
+}
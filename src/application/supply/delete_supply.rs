@@ -0,0 +1,86 @@
+use super::get_supply::not_found;
+use super::SupplyOutput;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::application::supply::events;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct DeleteSupplyUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+    events: Arc<dyn SupplyEventPublisher>,
+}
+
+impl DeleteSupplyUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>, events: Arc<dyn SupplyEventPublisher>) -> Self {
+        Self { gateway, events }
+    }
+
+    pub async fn execute(&self, context: &AuditContext, id: &str) -> Result<(), Notification> {
+        let supply_id = SupplyId::from_str(id);
+        let current = self
+            .gateway
+            .find_by_id(&supply_id)
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(id)))?;
+
+        let before = SupplyOutput::from(&current);
+        let entry = AuditEntry::new(
+            context,
+            AuditAction::Delete,
+            AGGREGATE_TYPE,
+            id,
+            Some(&before),
+            None,
+        );
+        self.gateway
+            .delete_by_id(&supply_id, &entry)
+            .await
+            .map_err(Notification::with_one_error)?;
+        events::publish(self.events.as_ref(), SupplyEvent::deleted(&current)).await;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod delete_supply_tests {
+    use super::*;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::supply::price::Price;
+    use crate::domain::supply::Supply;
+    use crate::domain::tenant_id::TenantId;
+
+    #[rocket::async_test]
+    async fn delete_a_supply_and_audit_the_last_state() {
+        let tenant = TenantId::from_str("acme");
+        let supply = Supply::new(tenant.clone(), "cimento", vec![Price::new("sc", 25000)]).unwrap();
+        let id = SupplyOutput::from(&supply).id;
+        let gateway = Arc::new(InMemorySupplyGateway::with(vec![supply]));
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = DeleteSupplyUseCase::new(gateway.clone(), events.clone());
+
+        use_case
+            .execute(&AuditContext::new("carol", tenant, None), &id)
+            .await
+            .unwrap();
+
+        assert_eq!(gateway.len(), 0);
+        let entries = audit.find(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries[0].action, AuditAction::Delete);
+        assert_eq!(entries[0].before.as_ref().unwrap()["name"], "cimento");
+        assert!(entries[0].after.is_none());
+        assert_eq!(events.events()[0].name(), "SupplyDeleted");
+    }
+}
@@ -0,0 +1,229 @@
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::Supply;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A change to a supply, published for consumers such as the SSE feed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type")]
+pub enum SupplyEvent {
+    SupplyCreated {
+        supply_id: String,
+        tenant_id: String,
+        name: String,
+        prices: Vec<Price>,
+        occurred_at: DateTime<Utc>,
+    },
+    SupplyPriceChanged {
+        supply_id: String,
+        tenant_id: String,
+        name: String,
+        previous_prices: Vec<Price>,
+        prices: Vec<Price>,
+        occurred_at: DateTime<Utc>,
+    },
+    SupplyRenamed {
+        supply_id: String,
+        tenant_id: String,
+        previous_name: String,
+        name: String,
+        occurred_at: DateTime<Utc>,
+    },
+    SupplyDeleted {
+        supply_id: String,
+        tenant_id: String,
+        name: String,
+        prices: Vec<Price>,
+        occurred_at: DateTime<Utc>,
+    },
+}
+
+impl SupplyEvent {
+    pub fn created(supply: &Supply) -> Self {
+        SupplyEvent::SupplyCreated {
+            supply_id: supply.get_id().get_value().to_string(),
+            tenant_id: supply.get_tenant_id().get_value().to_string(),
+            name: supply.get_name().to_string(),
+            prices: supply.get_prices().to_owned(),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    /// The price change from `before` to `after`, if their prices differ.
+    pub fn price_changed(before: &Supply, after: &Supply) -> Option<Self> {
+        if before.get_prices() == after.get_prices() {
+            return None;
+        }
+        Some(SupplyEvent::SupplyPriceChanged {
+            supply_id: after.get_id().get_value().to_string(),
+            tenant_id: after.get_tenant_id().get_value().to_string(),
+            name: after.get_name().to_string(),
+            previous_prices: before.get_prices().to_owned(),
+            prices: after.get_prices().to_owned(),
+            occurred_at: Utc::now(),
+        })
+    }
+
+    /// The renaming from `before` to `after`, if their names differ.
+    pub fn renamed(before: &Supply, after: &Supply) -> Option<Self> {
+        if before.get_name() == after.get_name() {
+            return None;
+        }
+        Some(SupplyEvent::SupplyRenamed {
+            supply_id: after.get_id().get_value().to_string(),
+            tenant_id: after.get_tenant_id().get_value().to_string(),
+            previous_name: before.get_name().to_string(),
+            name: after.get_name().to_string(),
+            occurred_at: Utc::now(),
+        })
+    }
+
+    pub fn deleted(supply: &Supply) -> Self {
+        SupplyEvent::SupplyDeleted {
+            supply_id: supply.get_id().get_value().to_string(),
+            tenant_id: supply.get_tenant_id().get_value().to_string(),
+            name: supply.get_name().to_string(),
+            prices: supply.get_prices().to_owned(),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SupplyEvent::SupplyCreated { .. } => "SupplyCreated",
+            SupplyEvent::SupplyPriceChanged { .. } => "SupplyPriceChanged",
+            SupplyEvent::SupplyRenamed { .. } => "SupplyRenamed",
+            SupplyEvent::SupplyDeleted { .. } => "SupplyDeleted",
+        }
+    }
+
+    pub fn supply_id(&self) -> &str {
+        match self {
+            SupplyEvent::SupplyCreated { supply_id, .. }
+            | SupplyEvent::SupplyPriceChanged { supply_id, .. }
+            | SupplyEvent::SupplyRenamed { supply_id, .. }
+            | SupplyEvent::SupplyDeleted { supply_id, .. } => supply_id,
+        }
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        match self {
+            SupplyEvent::SupplyCreated { tenant_id, .. }
+            | SupplyEvent::SupplyPriceChanged { tenant_id, .. }
+            | SupplyEvent::SupplyRenamed { tenant_id, .. }
+            | SupplyEvent::SupplyDeleted { tenant_id, .. } => tenant_id,
+        }
+    }
+
+    /// Units priced by the event, before or after the change.
+    pub fn units(&self) -> impl Iterator<Item = &str> {
+        let (previous, prices): (&[Price], &[Price]) = match self {
+            SupplyEvent::SupplyCreated { prices, .. }
+            | SupplyEvent::SupplyDeleted { prices, .. } => (&[], prices),
+            SupplyEvent::SupplyRenamed { .. } => (&[], &[]),
+            SupplyEvent::SupplyPriceChanged {
+                previous_prices,
+                prices,
+                ..
+            } => (previous_prices, prices),
+        };
+        previous.iter().chain(prices).map(Price::get_unit)
+    }
+}
+
+/// Which events of a tenant a subscriber wants.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SupplyEventFilter {
+    pub tenant_id: String,
+    pub supply_id: Option<String>,
+    pub unit: Option<String>,
+}
+
+impl SupplyEventFilter {
+    pub fn matches(&self, event: &SupplyEvent) -> bool {
+        event.tenant_id() == self.tenant_id
+            && self
+                .supply_id
+                .as_ref()
+                .is_none_or(|id| event.supply_id() == id)
+            && self
+                .unit
+                .as_ref()
+                .is_none_or(|unit| event.units().any(|u| u == unit))
+    }
+}
+
+#[async_trait]
+pub trait SupplyEventPublisher: Send + Sync {
+    async fn publish(&self, event: &SupplyEvent) -> Result<(), CustomError>;
+}
+
+/// Publishes an event, logging instead of failing: the change it describes
+/// has already been committed at this point.
+pub async fn publish(publisher: &dyn SupplyEventPublisher, event: SupplyEvent) {
+    if let Err(e) = publisher.publish(&event).await {
+        tracing::error!(
+            event = event.name(),
+            supply_id = event.supply_id(),
+            "failed to publish supply event: {}",
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod supply_events_tests {
+    use super::*;
+    use crate::domain::tenant_id::TenantId;
+
+    fn supply(prices: Vec<Price>) -> Supply {
+        Supply::new(TenantId::from_str("acme"), "cimento", prices).unwrap()
+    }
+
+    #[test]
+    fn price_changed_only_when_prices_differ() {
+        let before = supply(vec![Price::new("sc", 25000)]);
+        let renamed = before.update(Some("cimento cp2"), None).unwrap();
+        let repriced = before
+            .update(None, Some(vec![Price::new("sc", 27000)]))
+            .unwrap();
+
+        assert_eq!(SupplyEvent::price_changed(&before, &renamed), None);
+        assert_eq!(SupplyEvent::renamed(&before, &repriced), None);
+        assert_eq!(
+            SupplyEvent::renamed(&before, &renamed).unwrap().name(),
+            "SupplyRenamed"
+        );
+
+        let event = SupplyEvent::price_changed(&before, &repriced).unwrap();
+        assert_eq!(event.name(), "SupplyPriceChanged");
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "SupplyPriceChanged");
+        assert_eq!(json["previous_prices"][0]["value"], 250.0);
+        assert_eq!(json["prices"][0]["value"], 270.0);
+    }
+
+    #[test]
+    fn filters_by_tenant_supply_and_unit() {
+        let event = SupplyEvent::created(&supply(vec![Price::new("sc", 25000)]));
+        let filter =
+            |tenant: &str, supply_id: Option<&str>, unit: Option<&str>| SupplyEventFilter {
+                tenant_id: tenant.to_string(),
+                supply_id: supply_id.map(str::to_string),
+                unit: unit.map(str::to_string),
+            };
+
+        assert!(filter("acme", None, None).matches(&event));
+        assert!(!filter("other", None, None).matches(&event));
+        assert!(filter("acme", Some(event.supply_id()), Some("sc")).matches(&event));
+        assert!(!filter("acme", Some("another"), None).matches(&event));
+        assert!(!filter("acme", None, Some("kg")).matches(&event));
+    }
+}
@@ -0,0 +1,608 @@
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_gateway::SupplyKey;
+use crate::domain::supply::supply_gateway::SupplyPage;
+use crate::domain::supply::Supply;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use rocket::futures::stream;
+use rocket::futures::Stream;
+use rocket::futures::StreamExt;
+use rocket::futures::TryStreamExt;
+use rocket::tokio;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::Format;
+use rust_xlsxwriter::Workbook;
+use rust_xlsxwriter::XlsxError;
+use serde_json::Map;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Supplies read from the gateway per query.
+const PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self, CustomError> {
+        match value.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "xlsx" => Ok(Self::Xlsx),
+            _ => Err(CustomError::Validation(
+                Message::new("export-format-invalid")
+                    .arg("value", value)
+                    .for_field("format"),
+            )),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+            Self::Xlsx => "xlsx",
+        }
+    }
+
+    /// The line ending a streamed file whose export failed partway, so that
+    /// what was sent can't be taken for the whole file. The error is written
+    /// in `locale`.
+    pub fn failure_line(&self, notification: &Notification, locale: Locale) -> Vec<u8> {
+        let message = notification.format_errors_in(locale).join(", ");
+        match self {
+            Self::Csv => format!("# export failed: {}\n", message).into_bytes(),
+            _ => {
+                let mut line = serde_json::to_vec(&serde_json::json!({ "error": message }))
+                    .unwrap_or_default();
+                line.push(b'\n');
+                line
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportColumn {
+    Id,
+    Name,
+    Unit,
+    Price,
+    Version,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl ExportColumn {
+    pub const ALL: [ExportColumn; 7] = [
+        Self::Id,
+        Self::Name,
+        Self::Unit,
+        Self::Price,
+        Self::Version,
+        Self::CreatedAt,
+        Self::UpdatedAt,
+    ];
+
+    /// Names match the columns `POST /supplies/import` reads.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Name => "name",
+            Self::Unit => "unit",
+            Self::Price => "price",
+            Self::Version => "version",
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+        }
+    }
+
+    /// Reads a comma separated list of column names, all of them if empty.
+    pub fn parse_list(value: &str) -> Result<Vec<Self>, CustomError> {
+        let columns = value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Self::ALL
+                    .into_iter()
+                    .find(|column| column.name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| {
+                        CustomError::Validation(
+                            Message::new("export-column-unknown")
+                                .arg("name", name)
+                                .for_field("columns"),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if columns.is_empty() {
+            return Ok(Self::ALL.to_vec());
+        }
+        Ok(columns)
+    }
+}
+
+/// How numbers are written in CSV files. NDJSON keeps JSON numbers and XLSX
+/// numeric cells, which spreadsheets display in the locale of the reader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberLocale {
+    /// `1,234.56`, with `,` between the columns.
+    En,
+    /// `1.234,56`, with `;` between the columns.
+    PtBr,
+}
+
+impl NumberLocale {
+    pub fn parse(value: &str) -> Result<Self, CustomError> {
+        match value.to_ascii_lowercase().as_str() {
+            "en" | "en-us" => Ok(Self::En),
+            "pt" | "pt-br" => Ok(Self::PtBr),
+            _ => Err(CustomError::Validation(
+                Message::new("export-locale-invalid")
+                    .arg("value", value)
+                    .for_field("locale"),
+            )),
+        }
+    }
+
+    fn separators(&self) -> (char, char) {
+        match self {
+            Self::En => (',', '.'),
+            Self::PtBr => ('.', ','),
+        }
+    }
+
+    pub fn delimiter(&self) -> u8 {
+        match self {
+            Self::En => b',',
+            Self::PtBr => b';',
+        }
+    }
+
+    /// Formats `value` with two decimal places and thousands separators.
+    pub fn format(&self, value: Decimal) -> String {
+        let (thousands, decimal) = self.separators();
+        let value = value.round_dp(2);
+        let formatted = format!("{:.2}", value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(thousands);
+            }
+            grouped.push(digit);
+        }
+        let sign = if value.is_sign_negative() && !value.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        format!("{}{}{}{}", sign, grouped, decimal, fraction)
+    }
+}
+
+/// Which supplies and prices are exported.
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    /// Part of the name, in any case.
+    pub name: Option<String>,
+    /// Only the prices of this unit.
+    pub unit: Option<String>,
+}
+
+impl ExportFilter {
+    /// A row per price of `supplies`, which the gateway already filtered.
+    fn rows<'a>(&'a self, supplies: &'a [Supply]) -> impl Iterator<Item = Row<'a>> {
+        supplies.iter().flat_map(move |supply| {
+            supply
+                .get_prices()
+                .iter()
+                .filter(move |price| {
+                    self.unit
+                        .as_ref()
+                        .is_none_or(|unit| price.get_unit() == unit)
+                })
+                .map(move |price| Row { supply, price })
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub columns: Vec<ExportColumn>,
+    pub locale: NumberLocale,
+    pub filter: ExportFilter,
+}
+
+impl ExportOptions {
+    /// Reads the options as given in a query string or on the command line.
+    pub fn parse(
+        format: Option<&str>,
+        columns: Option<&str>,
+        locale: Option<&str>,
+        filter: ExportFilter,
+    ) -> Result<Self, Notification> {
+        let format = format.map_or(Ok(ExportFormat::Csv), ExportFormat::parse);
+        let columns = ExportColumn::parse_list(columns.unwrap_or_default());
+        let locale = locale.map_or(Ok(NumberLocale::En), NumberLocale::parse);
+
+        match (format, columns, locale) {
+            (Ok(format), Ok(columns), Ok(locale)) => Ok(Self {
+                format,
+                columns,
+                locale,
+                filter,
+            }),
+            (format, columns, locale) => Err(Notification::with_errors(
+                [format.err(), columns.err(), locale.err()]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            )),
+        }
+    }
+}
+
+struct Row<'a> {
+    supply: &'a Supply,
+    price: &'a Price,
+}
+
+impl Row<'_> {
+    fn text(&self, column: ExportColumn, locale: NumberLocale) -> String {
+        match column {
+            ExportColumn::Id => self.supply.get_id().get_value().to_string(),
+            ExportColumn::Name => self.supply.get_name().to_string(),
+            ExportColumn::Unit => self.price.get_unit().to_string(),
+            ExportColumn::Price => locale.format(self.price.get_value()),
+            ExportColumn::Version => self.supply.get_version().to_string(),
+            ExportColumn::CreatedAt => self.supply.get_created_at().to_rfc3339(),
+            ExportColumn::UpdatedAt => self
+                .supply
+                .get_updated_at()
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn json(&self, column: ExportColumn) -> Value {
+        match column {
+            ExportColumn::Price => Value::from(self.price.get_value().to_f64()),
+            ExportColumn::Version => Value::from(self.supply.get_version()),
+            ExportColumn::UpdatedAt if self.supply.get_updated_at().is_none() => Value::Null,
+            column => Value::from(self.text(column, NumberLocale::En)),
+        }
+    }
+}
+
+/// Exports the supplies of a tenant with their current prices, a row per
+/// price, reading them a page at a time.
+pub struct ExportSuppliesUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+}
+
+impl ExportSuppliesUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>) -> Self {
+        Self { gateway }
+    }
+
+    /// The supplies matching `filter`, a page at a time.
+    fn pages(
+        &self,
+        filter: &ExportFilter,
+    ) -> impl Stream<Item = Result<Vec<Supply>, Notification>> + Send + 'static {
+        let first = SupplyPage {
+            limit: PAGE_SIZE,
+            name: filter.name.to_owned(),
+            unit: filter.unit.to_owned(),
+            ..SupplyPage::default()
+        };
+        let start = (self.gateway.clone(), Some(first));
+        stream::try_unfold(start, |(gateway, page)| async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let supplies = gateway
+                .find_page(&page)
+                .await
+                .map_err(Notification::with_one_error)?;
+            let next = ((supplies.len() as i64) == PAGE_SIZE).then(|| SupplyPage {
+                after: supplies.last().map(SupplyKey::from),
+                ..page
+            });
+            Ok(Some((supplies, (gateway, next))))
+        })
+    }
+
+    /// The CSV or NDJSON file as a stream of chunks, the header first.
+    pub fn stream(
+        &self,
+        options: ExportOptions,
+    ) -> impl Stream<Item = Result<Vec<u8>, Notification>> + Send + 'static {
+        let header = match options.format {
+            ExportFormat::Csv => {
+                let names = options.columns.iter().map(|column| column.name());
+                csv_record(&options, names)
+            }
+            _ => Ok(Vec::new()),
+        };
+        let rows = self.pages(&options.filter).and_then(move |page| {
+            let chunk = match options.format {
+                ExportFormat::Csv => {
+                    options
+                        .filter
+                        .rows(&page)
+                        .try_fold(Vec::new(), |mut chunk, row| {
+                            let fields = options
+                                .columns
+                                .iter()
+                                .map(|column| row.text(*column, options.locale));
+                            chunk.extend(csv_record(&options, fields)?);
+                            Ok(chunk)
+                        })
+                }
+                _ => options
+                    .filter
+                    .rows(&page)
+                    .try_fold(Vec::new(), |mut chunk, row| {
+                        let object = options
+                            .columns
+                            .iter()
+                            .map(|column| (column.name().to_string(), row.json(*column)))
+                            .collect::<Map<_, _>>();
+                        serde_json::to_writer(&mut chunk, &object).map_err(export_error)?;
+                        chunk.push(b'\n');
+                        Ok(chunk)
+                    }),
+            };
+            async move { chunk }
+        });
+        stream::once(async move { header })
+            .chain(rows)
+            .try_filter(|chunk| std::future::ready(!chunk.is_empty()))
+    }
+
+    /// Writes the XLSX workbook to `path`, flushing its rows to disk as they
+    /// are written. Unlike CSV and NDJSON it can't be streamed: the file is
+    /// a zip archive only readable once the whole workbook is saved.
+    pub async fn write_xlsx(
+        &self,
+        options: &ExportOptions,
+        path: &Path,
+    ) -> Result<(), Notification> {
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+        let money = Format::new().set_num_format("#,##0.00");
+        let sheet = workbook.add_worksheet_with_constant_memory();
+        sheet.set_name("supplies").map_err(xlsx_error)?;
+        for (col, column) in options.columns.iter().enumerate() {
+            sheet
+                .write_string_with_format(0, col as u16, column.name(), &bold)
+                .map_err(xlsx_error)?;
+        }
+
+        let mut row_number = 0;
+        let mut pages = Box::pin(self.pages(&options.filter));
+        while let Some(page) = pages.try_next().await? {
+            let sheet = workbook.worksheet_from_index(0).map_err(xlsx_error)?;
+            for row in options.filter.rows(&page) {
+                row_number += 1;
+                for (col, column) in options.columns.iter().enumerate() {
+                    let col = col as u16;
+                    let written = match column {
+                        ExportColumn::Price => sheet.write_number_with_format(
+                            row_number,
+                            col,
+                            row.price.get_value().to_f64().unwrap_or_default(),
+                            &money,
+                        ),
+                        ExportColumn::Version => {
+                            sheet.write_number(row_number, col, row.supply.get_version() as f64)
+                        }
+                        column => {
+                            sheet.write_string(row_number, col, row.text(*column, options.locale))
+                        }
+                    };
+                    written.map_err(xlsx_error)?;
+                }
+            }
+        }
+
+        // Zipping the workbook into the file is blocking I/O
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || workbook.save(path))
+            .await
+            .map_err(export_error)?
+            .map_err(xlsx_error)
+    }
+}
+
+fn csv_record<I, F>(options: &ExportOptions, fields: I) -> Result<Vec<u8>, Notification>
+where
+    I: IntoIterator<Item = F>,
+    F: AsRef<[u8]>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.locale.delimiter())
+        .from_writer(Vec::new());
+    writer.write_record(fields).map_err(export_error)?;
+    writer.into_inner().map_err(export_error)
+}
+
+fn export_error(e: impl std::fmt::Display) -> Notification {
+    Notification::with_one_error(CustomError::RepositoryError(format!(
+        "export failed: {}",
+        e
+    )))
+}
+
+fn xlsx_error(e: XlsxError) -> Notification {
+    export_error(e)
+}
+
+#[cfg(test)]
+mod export_supplies_tests {
+    use super::*;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::supply::supply_id::SupplyId;
+    use crate::domain::tenant_id::TenantId;
+    use rocket::futures::StreamExt;
+    use rust_decimal_macros::dec;
+
+    fn supply(id: &str, name: &str, prices: Vec<Price>) -> Supply {
+        Supply::with(
+            SupplyId::from_str(id),
+            TenantId::from_str("acme"),
+            name,
+            prices,
+        )
+        .unwrap()
+    }
+
+    fn use_case() -> ExportSuppliesUseCase {
+        let supplies = (0..PAGE_SIZE + 2)
+            .map(|i| {
+                supply(
+                    &format!("brita-{:04}", i),
+                    "brita",
+                    vec![Price::new("m3", 8000)],
+                )
+            })
+            .chain([supply(
+                "cimento-id",
+                "Cimento",
+                vec![Price::new("sc", 123456), Price::new("kg", 250)],
+            )])
+            .collect();
+        ExportSuppliesUseCase::new(Arc::new(InMemorySupplyGateway::with(supplies)))
+    }
+
+    async fn export(options: ExportOptions) -> String {
+        let chunks = use_case()
+            .stream(options)
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        String::from_utf8(chunks.concat()).unwrap()
+    }
+
+    #[test]
+    fn formats_numbers_by_locale() {
+        assert_eq!(NumberLocale::En.format(dec!(1234567.5)), "1,234,567.50");
+        assert_eq!(NumberLocale::PtBr.format(dec!(1234.56)), "1.234,56");
+        assert_eq!(NumberLocale::PtBr.format(dec!(0.5)), "0,50");
+        assert_eq!(NumberLocale::En.format(dec!(-999.999)), "-1,000.00");
+    }
+
+    #[test]
+    fn parses_options_and_reports_every_invalid_one() {
+        let options = ExportOptions::parse(
+            Some("XLSX"),
+            Some("name, price"),
+            Some("pt-BR"),
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(options.format, ExportFormat::Xlsx);
+        assert_eq!(
+            options.columns,
+            vec![ExportColumn::Name, ExportColumn::Price]
+        );
+        assert_eq!(options.locale, NumberLocale::PtBr);
+
+        let defaults = ExportOptions::parse(None, None, None, Default::default()).unwrap();
+        assert_eq!(defaults.format, ExportFormat::Csv);
+        assert_eq!(defaults.columns, ExportColumn::ALL.to_vec());
+
+        let error = ExportOptions::parse(Some("pdf"), Some("name,cost"), None, Default::default())
+            .unwrap_err();
+        assert_eq!(
+            error.format_errors(),
+            vec![
+                "'format' must be csv, ndjson or xlsx, not 'pdf'",
+                "'columns' has no 'cost'"
+            ]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn streams_csv_across_pages_with_selected_columns() {
+        let options = ExportOptions::parse(
+            None,
+            Some("name,unit,price"),
+            Some("pt-BR"),
+            ExportFilter::default(),
+        )
+        .unwrap();
+
+        let csv = export(options).await;
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 1 + PAGE_SIZE as usize + 2 + 2);
+        assert_eq!(lines[0], "name;unit;price");
+        assert_eq!(lines[1], "Cimento;sc;1.234,56");
+        assert_eq!(lines[2], "Cimento;kg;2,50");
+        assert_eq!(lines[3], "brita;m3;80,00");
+    }
+
+    #[test]
+    fn ends_a_failed_export_with_an_error_line() {
+        let notification = Notification::with_one_error(CustomError::RepositoryError(
+            "connection reset".to_string(),
+        ));
+
+        assert_eq!(
+            ExportFormat::Csv.failure_line(&notification, Locale::En),
+            b"# export failed: RepositoryError: connection reset\n"
+        );
+        assert_eq!(
+            ExportFormat::Ndjson.failure_line(&notification, Locale::En),
+            b"{\"error\":\"RepositoryError: connection reset\"}\n"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn streams_filtered_ndjson() {
+        let filter = ExportFilter {
+            name: Some("ciMEN".to_string()),
+            unit: Some("sc".to_string()),
+        };
+        let options =
+            ExportOptions::parse(Some("ndjson"), Some("id,price,updated_at"), None, filter)
+                .unwrap();
+
+        let ndjson = export(options).await;
+
+        assert_eq!(
+            ndjson,
+            "{\"id\":\"cimento-id\",\"price\":1234.56,\"updated_at\":null}\n"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn writes_xlsx_workbooks() {
+        let path = std::env::temp_dir().join(format!("supplies-{}.xlsx", uuid::Uuid::new_v4()));
+        let options =
+            ExportOptions::parse(Some("xlsx"), None, None, ExportFilter::default()).unwrap();
+
+        use_case().write_xlsx(&options, &path).await.unwrap();
+
+        let workbook = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(workbook.starts_with(b"PK"));
+    }
+}
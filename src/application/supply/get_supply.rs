@@ -0,0 +1,40 @@
+use super::SupplyOutput;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use std::sync::Arc;
+
+pub struct GetSupplyUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+}
+
+impl GetSupplyUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(&self, id: &str) -> Result<SupplyOutput, Notification> {
+        self.gateway
+            .find_by_id(&SupplyId::from_str(id))
+            .await
+            .map_err(Notification::with_one_error)?
+            .map(|supply| SupplyOutput::from(&supply))
+            .ok_or_else(|| Notification::with_one_error(not_found(id)))
+    }
+}
+
+/// The supply `id` is at version `current` rather than the `expected` one.
+pub fn version_conflict(id: &str, current: i64, expected: i64) -> CustomError {
+    CustomError::VersionConflict(
+        Message::new("supply-version-conflict")
+            .arg("id", id)
+            .arg("current", current)
+            .arg("expected", expected),
+    )
+}
+
+pub fn not_found(id: &str) -> CustomError {
+    CustomError::NotFound(Message::new("supply-not-found").arg("id", id))
+}
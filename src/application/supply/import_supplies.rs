@@ -0,0 +1,675 @@
+use super::SupplyOutput;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::application::mapping_profile::MappedRow;
+use crate::application::mapping_profile::MappingProfile;
+use crate::application::supply::events;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::Supply;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// How the columns and the prices of a CSV file are written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CsvFormat {
+    /// A single ASCII character, such as `,`, `;` or a tab.
+    pub delimiter: String,
+    /// Prices are written as `1.234,56` instead of `1,234.56`.
+    pub decimal_comma: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: ",".to_string(),
+            decimal_comma: false,
+        }
+    }
+}
+
+impl CsvFormat {
+    pub fn delimiter_byte(&self) -> Option<u8> {
+        match self.delimiter.as_bytes() {
+            [delimiter] if delimiter.is_ascii() && *delimiter != b'"' => Some(*delimiter),
+            _ => None,
+        }
+    }
+
+    /// Reads `value` as a price with two decimal places, ignoring the
+    /// thousands separators.
+    pub fn parse_price(&self, value: &str) -> Option<Decimal> {
+        let (thousands, decimal) = if self.decimal_comma {
+            ('.', ',')
+        } else {
+            (',', '.')
+        };
+        let value = value
+            .trim()
+            .chars()
+            .filter(|c| *c != thousands && !c.is_whitespace())
+            .map(|c| if c == decimal { '.' } else { c })
+            .collect::<String>();
+        let mut value = Decimal::from_str_exact(&value).ok()?;
+        value.rescale(2);
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Created,
+    Updated,
+    /// The supply already has the price and name of the row.
+    Unchanged,
+    Rejected,
+    Skipped,
+}
+
+/// What happened to a row of the file, at `line` of it, and the supply and
+/// price it was read as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ImportedRow {
+    pub line: u64,
+    pub status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supply_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    /// Nothing was saved: the rows show what the import would do.
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub rejected: usize,
+    pub skipped: usize,
+    pub rows: Vec<ImportedRow>,
+}
+
+impl ImportReport {
+    fn new(rows: Vec<ImportedRow>, dry_run: bool) -> Self {
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+        Self {
+            dry_run,
+            created: count(ImportStatus::Created),
+            updated: count(ImportStatus::Updated),
+            unchanged: count(ImportStatus::Unchanged),
+            rejected: count(ImportStatus::Rejected),
+            skipped: count(ImportStatus::Skipped),
+            rows,
+        }
+    }
+}
+
+/// A price for `unit` of the supply `id`, or of the supply called `name`.
+struct PriceRow {
+    id: Option<String>,
+    name: String,
+    price: Price,
+}
+
+/// Imports a price list: each row of the CSV file sets the price of one unit
+/// of a supply, creating the supplies that do not exist yet. Rows are read
+/// through the mapping profile of the supplier, validated one by one and the
+/// valid ones saved together, unless it is a dry run.
+pub struct ImportSuppliesUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+    events: Arc<dyn SupplyEventPublisher>,
+}
+
+impl ImportSuppliesUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>, events: Arc<dyn SupplyEventPublisher>) -> Self {
+        Self { gateway, events }
+    }
+
+    /// The errors of rejected rows are reported in `locale`.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        file: &[u8],
+        profile: &MappingProfile,
+        dry_run: bool,
+        locale: Locale,
+    ) -> Result<ImportReport, Notification> {
+        profile.validate()?;
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(profile.format.delimiter_byte().unwrap_or(b','))
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(file);
+        let mut records = reader.records().skip(profile.skip.leading_rows);
+        let header = records
+            .next()
+            .transpose()
+            .map_err(|e| {
+                Notification::with_one_error(CustomError::Validation(
+                    Message::new("file-unreadable")
+                        .arg("error", e.to_string())
+                        .for_field("file"),
+                ))
+            })?
+            .ok_or_else(|| {
+                Notification::with_one_error(CustomError::Validation(
+                    Message::new("file-no-header").for_field("file"),
+                ))
+            })?;
+        let columns = profile.columns(&header)?;
+
+        let existing = self
+            .gateway
+            .find_all()
+            .await
+            .map_err(Notification::with_one_error)?;
+        let mut staged = Staged::new(existing);
+        let mut rows = Vec::new();
+
+        for record in records {
+            let line = match &record {
+                Ok(record) => record.position(),
+                Err(e) => e.position(),
+            }
+            .map(|p| p.line())
+            .unwrap_or_default();
+            let mut imported = ImportedRow {
+                line,
+                status: ImportStatus::Rejected,
+                supply_id: None,
+                name: None,
+                price: None,
+                errors: Vec::new(),
+            };
+            let row = match record {
+                Ok(record) if profile.skips(&record, &columns) => {
+                    imported.status = ImportStatus::Skipped;
+                    rows.push(imported);
+                    continue;
+                }
+                Ok(record) => profile.map_row(&record, &columns).and_then(price_row),
+                Err(e) => Err(Notification::with_one_error(CustomError::Validation(
+                    Message::new("row-unreadable").arg("error", e.to_string()),
+                ))),
+            };
+            match row.and_then(|row| staged.apply(context, row)) {
+                Ok((status, supply, price)) => {
+                    imported.status = status;
+                    imported.supply_id = Some(supply.get_id().get_value().to_string());
+                    imported.name = Some(supply.get_name().to_string());
+                    imported.price = Some(price);
+                }
+                Err(errors) => imported.errors = errors.format_errors_in(locale),
+            }
+            rows.push(imported);
+        }
+
+        if dry_run {
+            return Ok(ImportReport::new(rows, dry_run));
+        }
+        if !staged.changed.is_empty() {
+            let audit = |after: &Supply| {
+                let before = staged
+                    .loaded
+                    .get(after.get_id().get_value())
+                    .map(SupplyOutput::from);
+                let action = match before {
+                    Some(_) => AuditAction::Update,
+                    None => AuditAction::Create,
+                };
+                let output = SupplyOutput::from(after);
+                AuditEntry::new(
+                    context,
+                    action,
+                    AGGREGATE_TYPE,
+                    &output.id,
+                    before.as_ref(),
+                    Some(&output),
+                )
+            };
+            let saved = self
+                .gateway
+                .upsert_all(&staged.changed, &audit)
+                .await
+                .map_err(Notification::with_one_error)?;
+            for supply in saved {
+                self.publish(staged.loaded.get(supply.get_id().get_value()), &supply)
+                    .await;
+            }
+        }
+
+        Ok(ImportReport::new(rows, dry_run))
+    }
+
+    async fn publish(&self, before: Option<&Supply>, after: &Supply) {
+        let changes = match before {
+            Some(before) => [
+                SupplyEvent::price_changed(before, after),
+                SupplyEvent::renamed(before, after),
+            ],
+            None => [Some(SupplyEvent::created(after)), None],
+        };
+        for event in changes.into_iter().flatten() {
+            events::publish(self.events.as_ref(), event).await;
+        }
+    }
+}
+
+fn price_row(row: MappedRow) -> Result<PriceRow, Notification> {
+    let cents = i64::try_from(row.price.mantissa()).map_err(|_| {
+        Notification::with_one_error(CustomError::Validation(
+            Message::new("price-out-of-range").for_field("price"),
+        ))
+    })?;
+    Ok(PriceRow {
+        id: row.id,
+        name: row.name,
+        price: Price::new(&row.unit, cents),
+    })
+}
+
+/// The supplies as the rows applied so far left them.
+struct Staged {
+    /// Supplies as they were before the import, by id.
+    loaded: HashMap<String, Supply>,
+    /// Supplies the import creates or changes, in the order first touched.
+    changed: Vec<Supply>,
+    /// Where each supply of `changed` is, by id.
+    positions: HashMap<String, usize>,
+    /// Ids of the supplies as staged so far, by lowercase name.
+    names: HashMap<String, String>,
+}
+
+fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+impl Staged {
+    fn new(existing: Vec<Supply>) -> Self {
+        let mut names = HashMap::new();
+        for supply in &existing {
+            names
+                .entry(name_key(supply.get_name()))
+                .or_insert_with(|| supply.get_id().get_value().to_string());
+        }
+        Self {
+            loaded: existing
+                .into_iter()
+                .map(|supply| (supply.get_id().get_value().to_string(), supply))
+                .collect(),
+            changed: Vec::new(),
+            positions: HashMap::new(),
+            names,
+        }
+    }
+
+    /// The supply `id` as staged so far.
+    fn current(&self, id: &str) -> Option<&Supply> {
+        match self.positions.get(id) {
+            Some(index) => self.changed.get(*index),
+            None => self.loaded.get(id),
+        }
+    }
+
+    fn find(&self, row: &PriceRow) -> Result<Option<Supply>, Notification> {
+        let id = match &row.id {
+            Some(id) => Some(id),
+            None => self.names.get(&name_key(&row.name)),
+        };
+        let found = id.and_then(|id| self.current(id)).cloned();
+        match (&row.id, found) {
+            (Some(id), None) => Err(Notification::with_one_error(CustomError::Validation(
+                Message::new("supply-not-found")
+                    .arg("id", id.as_str())
+                    .for_field("id"),
+            ))),
+            (_, found) => Ok(found),
+        }
+    }
+
+    /// Stages `supply`, returning where it is in `changed`.
+    fn stage(&mut self, supply: Supply) -> usize {
+        let id = supply.get_id().get_value().to_string();
+        if let Some(previous) = self.current(&id).map(|s| name_key(s.get_name())) {
+            if self.names.get(&previous) == Some(&id) {
+                self.names.remove(&previous);
+            }
+        }
+        self.names
+            .insert(name_key(supply.get_name()), id.to_owned());
+        match self.positions.get(&id) {
+            Some(&index) => {
+                self.changed[index] = supply;
+                index
+            }
+            None => {
+                self.changed.push(supply);
+                self.positions.insert(id, self.changed.len() - 1);
+                self.changed.len() - 1
+            }
+        }
+    }
+
+    /// Sets the price of the row on its supply, validating the result. A
+    /// row the supply already matches is left out of `changed`, so saving
+    /// the import doesn't bump its version.
+    fn apply(
+        &mut self,
+        context: &AuditContext,
+        row: PriceRow,
+    ) -> Result<(ImportStatus, Supply, Price), Notification> {
+        let price = row.price.to_owned();
+        let supply = match self.find(&row)? {
+            Some(current)
+                if current.get_prices().contains(&row.price)
+                    && (row.name.is_empty() || row.name == current.get_name()) =>
+            {
+                return Ok((ImportStatus::Unchanged, current, price));
+            }
+            Some(current) => {
+                let mut prices = current.get_prices().to_owned();
+                match prices
+                    .iter_mut()
+                    .find(|price| price.get_unit() == row.price.get_unit())
+                {
+                    Some(price) => *price = row.price,
+                    None => prices.push(row.price),
+                }
+                let name = Some(row.name.as_str()).filter(|name| !name.is_empty());
+                current.update(name, Some(prices))?
+            }
+            None => Supply::new(context.tenant_id.to_owned(), &row.name, vec![row.price])?,
+        };
+
+        let status = if self.loaded.contains_key(supply.get_id().get_value()) {
+            ImportStatus::Updated
+        } else {
+            ImportStatus::Created
+        };
+        let index = self.stage(supply);
+        Ok((status, self.changed[index].to_owned(), price))
+    }
+}
+
+#[cfg(test)]
+mod import_supplies_tests {
+    use super::*;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::supply::supply_id::SupplyId;
+    use crate::domain::tenant_id::TenantId;
+    use rust_decimal_macros::dec;
+
+    fn context() -> AuditContext {
+        AuditContext::new("alice", TenantId::from_str("acme"), None)
+    }
+
+    #[test]
+    fn parses_prices_with_decimal_point_or_comma() {
+        let point = CsvFormat::default();
+        let comma = CsvFormat {
+            delimiter: ";".to_string(),
+            decimal_comma: true,
+        };
+
+        assert_eq!(point.parse_price("1,234.56"), Some(dec!(1234.56)));
+        assert_eq!(point.parse_price(" 25 "), Some(dec!(25.00)));
+        assert_eq!(comma.parse_price("1.234,56"), Some(dec!(1234.56)));
+        assert_eq!(comma.parse_price("0,5"), Some(dec!(0.50)));
+        assert_eq!(comma.parse_price("vinte"), None);
+        assert_eq!(point.parse_price(""), None);
+    }
+
+    #[rocket::async_test]
+    async fn imports_rows_and_reports_each_of_them() {
+        let existing = Supply::with(
+            SupplyId::from_str("areia-id"),
+            TenantId::from_str("acme"),
+            "Areia",
+            vec![Price::new("m3", 9000)],
+        )
+        .unwrap();
+        let gateway = Arc::new(InMemorySupplyGateway::with(vec![existing]));
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = ImportSuppliesUseCase::new(gateway.clone(), events.clone());
+        let file = "name;unit;price\n\
+                    cimento;sc;\"1.234,56\"\n\
+                    cimento;kg;2,5\n\
+                    areia;m3;95\n\
+                    brita;m3;vinte\n\
+                    ;sc;10\n\
+                    cimento;kg;2,50\n";
+
+        let report = use_case
+            .execute(
+                &context(),
+                file.as_bytes(),
+                &MappingProfile {
+                    format: CsvFormat {
+                        delimiter: ";".to_string(),
+                        decimal_comma: true,
+                    },
+                    ..MappingProfile::default()
+                },
+                false,
+                Locale::En,
+            )
+            .await
+            .unwrap();
+
+        let statuses = report
+            .rows
+            .iter()
+            .map(|row| (row.line, row.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                (2, ImportStatus::Created),
+                (3, ImportStatus::Created),
+                (4, ImportStatus::Updated),
+                (5, ImportStatus::Rejected),
+                (6, ImportStatus::Rejected),
+                (7, ImportStatus::Unchanged),
+            ]
+        );
+        assert_eq!(
+            (
+                report.created,
+                report.updated,
+                report.unchanged,
+                report.rejected
+            ),
+            (2, 1, 1, 2)
+        );
+        assert!(!report.dry_run);
+        assert_eq!(report.rows[0].supply_id, report.rows[1].supply_id);
+        assert_eq!(report.rows[2].supply_id.as_deref(), Some("areia-id"));
+        assert_eq!(
+            report.rows[3].errors,
+            vec!["'price' is not a valid number: 'vinte'"]
+        );
+        assert_eq!(
+            report.rows[4].errors,
+            vec![
+                "'name' should not be empty",
+                "'name' must be between 1 and 255 characters"
+            ]
+        );
+
+        let supplies = gateway.find_all().await.unwrap();
+        assert_eq!(supplies.len(), 2);
+        let cimento = supplies.iter().find(|s| s.get_name() == "cimento").unwrap();
+        assert_eq!(
+            cimento.get_prices(),
+            &vec![Price::new("sc", 123456), Price::new("kg", 250)]
+        );
+        let areia = supplies.iter().find(|s| s.get_name() == "areia").unwrap();
+        assert_eq!(areia.get_prices(), &vec![Price::new("m3", 9500)]);
+        assert_eq!(areia.get_version(), 2);
+
+        let entries = audit.find(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 2);
+
+        // Importing the same prices again changes nothing
+        let report = use_case
+            .execute(
+                &context(),
+                "name,unit,price\nareia,m3,95\n".as_bytes(),
+                &MappingProfile::default(),
+                false,
+                Locale::En,
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.rows[0].status, ImportStatus::Unchanged);
+        let areia = gateway
+            .find_by_id(&SupplyId::from_str("areia-id"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(areia.get_version(), 2);
+        assert_eq!(audit.find(&AuditFilter::default()).await.unwrap().len(), 2);
+        let names = events
+            .events()
+            .iter()
+            .map(|event| event.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["SupplyCreated", "SupplyPriceChanged", "SupplyRenamed"]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn rejects_files_without_the_required_columns() {
+        let gateway = Arc::new(InMemorySupplyGateway::default());
+        let use_case = ImportSuppliesUseCase::new(
+            gateway.clone(),
+            Arc::new(InMemorySupplyEventPublisher::default()),
+        );
+
+        let error = use_case
+            .execute(
+                &context(),
+                "name,valor\ncimento,25\n".as_bytes(),
+                &MappingProfile::default(),
+                false,
+                Locale::En,
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.format_errors(),
+            vec![
+                "'unit' column is missing from the header",
+                "'price' column is missing from the header"
+            ]
+        );
+        assert_eq!(gateway.len(), 0);
+    }
+
+    #[rocket::async_test]
+    async fn reports_row_errors_in_the_requested_locale() {
+        let use_case = ImportSuppliesUseCase::new(
+            Arc::new(InMemorySupplyGateway::default()),
+            Arc::new(InMemorySupplyEventPublisher::default()),
+        );
+
+        let report = use_case
+            .execute(
+                &context(),
+                "id,name,unit,price
+,cimento,sc,vinte
+nenhum,areia,m3,95
+"
+                .as_bytes(),
+                &MappingProfile::default(),
+                true,
+                Locale::PtBr,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report.rows[0].errors,
+            vec!["'price' não é um número válido: 'vinte'"]
+        );
+        assert_eq!(
+            report.rows[1].errors,
+            vec!["insumo com id 'nenhum' não foi encontrado"]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn previews_a_supplier_price_list_without_saving_it() {
+        let gateway = Arc::new(InMemorySupplyGateway::default());
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = ImportSuppliesUseCase::new(gateway.clone(), events.clone());
+        let profile: MappingProfile = serde_json::from_value(serde_json::json!({
+            "format": { "delimiter": ";", "decimal_comma": true },
+            "columns": { "id": null, "name": "Descrição", "unit": "Un.", "price": "Preço" },
+            "unit_aliases": { "SACO": "sc" },
+            "transforms": { "name": [{ "op": "lowercase" }] },
+            "skip": { "leading_rows": 1, "matching": [{ "column": "Descrição", "contains": "total" }] }
+        }))
+        .unwrap();
+        let file = "Tabela de preços;;\n\
+                    Descrição;Un.;Preço\n\
+                    Cimento CP2;Saco;32,90\n\
+                    Total;;32,90\n";
+
+        let report = use_case
+            .execute(&context(), file.as_bytes(), &profile, true, Locale::En)
+            .await
+            .unwrap();
+
+        let statuses = report
+            .rows
+            .iter()
+            .map(|row| (row.line, row.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![(3, ImportStatus::Created), (4, ImportStatus::Skipped)]
+        );
+        assert!(report.dry_run);
+        assert_eq!((report.created, report.skipped), (1, 1));
+        assert_eq!(report.rows[0].name.as_deref(), Some("cimento cp2"));
+        assert_eq!(report.rows[0].price, Some(Price::new("sc", 3290)));
+
+        assert_eq!(gateway.len(), 0);
+        assert!(audit
+            .find(&AuditFilter::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(events.events().is_empty());
+    }
+}
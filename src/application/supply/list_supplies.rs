@@ -0,0 +1,46 @@
+use super::SupplyOutput;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_gateway::SupplyPage;
+use crate::domain::validation::notification::Notification;
+use std::sync::Arc;
+
+pub struct ListSuppliesUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+}
+
+impl ListSuppliesUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>) -> Self {
+        Self { gateway }
+    }
+
+    pub async fn execute(&self) -> Result<Vec<SupplyOutput>, Notification> {
+        let supplies = self
+            .gateway
+            .find_all()
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        Ok(supplies.iter().map(SupplyOutput::from).collect())
+    }
+
+    /// The supplies on the supply list `list`.
+    pub async fn in_list(&self, list: &str) -> Result<Vec<SupplyOutput>, Notification> {
+        let supplies = self
+            .gateway
+            .find_in_list(list)
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        Ok(supplies.iter().map(SupplyOutput::from).collect())
+    }
+
+    pub async fn page(&self, page: &SupplyPage) -> Result<Vec<SupplyOutput>, Notification> {
+        let supplies = self
+            .gateway
+            .find_page(page)
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        Ok(supplies.iter().map(SupplyOutput::from).collect())
+    }
+}
@@ -0,0 +1,47 @@
+pub mod create_supply;
+pub mod delete_supply;
+pub mod events;
+pub mod export_supplies;
+pub mod get_supply;
+pub mod import_supplies;
+pub mod list_supplies;
+pub mod price_history;
+pub mod search_supplies;
+pub mod update_supply;
+
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::Supply;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+pub const AGGREGATE_TYPE: &str = "supply";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SupplyOutput {
+    pub id: String,
+    pub tenant_id: String,
+    pub name: String,
+    pub prices: Vec<Price>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i64,
+}
+
+impl From<&Supply> for SupplyOutput {
+    fn from(supply: &Supply) -> Self {
+        Self {
+            id: supply.get_id().get_value().to_string(),
+            tenant_id: supply.get_tenant_id().get_value().to_string(),
+            name: supply.get_name().to_string(),
+            prices: supply.get_prices().to_owned(),
+            created_at: supply.get_created_at().to_owned(),
+            updated_at: supply.get_updated_at().cloned(),
+            version: supply.get_version(),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditFilter;
+use crate::application::audit::AuditGateway;
+use crate::domain::supply::price::Price;
+use crate::domain::validation::notification::Notification;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+const HISTORY_LIMIT: i64 = 10_000;
+
+/// The prices a supply had from `occurred_at` on, as set by `actor`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PriceChange {
+    pub prices: Vec<Price>,
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Price history of supplies, read from the audit trail of their creations
+/// and updates.
+pub struct PriceHistoryUseCase {
+    audit: Arc<dyn AuditGateway>,
+}
+
+impl PriceHistoryUseCase {
+    pub fn new(audit: Arc<dyn AuditGateway>) -> Self {
+        Self { audit }
+    }
+
+    /// The changes of each of `supply_ids`, oldest first, in one query.
+    pub async fn execute(
+        &self,
+        supply_ids: &[String],
+    ) -> Result<HashMap<String, Vec<PriceChange>>, Notification> {
+        let mut history: HashMap<String, Vec<PriceChange>> = supply_ids
+            .iter()
+            .map(|id| (id.to_owned(), Vec::new()))
+            .collect();
+        if supply_ids.is_empty() {
+            return Ok(history);
+        }
+
+        let mut entries = self
+            .audit
+            .find(&AuditFilter {
+                aggregate_type: Some(AGGREGATE_TYPE.to_string()),
+                aggregate_ids: supply_ids.to_vec(),
+                limit: HISTORY_LIMIT,
+                ..AuditFilter::default()
+            })
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        entries.sort_by_key(|entry| (entry.occurred_at, entry.id));
+        for entry in entries {
+            let prices = |snapshot: &Option<Value>| {
+                snapshot
+                    .as_ref()
+                    .and_then(|snapshot| snapshot.get("prices"))
+                    .and_then(|prices| serde_json::from_value::<Vec<Price>>(prices.to_owned()).ok())
+            };
+            let Some(after) = prices(&entry.after) else {
+                continue;
+            };
+            if prices(&entry.before).as_ref() == Some(&after) {
+                continue;
+            }
+            if let Some(changes) = history.get_mut(&entry.aggregate_id) {
+                changes.push(PriceChange {
+                    prices: after,
+                    actor: entry.actor,
+                    occurred_at: entry.occurred_at,
+                });
+            }
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod price_history_tests {
+    use super::*;
+    use crate::application::audit::AuditContext;
+    use crate::application::supply::create_supply::CreateSupplyInput;
+    use crate::application::supply::create_supply::CreateSupplyUseCase;
+    use crate::application::supply::update_supply::UpdateSupplyInput;
+    use crate::application::supply::update_supply::UpdateSupplyUseCase;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::tenant_id::TenantId;
+
+    #[rocket::async_test]
+    async fn lists_price_changes_of_each_supply_oldest_first() {
+        let gateway = Arc::new(InMemorySupplyGateway::default());
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+        let create = CreateSupplyUseCase::new(gateway.clone(), events.clone());
+        let update = UpdateSupplyUseCase::new(gateway.clone(), events);
+
+        let cimento = create
+            .execute(
+                &context,
+                CreateSupplyInput {
+                    name: "cimento".to_string(),
+                    prices: vec![Price::new("sc", 25000)],
+                },
+            )
+            .await
+            .unwrap();
+        let areia = create
+            .execute(
+                &context,
+                CreateSupplyInput {
+                    name: "areia".to_string(),
+                    prices: vec![Price::new("m3", 9000)],
+                },
+            )
+            .await
+            .unwrap();
+        let renamed = UpdateSupplyInput {
+            name: Some("cimento cp2".to_string()),
+            prices: None,
+        };
+        update
+            .execute(&context, &cimento.id, None, renamed)
+            .await
+            .unwrap();
+        let repriced = UpdateSupplyInput {
+            name: None,
+            prices: Some(vec![Price::new("sc", 27000)]),
+        };
+        update
+            .execute(&context, &cimento.id, None, repriced)
+            .await
+            .unwrap();
+
+        let history = PriceHistoryUseCase::new(audit)
+            .execute(&[cimento.id.to_owned(), areia.id.to_owned()])
+            .await
+            .unwrap();
+
+        let prices = |id: &str| {
+            history[id]
+                .iter()
+                .map(|change| change.prices.to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            prices(&cimento.id),
+            vec![vec![Price::new("sc", 25000)], vec![Price::new("sc", 27000)]]
+        );
+        assert_eq!(prices(&areia.id), vec![vec![Price::new("m3", 9000)]]);
+    }
+}
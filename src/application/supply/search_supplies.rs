@@ -0,0 +1,140 @@
+use super::SupplyOutput;
+use crate::domain::supply::supply_gateway;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::validation::notification::Notification;
+use serde::Serialize;
+use std::sync::Arc;
+
+pub const PER_PAGE: usize = 20;
+
+/// Supplies whose name contains `query`, in any case, `per_page` at a time.
+/// Pages start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupplySearch {
+    pub query: Option<String>,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl Default for SupplySearch {
+    fn default() -> Self {
+        Self {
+            query: None,
+            page: 1,
+            per_page: PER_PAGE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SupplyPage {
+    pub supplies: Vec<SupplyOutput>,
+    pub page: usize,
+    /// At least 1, so an empty result still has a page to show.
+    pub pages: usize,
+    /// Supplies matching the query, on every page.
+    pub total: usize,
+}
+
+pub struct SearchSuppliesUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+}
+
+impl SearchSuppliesUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>) -> Self {
+        Self { gateway }
+    }
+
+    /// Pages past the last one are clamped to it. The matches are counted
+    /// and paged by the gateway, so only the page shown is read.
+    pub async fn execute(&self, search: &SupplySearch) -> Result<SupplyPage, Notification> {
+        let name = search
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|query| !query.is_empty())
+            .map(str::to_string);
+        let mut filter = supply_gateway::SupplyPage {
+            name,
+            ..supply_gateway::SupplyPage::default()
+        };
+        let total = self
+            .gateway
+            .count(&filter)
+            .await
+            .map_err(Notification::with_one_error)? as usize;
+
+        let per_page = search.per_page.max(1);
+        let pages = total.div_ceil(per_page).max(1);
+        let page = search.page.clamp(1, pages);
+        filter.limit = per_page as i64;
+        filter.offset = ((page - 1) * per_page) as i64;
+        let supplies = self
+            .gateway
+            .find_page(&filter)
+            .await
+            .map_err(Notification::with_one_error)?;
+        Ok(SupplyPage {
+            supplies: supplies.iter().map(SupplyOutput::from).collect(),
+            page,
+            pages,
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod search_supplies_tests {
+    use super::*;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::supply::price::Price;
+    use crate::domain::supply::Supply;
+    use crate::domain::tenant_id::TenantId;
+
+    #[rocket::async_test]
+    async fn filters_by_name_and_pages_the_matches() {
+        let supplies = ["Areia fina", "Areia grossa", "Cimento", "areia média"]
+            .into_iter()
+            .map(|name| {
+                Supply::new(
+                    TenantId::from_str("acme"),
+                    name,
+                    vec![Price::new("m3", 9000)],
+                )
+                .unwrap()
+            })
+            .collect();
+        let gateway = Arc::new(InMemorySupplyGateway::with(supplies));
+        let use_case = SearchSuppliesUseCase::new(gateway);
+        let names = |page: &SupplyPage| {
+            page.supplies
+                .iter()
+                .map(|supply| supply.name.to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let search = SupplySearch {
+            query: Some(" AREIA ".to_string()),
+            page: 2,
+            per_page: 2,
+        };
+        let page = use_case.execute(&search).await.unwrap();
+        assert_eq!(names(&page), vec!["areia média"]);
+        assert_eq!((page.page, page.pages, page.total), (2, 2, 3));
+
+        let search = SupplySearch {
+            page: 9,
+            ..SupplySearch::default()
+        };
+        let page = use_case.execute(&search).await.unwrap();
+        assert_eq!((page.page, page.pages, page.total), (1, 1, 4));
+
+        let search = SupplySearch {
+            query: Some("tijolo".to_string()),
+            ..SupplySearch::default()
+        };
+        let page = use_case.execute(&search).await.unwrap();
+        assert!(page.supplies.is_empty());
+        assert_eq!((page.page, page.pages), (1, 1));
+    }
+}
@@ -0,0 +1,207 @@
+use super::get_supply::not_found;
+use super::get_supply::version_conflict;
+use super::SupplyOutput;
+use super::AGGREGATE_TYPE;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::application::supply::events;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::supply::Supply;
+use crate::domain::validation::notification::Notification;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSupplyInput {
+    pub name: Option<String>,
+    pub prices: Option<Vec<Price>>,
+}
+
+pub struct UpdateSupplyUseCase {
+    gateway: Arc<dyn SupplyGateway>,
+    events: Arc<dyn SupplyEventPublisher>,
+}
+
+impl UpdateSupplyUseCase {
+    pub fn new(gateway: Arc<dyn SupplyGateway>, events: Arc<dyn SupplyEventPublisher>) -> Self {
+        Self { gateway, events }
+    }
+
+    /// Updates the supply `id`. When `expected_version` is given the update
+    /// only applies to that version of the supply.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        id: &str,
+        expected_version: Option<i64>,
+        input: UpdateSupplyInput,
+    ) -> Result<SupplyOutput, Notification> {
+        let current = self
+            .gateway
+            .find_by_id(&SupplyId::from_str(id))
+            .await
+            .map_err(Notification::with_one_error)?
+            .ok_or_else(|| Notification::with_one_error(not_found(id)))?;
+
+        if let Some(expected) = expected_version {
+            if expected != current.get_version() {
+                return Err(Notification::with_one_error(version_conflict(
+                    id,
+                    current.get_version(),
+                    expected,
+                )));
+            }
+        }
+
+        let supply = current.update(input.name.as_deref(), input.prices)?;
+        let before = SupplyOutput::from(&current);
+        let audit = |updated: &Supply| {
+            let output = SupplyOutput::from(updated);
+            AuditEntry::new(
+                context,
+                AuditAction::Update,
+                AGGREGATE_TYPE,
+                &output.id,
+                Some(&before),
+                Some(&output),
+            )
+        };
+        let updated = self
+            .gateway
+            .update(&supply, &audit)
+            .await
+            .map_err(Notification::with_one_error)?;
+
+        let output = SupplyOutput::from(&updated);
+        let changes = [
+            SupplyEvent::price_changed(&current, &updated),
+            SupplyEvent::renamed(&current, &updated),
+        ];
+        for event in changes.into_iter().flatten() {
+            events::publish(self.events.as_ref(), event).await;
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod update_supply_tests {
+    use super::*;
+    use crate::application::audit::AuditFilter;
+    use crate::application::audit::AuditGateway;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::tenant_id::TenantId;
+    use crate::domain::validation::error::CustomError;
+    use crate::domain::validation::validation_handler::ValidationHandler;
+
+    fn tenant() -> TenantId {
+        TenantId::from_str("acme")
+    }
+
+    #[rocket::async_test]
+    async fn update_price_and_audit_before_and_after() {
+        let supply = Supply::new(tenant(), "cimento", vec![Price::new("sc", 25000)]).unwrap();
+        let gateway = Arc::new(InMemorySupplyGateway::with(vec![supply.clone()]));
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = UpdateSupplyUseCase::new(gateway, events.clone());
+        let id = SupplyOutput::from(&supply).id;
+
+        let output = use_case
+            .execute(
+                &AuditContext::new("bob", tenant(), None),
+                &id,
+                Some(supply.get_version()),
+                UpdateSupplyInput {
+                    name: None,
+                    prices: Some(vec![Price::new("sc", 27000)]),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.name, "cimento");
+        assert_eq!(output.prices[0].get_value_formatted(), "270.00");
+        assert!(output.updated_at.is_some());
+        assert_eq!(output.version, supply.get_version() + 1);
+
+        let entries = audit.find(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::Update);
+        assert_eq!(
+            entries[0].before.as_ref().unwrap()["prices"][0]["value"],
+            250.0
+        );
+        assert_eq!(
+            entries[0].after.as_ref().unwrap()["prices"][0]["value"],
+            270.0
+        );
+
+        let events = events.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "SupplyPriceChanged");
+    }
+
+    #[rocket::async_test]
+    async fn update_an_unknown_supply_returns_not_found() {
+        let use_case = UpdateSupplyUseCase::new(
+            Arc::new(InMemorySupplyGateway::default()),
+            Arc::new(InMemorySupplyEventPublisher::default()),
+        );
+
+        let error = use_case
+            .execute(
+                &AuditContext::new("bob", tenant(), None),
+                "missing",
+                None,
+                UpdateSupplyInput::default(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.get_first_error(),
+            Some(CustomError::NotFound(_))
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn update_a_stale_version_is_rejected_without_audit() {
+        let supply = Supply::new(tenant(), "cimento", vec![Price::new("sc", 25000)]).unwrap();
+        let gateway = Arc::new(InMemorySupplyGateway::with(vec![supply.clone()]));
+        let audit = gateway.audit();
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let use_case = UpdateSupplyUseCase::new(gateway, events.clone());
+        let id = SupplyOutput::from(&supply).id;
+        let context = AuditContext::new("bob", tenant(), None);
+        let input = UpdateSupplyInput {
+            name: Some("cimento cp2".to_string()),
+            prices: None,
+        };
+
+        use_case
+            .execute(&context, &id, Some(supply.get_version()), input.clone())
+            .await
+            .unwrap();
+        let error = use_case
+            .execute(&context, &id, Some(supply.get_version()), input)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.get_first_error(),
+            Some(CustomError::VersionConflict(_))
+        ));
+        assert_eq!(audit.find(&AuditFilter::default()).await.unwrap().len(), 1);
+        assert_eq!(events.events().len(), 1);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::application::attachment::Attachment;
+use crate::application::attachment::AttachmentGateway;
+use crate::application::attachment::ObjectStorage;
+use crate::application::audit::AuditEntry;
+use crate::application::audit::AuditFilter;
+use crate::application::audit::AuditGateway;
+use crate::application::mapping_profile::MappingProfileGateway;
+use crate::application::mapping_profile::SupplierProfile;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::application::supply::get_supply::not_found;
+use crate::application::supply::get_supply::version_conflict;
+use crate::domain::audit::Audit;
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_gateway::SupplyKey;
+use crate::domain::supply::supply_gateway::SupplyPage;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::supply::Supply;
+use crate::domain::validation::error::CustomError;
+use crate::i18n::Message;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Supplies in memory, auditing their changes to an in-memory audit log the
+/// way the Postgres gateway does in the transaction of the change.
+#[derive(Default)]
+pub struct InMemorySupplyGateway {
+    supplies: Mutex<Vec<Supply>>,
+    lists: Mutex<Vec<(String, SupplyId)>>,
+    audit: Arc<InMemoryAuditGateway>,
+}
+
+impl InMemorySupplyGateway {
+    pub fn with(supplies: Vec<Supply>) -> Self {
+        Self {
+            supplies: Mutex::new(supplies),
+            ..Default::default()
+        }
+    }
+
+    pub fn auditing_to(mut self, audit: Arc<InMemoryAuditGateway>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn audit(&self) -> Arc<InMemoryAuditGateway> {
+        self.audit.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.supplies.lock().unwrap().len()
+    }
+}
+
+fn next_version(supply: &Supply) -> Supply {
+    Supply::restore(
+        supply.get_id().to_owned(),
+        supply.get_tenant_id().to_owned(),
+        supply.get_name().to_string(),
+        supply.get_prices().to_owned(),
+        supply.get_created_at().to_owned(),
+        supply.get_updated_at().cloned(),
+        supply.get_version() + 1,
+    )
+}
+
+#[async_trait]
+impl SupplyGateway for InMemorySupplyGateway {
+    async fn create(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError> {
+        let mut supplies = self.supplies.lock().unwrap();
+        self.audit.append(&[audit(supply)])?;
+        supplies.push(supply.to_owned());
+        Ok(supply.to_owned())
+    }
+
+    async fn find_by_id(&self, id: &SupplyId) -> Result<Option<Supply>, CustomError> {
+        let supplies = self.supplies.lock().unwrap();
+        Ok(supplies.iter().find(|s| s.get_id() == id).cloned())
+    }
+
+    async fn find_all(&self) -> Result<Vec<Supply>, CustomError> {
+        Ok(self.supplies.lock().unwrap().to_owned())
+    }
+
+    async fn find_page(&self, page: &SupplyPage) -> Result<Vec<Supply>, CustomError> {
+        let mut supplies = self
+            .supplies
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| {
+                page.after
+                    .as_ref()
+                    .is_none_or(|after| SupplyKey::from(*s) > *after)
+            })
+            .filter(|s| {
+                page.before
+                    .as_ref()
+                    .is_none_or(|before| SupplyKey::from(*s) < *before)
+            })
+            .filter(|s| page.matches(s))
+            .cloned()
+            .collect::<Vec<_>>();
+        supplies.sort_by_key(|s| SupplyKey::from(s));
+        let (limit, offset) = (page.limit as usize, page.offset as usize);
+        if page.from_end {
+            supplies.truncate(supplies.len().saturating_sub(offset));
+            supplies.drain(..supplies.len().saturating_sub(limit));
+        } else {
+            supplies.drain(..offset.min(supplies.len()));
+            supplies.truncate(limit);
+        }
+        Ok(supplies)
+    }
+
+    async fn count(&self, page: &SupplyPage) -> Result<i64, CustomError> {
+        let supplies = self.supplies.lock().unwrap();
+        Ok(supplies.iter().filter(|s| page.matches(s)).count() as i64)
+    }
+
+    async fn update(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError> {
+        let mut supplies = self.supplies.lock().unwrap();
+        match supplies.iter_mut().find(|s| s.get_id() == supply.get_id()) {
+            Some(current) if current.get_version() != supply.get_version() => {
+                Err(version_conflict(
+                    current.get_id().get_value(),
+                    current.get_version(),
+                    supply.get_version(),
+                ))
+            }
+            Some(current) => {
+                let updated = next_version(supply);
+                self.audit.append(&[audit(&updated)])?;
+                *current = updated;
+                Ok(current.to_owned())
+            }
+            None => Err(not_found(supply.get_id().get_value())),
+        }
+    }
+
+    async fn delete_by_id(&self, id: &SupplyId, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut supplies = self.supplies.lock().unwrap();
+        if supplies.iter().any(|s| s.get_id() == id) {
+            self.audit.append(std::slice::from_ref(audit))?;
+        }
+        supplies.retain(|s| s.get_id() != id);
+        self.lists
+            .lock()
+            .unwrap()
+            .retain(|(_, listed)| listed != id);
+        Ok(())
+    }
+
+    async fn find_in_list(&self, list: &str) -> Result<Vec<Supply>, CustomError> {
+        let lists = self.lists.lock().unwrap();
+        let mut supplies = self
+            .supplies
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| lists.iter().any(|(l, id)| l == list && id == s.get_id()))
+            .cloned()
+            .collect::<Vec<_>>();
+        supplies.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        Ok(supplies)
+    }
+
+    async fn add_to_list(&self, list: &str, id: &SupplyId) -> Result<Option<Supply>, CustomError> {
+        let supply = self.find_by_id(id).await?;
+        let mut lists = self.lists.lock().unwrap();
+        if supply.is_some() && !lists.iter().any(|(l, listed)| l == list && listed == id) {
+            lists.push((list.to_string(), id.to_owned()));
+        }
+        Ok(supply)
+    }
+
+    async fn remove_from_list(&self, list: &str, id: &SupplyId) -> Result<(), CustomError> {
+        self.lists
+            .lock()
+            .unwrap()
+            .retain(|(l, listed)| l != list || listed != id);
+        Ok(())
+    }
+
+    async fn upsert_all(
+        &self,
+        supplies: &[Supply],
+        audit: Audit<'_, Supply>,
+    ) -> Result<Vec<Supply>, CustomError> {
+        let mut stored = self.supplies.lock().unwrap();
+        let conflicts = supplies
+            .iter()
+            .filter(|supply| {
+                stored.iter().any(|s| {
+                    s.get_id() == supply.get_id() && s.get_version() != supply.get_version()
+                })
+            })
+            .count();
+        if conflicts > 0 {
+            return Err(CustomError::VersionConflict(
+                Message::new("supplies-changed").arg("count", conflicts),
+            ));
+        }
+
+        let saved = supplies
+            .iter()
+            .map(
+                |supply| match stored.iter().any(|s| s.get_id() == supply.get_id()) {
+                    true => next_version(supply),
+                    false => supply.to_owned(),
+                },
+            )
+            .collect::<Vec<_>>();
+        self.audit
+            .append(&saved.iter().map(audit).collect::<Vec<_>>())?;
+        for supply in &saved {
+            match stored.iter_mut().find(|s| s.get_id() == supply.get_id()) {
+                Some(current) => *current = supply.to_owned(),
+                None => stored.push(supply.to_owned()),
+            }
+        }
+        Ok(saved)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryAuditGateway {
+    entries: Mutex<Vec<AuditEntry>>,
+    failing: bool,
+}
+
+impl InMemoryAuditGateway {
+    /// An audit log that can't be written to.
+    pub fn failing() -> Self {
+        Self {
+            failing: true,
+            ..Default::default()
+        }
+    }
+
+    fn append(&self, new: &[AuditEntry]) -> Result<(), CustomError> {
+        if self.failing {
+            return Err(CustomError::RepositoryError(
+                "audit log is unavailable".to_string(),
+            ));
+        }
+        let mut entries = self.entries.lock().unwrap();
+        for entry in new {
+            let mut entry = entry.to_owned();
+            entry.id = Some(entries.len() as i64 + 1);
+            entries.push(entry);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditGateway for InMemoryAuditGateway {
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, CustomError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .iter()
+            .filter(|entry| matches(filter, entry))
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemorySupplyEventPublisher {
+    events: Mutex<Vec<SupplyEvent>>,
+}
+
+impl InMemorySupplyEventPublisher {
+    pub fn events(&self) -> Vec<SupplyEvent> {
+        self.events.lock().unwrap().to_owned()
+    }
+}
+
+#[async_trait]
+impl SupplyEventPublisher for InMemorySupplyEventPublisher {
+    async fn publish(&self, event: &SupplyEvent) -> Result<(), CustomError> {
+        self.events.lock().unwrap().push(event.to_owned());
+        Ok(())
+    }
+}
+
+fn matches(filter: &AuditFilter, entry: &AuditEntry) -> bool {
+    let eq = |expected: &Option<String>, value: &str| {
+        expected.as_deref().is_none_or(|expected| expected == value)
+    };
+    eq(&filter.actor, &entry.actor)
+        && filter.action.is_none_or(|action| action == entry.action)
+        && eq(&filter.aggregate_type, &entry.aggregate_type)
+        && eq(&filter.aggregate_id, &entry.aggregate_id)
+        && (filter.aggregate_ids.is_empty() || filter.aggregate_ids.contains(&entry.aggregate_id))
+        && filter
+            .request_id
+            .as_deref()
+            .is_none_or(|expected| entry.request_id.as_deref() == Some(expected))
+        && filter.from.is_none_or(|from| entry.occurred_at >= from)
+        && filter.to.is_none_or(|to| entry.occurred_at <= to)
+}
+
+#[derive(Default)]
+pub struct InMemoryMappingProfileGateway {
+    profiles: Mutex<Vec<SupplierProfile>>,
+    audit: Arc<InMemoryAuditGateway>,
+}
+
+impl InMemoryMappingProfileGateway {
+    pub fn auditing_to(mut self, audit: Arc<InMemoryAuditGateway>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn audit(&self) -> Arc<InMemoryAuditGateway> {
+        self.audit.clone()
+    }
+}
+
+#[async_trait]
+impl MappingProfileGateway for InMemoryMappingProfileGateway {
+    async fn save(
+        &self,
+        profile: &SupplierProfile,
+        audit: Audit<'_, SupplierProfile>,
+    ) -> Result<SupplierProfile, CustomError> {
+        let mut profiles = self.profiles.lock().unwrap();
+        self.audit.append(&[audit(profile)])?;
+        profiles.retain(|p| p.supplier != profile.supplier);
+        profiles.push(profile.to_owned());
+        Ok(profile.to_owned())
+    }
+
+    async fn find(&self, supplier: &str) -> Result<Option<SupplierProfile>, CustomError> {
+        let profiles = self.profiles.lock().unwrap();
+        Ok(profiles.iter().find(|p| p.supplier == supplier).cloned())
+    }
+
+    async fn find_all(&self) -> Result<Vec<SupplierProfile>, CustomError> {
+        let mut profiles = self.profiles.lock().unwrap().to_owned();
+        profiles.sort_by(|a, b| a.supplier.cmp(&b.supplier));
+        Ok(profiles)
+    }
+
+    async fn delete(&self, supplier: &str, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut profiles = self.profiles.lock().unwrap();
+        if profiles.iter().any(|p| p.supplier == supplier) {
+            self.audit.append(std::slice::from_ref(audit))?;
+        }
+        profiles.retain(|p| p.supplier != supplier);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryAttachmentGateway {
+    attachments: Mutex<Vec<Attachment>>,
+    audit: Arc<InMemoryAuditGateway>,
+}
+
+impl InMemoryAttachmentGateway {
+    pub fn with(attachments: Vec<Attachment>) -> Self {
+        Self {
+            attachments: Mutex::new(attachments),
+            ..Default::default()
+        }
+    }
+
+    pub fn auditing_to(mut self, audit: Arc<InMemoryAuditGateway>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn audit(&self) -> Arc<InMemoryAuditGateway> {
+        self.audit.clone()
+    }
+}
+
+#[async_trait]
+impl AttachmentGateway for InMemoryAttachmentGateway {
+    async fn create(
+        &self,
+        attachment: &Attachment,
+        audit: Audit<'_, Attachment>,
+    ) -> Result<Attachment, CustomError> {
+        let mut attachments = self.attachments.lock().unwrap();
+        self.audit.append(&[audit(attachment)])?;
+        attachments.push(attachment.to_owned());
+        Ok(attachment.to_owned())
+    }
+
+    async fn find(&self, supply_id: &str, id: &str) -> Result<Option<Attachment>, CustomError> {
+        let attachments = self.attachments.lock().unwrap();
+        Ok(attachments
+            .iter()
+            .find(|a| a.supply_id == supply_id && a.id == id)
+            .cloned())
+    }
+
+    async fn find_by_supply(&self, supply_id: &str) -> Result<Vec<Attachment>, CustomError> {
+        let attachments = self.attachments.lock().unwrap();
+        Ok(attachments
+            .iter()
+            .filter(|a| a.supply_id == supply_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, id: &str, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut attachments = self.attachments.lock().unwrap();
+        if attachments.iter().any(|a| a.id == id) {
+            self.audit.append(std::slice::from_ref(audit))?;
+        }
+        attachments.retain(|a| a.id != id);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryObjectStorage {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryObjectStorage {
+    pub fn len(&self) -> usize {
+        self.objects.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for InMemoryObjectStorage {
+    async fn put(&self, key: &str, content: &[u8], _: &str) -> Result<(), CustomError> {
+        let mut objects = self.objects.lock().unwrap();
+        objects.insert(key.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CustomError> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CustomError> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use crate::application::attachment::delete_attachment::DeleteAttachmentUseCase;
+use crate::application::attachment::download_attachment::DownloadAttachmentUseCase;
+use crate::application::attachment::download_derivative::DownloadDerivativeUseCase;
+use crate::application::attachment::generate_derivatives::GenerateDerivativesUseCase;
+use crate::application::attachment::list_attachments::ListAttachmentsUseCase;
+use crate::application::attachment::upload_attachment::UploadAttachmentInput;
+use crate::application::attachment::upload_attachment::UploadAttachmentUseCase;
+use crate::application::attachment::Attachment;
+use crate::application::attachment::AttachmentGateway;
+use crate::application::attachment::ObjectStorage;
+use crate::application::audit::AuditContext;
+use crate::domain::tenant_id::TenantId;
+use crate::infra::db::attachment_gateway::AttachmentPostgresGateway;
+use crate::infra::db::DbSqlx;
+use crate::infra::storage::StorageConfig;
+use crate::middler::payload::Payload;
+use crate::middler::preconditions::IfNoneMatch;
+use crate::middler::preconditions::Tagged;
+use crate::middler::tenant::Tenant;
+use crate::problem::Problem;
+use crate::supplies::supply_gateway;
+use rocket::delete;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::fs::TempFile;
+use rocket::get;
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::post;
+use rocket::response;
+use rocket::response::status::Created;
+use rocket::response::status::NoContent;
+use rocket::response::Responder;
+use rocket::tokio;
+use rocket::tokio::io::AsyncReadExt;
+use rocket::tokio::sync::Semaphore;
+use rocket::Build;
+use rocket::Request;
+use rocket::Response;
+use rocket::Rocket;
+use rocket::State;
+use std::sync::Arc;
+use tracing::error;
+use tracing::info;
+
+type Result<T, E = Problem> = std::result::Result<T, E>;
+
+/// Photos rendered at once; the others wait for a permit.
+const DERIVATIVE_WORKERS: usize = 2;
+
+/// Derivatives never change once rendered, as an attachment never changes.
+const IMMUTABLE: &str = "private, max-age=31536000, immutable";
+
+/// The storage of attachment contents, managed by the `Attachments` fairing.
+pub struct AttachmentStorage(pub Arc<dyn ObjectStorage>);
+
+/// Renders the derivatives of uploaded photos in the background, a few at a
+/// time.
+pub struct DerivativeJobs(Arc<Semaphore>);
+
+impl Default for DerivativeJobs {
+    fn default() -> Self {
+        Self(Arc::new(Semaphore::new(DERIVATIVE_WORKERS)))
+    }
+}
+
+impl DerivativeJobs {
+    /// Failures are logged: the photo is saved, and only its derivatives are
+    /// missing.
+    pub fn generate(
+        &self,
+        storage: Arc<dyn ObjectStorage>,
+        attachment: Attachment,
+        content: Vec<u8>,
+    ) {
+        let permits = self.0.clone();
+        tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            match GenerateDerivativesUseCase::new(storage)
+                .execute(&attachment, content)
+                .await
+            {
+                Ok(0) => {}
+                Ok(count) => {
+                    info!(attachment = %attachment.id, count, "generated attachment derivatives")
+                }
+                Err(e) => error!(
+                    attachment = %attachment.id,
+                    errors = ?e.format_errors(),
+                    "failed to generate attachment derivatives"
+                ),
+            }
+        });
+    }
+}
+
+/// Builds the attachment storage from `[attachments]` in Rocket.toml, the
+/// local one when the table is missing.
+pub struct Attachments;
+
+#[rocket::async_trait]
+impl Fairing for Attachments {
+    fn info(&self) -> Info {
+        Info {
+            name: "Attachment storage",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket
+            .figment()
+            .extract_inner::<StorageConfig>("attachments")
+        {
+            Ok(config) => config,
+            Err(e) if e.missing() => StorageConfig::default(),
+            Err(e) => {
+                error!("invalid attachments config: {}", e);
+                return Err(rocket);
+            }
+        };
+        match config.build() {
+            Ok(storage) => Ok(rocket
+                .manage(AttachmentStorage(storage))
+                .manage(DerivativeJobs::default())),
+            Err(e) => {
+                error!("invalid attachments config: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+}
+
+pub(crate) fn attachment_gateway(db: &DbSqlx, tenant_id: &TenantId) -> Arc<dyn AttachmentGateway> {
+    Arc::new(AttachmentPostgresGateway::new(
+        db.pool(),
+        tenant_id.to_owned(),
+    ))
+}
+
+/// The content of an attachment, shown inline under its file name.
+pub struct AttachmentFile(Attachment, Vec<u8>);
+
+impl<'r> Responder<'r, 'static> for AttachmentFile {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let AttachmentFile(attachment, content) = self;
+        Response::build_from(content.respond_to(req)?)
+            .raw_header("Content-Type", attachment.content_type)
+            .raw_header(
+                "Content-Disposition",
+                content_disposition(&attachment.file_name),
+            )
+            .ok()
+    }
+}
+
+/// A response browsers and proxies may keep for a year without asking again.
+pub struct Immutable<R>(R);
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for Immutable<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        Response::build_from(self.0.respond_to(req)?)
+            .raw_header("Cache-Control", IMMUTABLE)
+            .ok()
+    }
+}
+
+/// An ASCII `filename` for old clients and the UTF-8 `filename*` of RFC 6266.
+fn content_disposition(file_name: &str) -> String {
+    let ascii = file_name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect::<String>();
+    let encoded = file_name
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect::<String>();
+    format!(
+        "inline; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii, encoded
+    )
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("name" = Option<String>, Query, description = "File name shown on download, made from the id when missing")
+    ),
+    request_body(content(("application/pdf"), ("image/jpeg"), ("image/png"), ("image/webp")),
+        description = "The file. Its type is recognized from its content and must match the Content-Type when one is sent"),
+    responses(
+        (status = 201, description = "Attachment stored. The derivatives of photos are rendered afterwards", body = Attachment),
+        (status = 404, description = "Supply not found", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "The file is larger than the limit of its type", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The file is empty, of an unsupported type or mislabeled", body = Problem, content_type = "application/problem+json"),
+        (status = 502, description = "The storage failed", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[post("/<id>/attachments?<name>", data = "<file>")]
+pub async fn upload(
+    db: &State<DbSqlx>,
+    storage: &State<AttachmentStorage>,
+    jobs: &State<DerivativeJobs>,
+    context: AuditContext,
+    id: &str,
+    name: Option<String>,
+    file: TempFile<'_>,
+) -> Result<Created<Payload<Attachment>>> {
+    let content_type = file
+        .content_type()
+        .map(|content_type| format!("{}/{}", content_type.top(), content_type.sub()));
+    let mut content = Vec::new();
+    let read = match file.open().await {
+        Ok(mut reader) => reader.read_to_end(&mut content).await,
+        Err(e) => Err(e),
+    };
+    read.map_err(|e| Problem::new(Status::BadRequest, &format!("unreadable file: {}", e)))?;
+
+    let input = UploadAttachmentInput {
+        name,
+        content_type,
+        content,
+    };
+    let tenant_id = &context.tenant_id;
+    let attachment = UploadAttachmentUseCase::new(
+        supply_gateway(db, tenant_id),
+        attachment_gateway(db, tenant_id),
+        storage.0.clone(),
+    )
+    .execute(&context, id, &input)
+    .await
+    .map_err(Problem::from)?;
+    jobs.generate(storage.0.clone(), attachment.clone(), input.content);
+
+    Ok(
+        Created::new(format!("/supplies/{}/attachments/{}", id, attachment.id))
+            .body(Payload(attachment)),
+    )
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(("id" = String, Path, description = "Supply id")),
+    responses(
+        (status = 200, description = "Attachments of the supply, oldest first", body = Vec<Attachment>),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/<id>/attachments", rank = 2)]
+pub async fn list(
+    db: &State<DbSqlx>,
+    tenant: Tenant,
+    id: &str,
+) -> Result<Payload<Vec<Attachment>>> {
+    ListAttachmentsUseCase::new(attachment_gateway(db, &tenant.0))
+        .execute(id)
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already holds")
+    ),
+    responses(
+        (status = 200, description = "The file",
+            content(("application/pdf"), ("image/jpeg"), ("image/png"), ("image/webp")),
+            headers(("ETag" = String, description = "Checksum of the file"))),
+        (status = 304, description = "The file still has the ETag sent in If-None-Match"),
+        (status = 404, description = "Attachment not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "The file is missing from the storage or no longer matches its checksum", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/<id>/attachments/<attachment_id>")]
+pub async fn download(
+    db: &State<DbSqlx>,
+    storage: &State<AttachmentStorage>,
+    tenant: Tenant,
+    id: &str,
+    attachment_id: &str,
+    if_none_match: IfNoneMatch,
+) -> Result<Tagged<AttachmentFile>> {
+    DownloadAttachmentUseCase::new(attachment_gateway(db, &tenant.0), storage.0.clone())
+        .execute(id, attachment_id)
+        .await
+        .map(|(attachment, content)| {
+            let etag = format!("\"{}\"", attachment.checksum);
+            Tagged::unless_cached(etag, AttachmentFile(attachment, content), &if_none_match)
+        })
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+        ("derivative" = String, Path, description = "One of thumbnail.jpg, thumbnail.webp (320 px), display.jpg or display.webp (1280 px)"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already holds")
+    ),
+    responses(
+        (status = 200, description = "The resized photo, upright and without its EXIF metadata",
+            content(("image/jpeg"), ("image/webp")),
+            headers(
+                ("ETag" = String, description = "Checksum of the original and name of the derivative"),
+                ("Cache-Control" = String, description = "Cached for a year, as derivatives never change")
+            )),
+        (status = 304, description = "The derivative still has the ETag sent in If-None-Match"),
+        (status = 404, description = "Attachment not found, not a photo, or its derivatives are not rendered yet", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/<id>/attachments/<attachment_id>/<derivative>")]
+pub async fn derivative(
+    db: &State<DbSqlx>,
+    storage: &State<AttachmentStorage>,
+    tenant: Tenant,
+    id: &str,
+    attachment_id: &str,
+    derivative: &str,
+    if_none_match: IfNoneMatch,
+) -> Result<Immutable<Tagged<(ContentType, Vec<u8>)>>> {
+    DownloadDerivativeUseCase::new(attachment_gateway(db, &tenant.0), storage.0.clone())
+        .execute(id, attachment_id, derivative)
+        .await
+        .map(|(attachment, derivative, content)| {
+            let etag = format!("\"{}-{}\"", attachment.checksum, derivative.name());
+            let content_type =
+                ContentType::parse_flexible(derivative.attachment_type().content_type())
+                    .unwrap_or(ContentType::Binary);
+            Immutable(Tagged::unless_cached(
+                etag,
+                (content_type, content),
+                &if_none_match,
+            ))
+        })
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("attachment_id" = String, Path, description = "Attachment id")
+    ),
+    responses(
+        (status = 204, description = "Attachment deleted"),
+        (status = 404, description = "Attachment not found", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[delete("/<id>/attachments/<attachment_id>")]
+pub async fn delete(
+    db: &State<DbSqlx>,
+    storage: &State<AttachmentStorage>,
+    context: AuditContext,
+    id: &str,
+    attachment_id: &str,
+) -> Result<NoContent> {
+    let tenant_id = &context.tenant_id;
+    DeleteAttachmentUseCase::new(attachment_gateway(db, tenant_id), storage.0.clone())
+        .execute(&context, id, attachment_id)
+        .await
+        .map(|_| NoContent)
+        .map_err(Problem::from)
+}
+
+#[cfg(test)]
+mod attachments_tests {
+    use super::*;
+
+    #[test]
+    fn names_downloads_for_old_and_new_clients() {
+        assert_eq!(
+            content_disposition("ficha técnica.pdf"),
+            "inline; filename=\"ficha t_cnica.pdf\"; filename*=UTF-8''ficha%20t%C3%A9cnica.pdf"
+        );
+    }
+}
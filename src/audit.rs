@@ -0,0 +1,159 @@
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditEntry;
+use crate::application::audit::AuditFilter;
+use crate::application::audit::AuditGateway;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Message;
+use crate::infra::db::audit_gateway::AuditPostgresGateway;
+use crate::infra::db::DbSqlx;
+use crate::middler::payload::Payload;
+use crate::middler::tenant::Tenant;
+use crate::middler::ApiKey;
+use crate::problem::Problem;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket::get;
+use rocket::FromForm;
+use rocket::State;
+use utoipa::IntoParams;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+type Result<T, E = Problem> = std::result::Result<T, E>;
+
+#[derive(Debug, Default, FromForm, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    actor: Option<String>,
+    action: Option<String>,
+    aggregate_type: Option<String>,
+    aggregate_id: Option<String>,
+    request_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl TryFrom<AuditQuery> for AuditFilter {
+    type Error = Notification;
+
+    fn try_from(query: AuditQuery) -> std::result::Result<Self, Self::Error> {
+        let mut errors = Vec::new();
+
+        let action = query.action.as_deref().and_then(|action| {
+            let parsed = AuditAction::parse(action);
+            if parsed.is_none() {
+                errors.push(CustomError::Validation(
+                    Message::new("audit-action-invalid")
+                        .arg("value", action)
+                        .for_field("action"),
+                ));
+            }
+            parsed
+        });
+
+        let mut timestamp = |name: &'static str, value: Option<&str>| {
+            value.and_then(|value| match DateTime::parse_from_rfc3339(value) {
+                Ok(value) => Some(value.with_timezone(&Utc)),
+                Err(_) => {
+                    errors.push(CustomError::Validation(
+                        Message::new("timestamp-invalid")
+                            .arg("name", name)
+                            .for_field(name),
+                    ));
+                    None
+                }
+            })
+        };
+        let from = timestamp("from", query.from.as_deref());
+        let to = timestamp("to", query.to.as_deref());
+
+        if !errors.is_empty() {
+            return Err(Notification::with_errors(errors));
+        }
+
+        Ok(AuditFilter {
+            actor: query.actor,
+            action,
+            aggregate_type: query.aggregate_type,
+            aggregate_id: query.aggregate_id,
+            aggregate_ids: Vec::new(),
+            request_id: query.request_id,
+            from,
+            to,
+            limit: query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            offset: query.offset.unwrap_or(0).max(0),
+        })
+    }
+}
+
+#[utoipa::path(
+    tag = "audit",
+    context_path = "/admin",
+    params(AuditQuery),
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Matching audit entries, newest first", body = Vec<AuditEntry>),
+        (status = 400, description = "Missing API key or tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid filter", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/audit?<query..>")]
+pub async fn find(
+    _key: ApiKey<'_>,
+    tenant: Tenant,
+    db: &State<DbSqlx>,
+    query: AuditQuery,
+) -> Result<Payload<Vec<AuditEntry>>> {
+    let filter = AuditFilter::try_from(query).map_err(Problem::from)?;
+
+    AuditPostgresGateway::new(db.pool(), tenant.0)
+        .find(&filter)
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[cfg(test)]
+mod audit_query_tests {
+    use super::*;
+
+    #[test]
+    fn builds_filter_with_defaults() {
+        let filter = AuditFilter::try_from(AuditQuery {
+            aggregate_type: Some("supply".to_string()),
+            action: Some("update".to_string()),
+            from: Some("2026-01-01T00:00:00Z".to_string()),
+            limit: Some(5000),
+            ..AuditQuery::default()
+        })
+        .unwrap();
+
+        assert_eq!(filter.aggregate_type.as_deref(), Some("supply"));
+        assert_eq!(filter.action, Some(AuditAction::Update));
+        assert!(filter.from.is_some());
+        assert_eq!(filter.limit, MAX_LIMIT);
+        assert_eq!(filter.offset, 0);
+    }
+
+    #[test]
+    fn rejects_unknown_action_and_bad_timestamp() {
+        let error = AuditFilter::try_from(AuditQuery {
+            action: Some("rename".to_string()),
+            to: Some("yesterday".to_string()),
+            ..AuditQuery::default()
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.format_errors(),
+            vec![
+                "'action' must be one of create, update or delete, got 'rename'",
+                "'to' must be an RFC 3339 timestamp"
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::admin::session;
+use crate::application::supply::export_supplies::ExportFilter;
+use crate::application::supply::export_supplies::ExportFormat;
+use crate::application::supply::export_supplies::ExportOptions;
+use crate::application::supply::export_supplies::ExportSuppliesUseCase;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::notification::Notification;
+use crate::infra::db::supply_gateway::SupplyPostgresGateway;
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use rocket::futures::TryStreamExt;
+use rocket::tokio::fs::File;
+use rocket::tokio::io;
+use rocket::tokio::io::AsyncWrite;
+use rocket::tokio::io::AsyncWriteExt;
+use rocket_db_pools::sqlx::PgPool;
+use std::error::Error;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Supply catalog API")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Serves the HTTP and gRPC APIs. The default command.
+    Serve,
+    /// Exports the supplies of a tenant with their current prices, using the
+    /// `databases.sqlx` url of Rocket.toml.
+    Export(ExportArgs),
+    /// Hashes a password read from standard input, for the `password` of an
+    /// account in `[admin.users]`.
+    HashPassword,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Tenant whose supplies are exported.
+    #[arg(long)]
+    pub tenant: String,
+    /// csv, ndjson or xlsx.
+    #[arg(long, default_value = "csv")]
+    pub format: String,
+    /// Comma separated columns, all of them by default.
+    #[arg(long)]
+    pub columns: Option<String>,
+    /// en or pt-BR.
+    #[arg(long, default_value = "en")]
+    pub locale: String,
+    /// Only supplies whose name contains this.
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Only prices of this unit.
+    #[arg(long)]
+    pub unit: Option<String>,
+    /// Where to write the file; standard output when missing, except for xlsx.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+pub async fn export(args: ExportArgs) -> Result<()> {
+    if !TenantId::is_valid(&args.tenant) {
+        return Err(format!("'{}' is not a valid tenant id", args.tenant).into());
+    }
+    let filter = ExportFilter {
+        name: args.name,
+        unit: args.unit,
+    };
+    let options = ExportOptions::parse(
+        Some(&args.format),
+        args.columns.as_deref(),
+        Some(&args.locale),
+        filter,
+    )
+    .map_err(|notification| notification.format_errors().join("\n"))?;
+
+    let url = rocket::Config::figment().extract_inner::<String>("databases.sqlx.url")?;
+    let pool = PgPool::connect(&url).await?;
+    let gateway = SupplyPostgresGateway::new(pool, TenantId::from_str(&args.tenant));
+    let use_case = ExportSuppliesUseCase::new(Arc::new(gateway));
+    let failed = |notification: Notification| notification.format_errors().join("\n");
+
+    if options.format == ExportFormat::Xlsx {
+        let output = args.output.ok_or("xlsx exports need an --output file")?;
+        return Ok(use_case
+            .write_xlsx(&options, &output)
+            .await
+            .map_err(failed)?);
+    }
+
+    let mut output: Pin<Box<dyn AsyncWrite + Send>> = match args.output {
+        Some(path) => Box::pin(File::create(path).await?),
+        None => Box::pin(io::stdout()),
+    };
+    let mut chunks = Box::pin(use_case.stream(options));
+    while let Some(chunk) = chunks.try_next().await.map_err(failed)? {
+        output.write_all(&chunk).await?;
+    }
+    output.flush().await?;
+    Ok(())
+}
+
+pub fn hash_password() -> Result<()> {
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err("the password read from standard input is empty".into());
+    }
+    println!("{}", session::hash_password(password)?);
+    Ok(())
+}
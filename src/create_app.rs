@@ -1,26 +1,57 @@
+use crate::admin::AdminUi;
+use crate::graphql;
+use crate::grpc::Grpc;
+use crate::health;
+use crate::health::Health;
+use crate::i18n;
+use crate::infra::db::Db;
 use crate::infra::db::DbSqlx;
+use crate::logging;
+use crate::metrics;
+use crate::metrics::Metrics;
+use crate::middler::auth::Authentication;
+use crate::middler::csrf;
+use crate::middler::csrf::CsrfProtection;
+use crate::middler::idempotency::Idempotency;
+use crate::middler::rate_limit::RateLimiter;
+use crate::middler::request_id::correlate;
+use crate::middler::request_id::RequestIds;
+use crate::middler::security_headers::SecurityHeaders;
+use crate::middler::tenant::Tenancy;
 use crate::middler::RemoveServerHeader;
 use dotenvy::dotenv;
-use rocket::http::Status;
 use serde_json::Value;
 
+use crate::attachments;
+use crate::attachments::Attachments;
+use crate::audit;
 use crate::infra::adapters::rabbitmq_adapter::RabbitMqAdapter;
-use rocket::catch;
+use crate::mapping_profiles;
+use crate::openapi;
+use crate::posts;
+use crate::problem;
+use crate::supplies;
+use crate::supply_events;
+use crate::supply_events::SupplyEvents;
+use crate::supply_rooms;
+use crate::supply_rooms::SupplyRooms;
 use rocket::catchers;
 use rocket::get;
 use rocket::routes;
 use rocket::Build;
-use rocket::Request;
 use rocket::Rocket;
 use rocket_async_compression::Compression;
 use rocket_db_pools::Config;
 use rocket_db_pools::Database;
 use rocket_dyn_templates::Template;
+use tracing::info;
 
+#[utoipa::path(responses((status = 200, description = "Liveness text", body = String)))]
 #[get("/")]
-fn index() -> &'static str {
+pub fn index() -> &'static str {
     "root"
 }
+
 pub fn start_app() -> Rocket<Build> {
     dotenv().expect(".env file not found");
     let figment = rocket::Config::figment().merge((
@@ -33,30 +64,95 @@ pub fn start_app() -> Rocket<Build> {
             url: std::env::var("DATABASE_URL").expect("DATABASE_URL"),
         },
     ));
+    logging::init(&figment);
 
-    rocket::build()
+    let rocket = rocket::build()
         .configure(figment)
+        .attach(RequestIds)
+        .attach(Metrics)
         .attach(DbSqlx::init())
+        .attach(Db::init())
+        .attach(Health)
+        .attach(SupplyEvents)
+        .attach(Attachments)
+        .attach(Grpc)
+        .attach(Authentication)
+        .attach(Tenancy)
+        .attach(RateLimiter)
         .attach(RemoveServerHeader)
-        .attach(Template::fairing())
+        .attach(SecurityHeaders)
+        .attach(CsrfProtection)
+        .attach(Idempotency)
+        .attach(AdminUi)
+        .attach(Template::custom(|engines| {
+            csrf::register_helpers(&mut engines.handlebars);
+            i18n::register_helpers(&mut engines.handlebars);
+        }))
         .attach(Compression::fairing())
-        .register("/", catchers![internal_error, not_found, default])
-        .mount("/", routes![index])
-}
-
-#[catch(500)]
-pub fn internal_error() -> &'static str {
-    "internal server error"
-}
+        .register(
+            "/",
+            catchers![
+                problem::internal_error,
+                problem::not_found,
+                problem::default
+            ],
+        )
+        .manage(SupplyRooms::default())
+        .manage(graphql::schema())
+        .mount("/", openapi::routes());
 
-#[catch(404)]
-pub fn not_found(req: &Request) -> String {
-    format!("I couldn't find '{}'. Try something else?", req.uri())
+    mount_routes(rocket)
 }
 
-#[catch(default)]
-pub fn default(status: Status, req: &Request) -> String {
-    format!("{} ({})", status, req.uri())
+/// Mounts the API routes. Every route mounted here must be documented in
+/// `openapi::ApiDoc`.
+pub fn mount_routes(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .mount("/", correlate(routes![index]))
+        .mount("/health", correlate(routes![health::live, health::ready]))
+        .mount("/", correlate(routes![metrics::metrics]))
+        .mount(
+            "/posts",
+            correlate(routes![
+                posts::create,
+                posts::create2,
+                posts::find,
+                posts::find_all
+            ]),
+        )
+        .mount(
+            "/supplies",
+            correlate(routes![
+                supplies::create,
+                supplies::list,
+                supplies::find,
+                supplies::update,
+                supplies::delete,
+                supplies::import,
+                supplies::export,
+                attachments::upload,
+                attachments::list,
+                attachments::download,
+                attachments::derivative,
+                attachments::delete,
+                supply_events::events,
+                supply_rooms::join
+            ]),
+        )
+        .mount(
+            "/supplies/import/profiles",
+            correlate(routes![
+                mapping_profiles::list,
+                mapping_profiles::find,
+                mapping_profiles::save,
+                mapping_profiles::delete
+            ]),
+        )
+        .mount(
+            "/graphql",
+            correlate(routes![graphql::execute, graphql::graphiql]),
+        )
+        .mount("/admin", correlate(routes![audit::find]))
 }
 
 async fn _stream_consume() {
@@ -64,15 +160,12 @@ async fn _stream_consume() {
     let _queue_stream = rabbitmq.create_stream("stream", 1).await;
     let _handler = rabbitmq
         .consumer("stream", None, |delivery| async move {
-            println!(
-                "message {:?} with offset {}",
-                delivery
-                    .message()
-                    .data()
-                    .map(serde_json::from_slice::<Value>)
-                    .unwrap(),
-                delivery.offset()
-            );
+            let message = delivery
+                .message()
+                .data()
+                .map(serde_json::from_slice::<Value>)
+                .unwrap();
+            info!(message = ?message, "message consumed");
         })
         .await;
 }
@@ -1,6 +1,8 @@
 pub mod aggregate_root;
+pub mod audit;
 pub mod entity;
 pub mod identifier;
 pub mod supply;
+pub mod tenant_id;
 pub mod validation;
 pub mod value_object;
@@ -1,4 +1,5 @@
 pub mod price;
+pub mod supply_gateway;
 pub mod supply_id;
 pub mod supply_validator;
 
@@ -7,6 +8,7 @@ use self::supply_id::SupplyId;
 use self::supply_validator::SupplyValidator;
 use super::aggregate_root::AggregateRoot;
 use super::entity::Entity;
+use super::tenant_id::TenantId;
 use super::validation::notification::Notification;
 use super::validation::validation_handler::ValidationHandler;
 use super::validation::validator::Validator;
@@ -21,58 +23,97 @@ Input{
 */
 
 #[derive(Clone, Debug)]
-pub struct Supply<'a> {
+pub struct Supply {
     id: SupplyId,
-    name: &'a str,
+    tenant_id: TenantId,
+    name: String,
     price: Vec<Price>,
     created_at: DateTime<Utc>,
     updated_at: Option<DateTime<Utc>>,
+    version: i64,
 }
 
-impl<'a> Supply<'a> {
-    pub fn new(name: &'a str, price: Vec<Price>) -> Result<Self, Notification> {
+/// Version of a supply that has not been persisted yet.
+pub const INITIAL_VERSION: i64 = 1;
+
+impl Supply {
+    pub fn new(tenant_id: TenantId, name: &str, price: Vec<Price>) -> Result<Self, Notification> {
         let input = Self {
             id: SupplyId::unique(),
-            name,
+            tenant_id,
+            name: name.to_string(),
             price,
             created_at: Utc::now(),
             updated_at: None,
+            version: INITIAL_VERSION,
         };
         input.self_validate()
     }
 
-    pub fn with(id: SupplyId, name: &'a str, price: Vec<Price>) -> Result<Self, Notification> {
+    pub fn with(
+        id: SupplyId,
+        tenant_id: TenantId,
+        name: &str,
+        price: Vec<Price>,
+    ) -> Result<Self, Notification> {
         let supply = Self {
             id,
-            name,
+            tenant_id,
+            name: name.to_string(),
             price,
             created_at: Utc::now(),
             updated_at: None,
+            version: INITIAL_VERSION,
         };
         supply.self_validate()
     }
 
-    pub fn with_input(supply: &'a Supply) -> Self {
+    pub fn with_input(supply: &Supply) -> Self {
         Self {
             id: supply.id.to_owned(),
-            name: supply.name,
+            tenant_id: supply.tenant_id.to_owned(),
+            name: supply.name.to_owned(),
             price: supply.price.to_owned(),
             created_at: supply.created_at.to_owned(),
             updated_at: supply.updated_at.to_owned(),
+            version: supply.version,
+        }
+    }
+
+    /// Rebuilds a supply from persisted state, without validating it again.
+    pub fn restore(
+        id: SupplyId,
+        tenant_id: TenantId,
+        name: String,
+        price: Vec<Price>,
+        created_at: DateTime<Utc>,
+        updated_at: Option<DateTime<Utc>>,
+        version: i64,
+    ) -> Self {
+        Self {
+            id,
+            tenant_id,
+            name,
+            price,
+            created_at,
+            updated_at,
+            version,
         }
     }
 
     pub fn update(
         &self,
-        name: Option<&'a str>,
+        name: Option<&str>,
         price: Option<Vec<Price>>,
     ) -> Result<Self, Notification> {
         let supply = Self {
             id: self.id.to_owned(),
-            name: name.unwrap_or(self.name),
+            tenant_id: self.tenant_id.to_owned(),
+            name: name.unwrap_or(&self.name).to_string(),
             price: price.unwrap_or(self.price.to_owned()),
             created_at: self.created_at.to_owned(),
             updated_at: Some(Utc::now()),
+            version: self.version,
         };
         supply.self_validate()
     }
@@ -86,8 +127,12 @@ impl<'a> Supply<'a> {
         Ok(self)
     }
 
+    pub fn get_tenant_id(&self) -> &TenantId {
+        &self.tenant_id
+    }
+
     pub fn get_name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     pub fn get_prices(&self) -> &Vec<Price> {
@@ -107,9 +152,15 @@ impl<'a> Supply<'a> {
     pub fn get_updated_at(&self) -> Option<&DateTime<Utc>> {
         self.updated_at.as_ref()
     }
+
+    /// The version the supply was loaded at. Updates keep it, so the
+    /// repository can reject them when the stored version moved on.
+    pub fn get_version(&self) -> i64 {
+        self.version
+    }
 }
 
-impl<'a> Entity for Supply<'a> {
+impl Entity for Supply {
     type Id = SupplyId;
     fn get_id(&self) -> &SupplyId {
         &self.id
@@ -121,22 +172,28 @@ impl<'a> Entity for Supply<'a> {
     }
 }
 
-impl<'a> AggregateRoot for Supply<'a> {}
+impl AggregateRoot for Supply {}
 
 #[cfg(test)]
 mod supply_tests {
     use super::*;
     use crate::domain::identifier::Identifier;
+    use crate::i18n::Locale;
     use rust_decimal_macros::dec;
     use uuid::Uuid;
 
+    fn tenant() -> TenantId {
+        TenantId::from_str("acme")
+    }
+
     #[test]
     fn create_a_valid_input() {
-        let input = Supply::new("cimento", vec![Price::new("sc", 25000)]);
+        let input = Supply::new(tenant(), "cimento", vec![Price::new("sc", 25000)]);
         assert!(input.is_ok());
 
         if let Ok(value) = input {
             assert_eq!(value.get_name(), "cimento");
+            assert_eq!(value.get_tenant_id().get_value(), "acme");
             assert!(!value.get_id().get_value().is_empty());
             assert_eq!(value.get_prices().len(), 1);
             assert_eq!(value.get_prices().get(0).unwrap().get_unit(), "sc");
@@ -147,6 +204,7 @@ mod supply_tests {
             );
             assert!(!value.get_created_at().to_rfc3339().is_empty());
             assert!(value.get_updated_at().is_none());
+            assert_eq!(value.get_version(), INITIAL_VERSION);
         }
     }
 
@@ -154,6 +212,7 @@ mod supply_tests {
     fn create_a_valid_input_with_id_param() {
         let supply = Supply::with(
             SupplyId::from_str("fake_id"),
+            tenant(),
             "cimento",
             vec![Price::new("sc", 25000)],
         );
@@ -179,6 +238,7 @@ mod supply_tests {
     fn create_a_valid_input_with_id_param_uuid() {
         let supply = Supply::with(
             SupplyId::from_uuid(Uuid::nil()),
+            tenant(),
             "cimento",
             vec![Price::new("sc", 25000)],
         );
@@ -205,7 +265,7 @@ mod supply_tests {
 
     #[test]
     fn create_an_invalid_input_with_name_empty() {
-        let input = Supply::new("", vec![Price::new("sc", 25000)]);
+        let input = Supply::new(tenant(), "", vec![Price::new("sc", 25000)]);
         assert!(input.is_err());
 
         if let Err(error) = input {
@@ -222,12 +282,19 @@ mod supply_tests {
                     "'name' must be between 1 and 255 characters"
                 ]
             );
+            assert_eq!(
+                error.format_errors_in(Locale::PtBr),
+                vec![
+                    "'name' não deve ficar vazio",
+                    "'name' deve ter entre 1 e 255 caracteres"
+                ]
+            );
         }
     }
 
     #[test]
     fn create_an_invalid_input_with_price_empty() {
-        let supply = Supply::new("cimento", vec![]);
+        let supply = Supply::new(tenant(), "cimento", vec![]);
         assert!(supply.is_err());
 
         if let Err(error) = supply {
@@ -245,6 +312,7 @@ mod supply_tests {
     fn create_an_invalid_input_with_id_empty() {
         let supply = Supply::with(
             SupplyId::from_str(""),
+            tenant(),
             "cimento",
             vec![Price::new("sc", 25000)],
         );
@@ -261,9 +329,27 @@ mod supply_tests {
         }
     }
 
+    #[test]
+    fn create_an_invalid_input_with_tenant_id_empty() {
+        let supply = Supply::new(
+            TenantId::from_str(""),
+            "cimento",
+            vec![Price::new("sc", 25000)],
+        );
+        assert!(supply.is_err());
+
+        if let Err(error) = supply {
+            assert_eq!(error.get_errors().len(), 1);
+            assert_eq!(
+                error.format_errors(),
+                vec!["'tenant_id' must be a non-empty lowercase identifier"]
+            );
+        }
+    }
+
     #[test]
     fn update_name_and_price_a_valid_input() {
-        let supply = Supply::new("cimento", vec![Price::new("sc", 26000)]);
+        let supply = Supply::new(tenant(), "cimento", vec![Price::new("sc", 26000)]);
 
         if let Ok(value) = supply {
             let supply = value
@@ -288,12 +374,13 @@ mod supply_tests {
             );
             assert!(!supply.get_created_at().to_rfc3339().is_empty());
             assert!(supply.get_updated_at().is_some());
+            assert_eq!(supply.get_version(), value.get_version());
         }
     }
 
     #[test]
     fn create_a_valid_input_with_other_input() {
-        let supply: Result<_, _> = Supply::new("cimento", vec![Price::new("sc", 26000)]);
+        let supply: Result<_, _> = Supply::new(tenant(), "cimento", vec![Price::new("sc", 26000)]);
         if let Ok(value) = supply {
             let other = Supply::with_input(&value);
 
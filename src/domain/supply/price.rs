@@ -2,6 +2,7 @@ use crate::domain::value_object::ValueObject;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use serde::Serialize;
+use utoipa::ToSchema;
 
 /*
 Price{
@@ -10,11 +11,12 @@ Price{
 }
 */
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct Price {
     unit: String,
 
     #[serde(with = "rust_decimal::serde::float")]
+    #[schema(value_type = f64, example = 12.5)]
     value: Decimal,
 }
 
@@ -0,0 +1,95 @@
+use super::supply_id::SupplyId;
+use super::Supply;
+use crate::domain::audit::Audit;
+use crate::domain::audit::AuditEntry;
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Where a page of supplies, ordered by name and id, starts or ends.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SupplyKey {
+    pub name: String,
+    pub id: String,
+}
+
+impl From<&Supply> for SupplyKey {
+    fn from(supply: &Supply) -> Self {
+        Self {
+            name: supply.get_name().to_string(),
+            id: supply.get_id().get_value().to_string(),
+        }
+    }
+}
+
+/// Up to `limit` of the supplies between `after` and `before`, ordered by
+/// name and id: the first of them, or the last ones `from_end`, after
+/// skipping `offset` of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SupplyPage {
+    pub after: Option<SupplyKey>,
+    pub before: Option<SupplyKey>,
+    pub limit: i64,
+    pub offset: i64,
+    pub from_end: bool,
+    /// Only supplies whose name contains this, in any case.
+    pub name: Option<String>,
+    /// Only supplies with a price for this unit.
+    pub unit: Option<String>,
+}
+
+impl SupplyPage {
+    pub fn matches(&self, supply: &Supply) -> bool {
+        self.name.as_ref().is_none_or(|name| {
+            supply
+                .get_name()
+                .to_lowercase()
+                .contains(&name.to_lowercase())
+        }) && self.unit.as_ref().is_none_or(|unit| {
+            supply
+                .get_prices()
+                .iter()
+                .any(|price| price.get_unit() == unit)
+        })
+    }
+}
+
+/// Supplies of a tenant and the supply lists they are on. Every change to a
+/// supply is written together with its audit entry, and fails when the entry
+/// can't be written.
+#[async_trait]
+pub trait SupplyGateway: Send + Sync {
+    async fn create(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError>;
+    async fn find_by_id(&self, id: &SupplyId) -> Result<Option<Supply>, CustomError>;
+    async fn find_all(&self) -> Result<Vec<Supply>, CustomError>;
+    async fn find_page(&self, page: &SupplyPage) -> Result<Vec<Supply>, CustomError>;
+    /// How many supplies match the `name` and `unit` of the page.
+    async fn count(&self, page: &SupplyPage) -> Result<i64, CustomError>;
+    async fn update(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError>;
+    async fn delete_by_id(&self, id: &SupplyId, audit: &AuditEntry) -> Result<(), CustomError>;
+    /// The supplies on the supply list `list`, ordered by name. Deleting a
+    /// supply takes it off every list.
+    async fn find_in_list(&self, list: &str) -> Result<Vec<Supply>, CustomError>;
+    /// Puts the supply on `list`, returning it, or `None` when there is no
+    /// such supply.
+    async fn add_to_list(&self, list: &str, id: &SupplyId) -> Result<Option<Supply>, CustomError>;
+    async fn remove_from_list(&self, list: &str, id: &SupplyId) -> Result<(), CustomError>;
+    /// Inserts the new supplies and updates the existing ones at their
+    /// version, all or none of them.
+    async fn upsert_all(
+        &self,
+        supplies: &[Supply],
+        audit: Audit<'_, Supply>,
+    ) -> Result<Vec<Supply>, CustomError>;
+}
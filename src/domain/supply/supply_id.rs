@@ -1,7 +1,7 @@
 use crate::domain::identifier::Identifier;
 use uuid::Uuid;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SupplyId {
     value: String,
 }
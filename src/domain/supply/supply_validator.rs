@@ -1,15 +1,17 @@
 use super::Supply;
 use crate::domain::entity::Entity;
 use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
 use crate::domain::validation::error::CustomError;
 use crate::domain::validation::validation_handler::ValidationHandler;
 use crate::domain::validation::validator::Validator;
+use crate::i18n::Message;
 
 const NAME_MIN_LENGTH: usize = 1;
 const NAME_MAX_LENGTH: usize = 255;
 
 pub struct SupplyValidator<'a> {
-    pub supply: &'a Supply<'a>,
+    pub supply: &'a Supply,
     pub validation_handler: &'a mut dyn ValidationHandler,
 }
 
@@ -25,8 +27,19 @@ impl<'a> SupplyValidator<'a> {
         let var = self.supply.get_id().get_value().trim();
 
         if var.is_empty() {
-            self.validation_handler
-                .append(&CustomError::Error("'id' should not be empty".to_string()));
+            self.validation_handler.append(&CustomError::Validation(
+                Message::new("id-empty").for_field("id"),
+            ));
+        }
+    }
+
+    fn validate_tenant_id(&mut self) {
+        let var = self.supply.get_tenant_id().get_value();
+
+        if !TenantId::is_valid(var) {
+            self.validation_handler.append(&CustomError::Validation(
+                Message::new("tenant-id-invalid").for_field("tenant_id"),
+            ));
         }
     }
 
@@ -34,17 +47,18 @@ impl<'a> SupplyValidator<'a> {
         let var = self.supply.name.trim();
 
         if var.is_empty() {
-            self.validation_handler.append(&CustomError::Error(
-                "'name' should not be empty".to_string(),
+            self.validation_handler.append(&CustomError::Validation(
+                Message::new("name-empty").for_field("name"),
             ));
         }
 
         if var.len() < NAME_MIN_LENGTH || var.len() > NAME_MAX_LENGTH {
-            let message = format!(
-                "'name' must be between {} and {} characters",
-                NAME_MIN_LENGTH, NAME_MAX_LENGTH
-            );
-            self.validation_handler.append(&CustomError::Error(message));
+            let message = Message::new("name-length")
+                .arg("min", NAME_MIN_LENGTH)
+                .arg("max", NAME_MAX_LENGTH)
+                .for_field("name");
+            self.validation_handler
+                .append(&CustomError::Validation(message));
         }
     }
 
@@ -52,8 +66,8 @@ impl<'a> SupplyValidator<'a> {
         let var = self.supply.price.to_owned();
 
         if var.is_empty() {
-            self.validation_handler.append(&CustomError::Error(
-                "'price' should not be empty".to_string(),
+            self.validation_handler.append(&CustomError::Validation(
+                Message::new("price-empty").for_field("price"),
             ));
         }
     }
@@ -62,6 +76,7 @@ impl<'a> SupplyValidator<'a> {
 impl<'a> Validator for SupplyValidator<'a> {
     fn validate(&mut self) {
         self.validate_id();
+        self.validate_tenant_id();
         self.validate_name();
         self.validate_price();
     }
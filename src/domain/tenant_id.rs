@@ -0,0 +1,30 @@
+use super::identifier::Identifier;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TenantId {
+    value: String,
+}
+
+impl Identifier for TenantId {
+    fn get_value(&self) -> &str {
+        self.value.as_ref()
+    }
+}
+
+impl TenantId {
+    pub fn from_str(an_id: &str) -> Self {
+        TenantId {
+            value: an_id.to_string(),
+        }
+    }
+
+    /// Tenant ids are used in hostnames and headers, so only lowercase ascii
+    /// letters, digits, `-` and `_` are accepted.
+    pub fn is_valid(an_id: &str) -> bool {
+        !an_id.is_empty()
+            && an_id.len() <= 63
+            && an_id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    }
+}
@@ -1,17 +1,58 @@
 #![allow(dead_code)]
+use crate::i18n::Locale;
+use crate::i18n::Message;
 use serde::Serialize;
 use thiserror::Error;
 
-#[derive(Error, Serialize, Debug, Clone)]
+#[derive(Error, Serialize, Debug, Clone, PartialEq)]
 pub enum CustomError {
     #[error("{0}")]
     Error(String),
 
+    /// An invalid input, by the code of its message in the catalogs.
+    #[error("{0}")]
+    Validation(Message),
+
     #[error("ApiError: {0}")]
     ApiError(String),
 
     #[error("RepositoryError: {0}")]
     RepositoryError(String),
+
+    #[error("NotFound: {0}")]
+    NotFound(Message),
+
+    #[error("VersionConflict: {0}")]
+    VersionConflict(Message),
+}
+
+impl CustomError {
+    /// The error as `to_string` shows it, with the messages of the catalogs
+    /// in `locale`.
+    pub fn render(&self, locale: Locale) -> String {
+        match self {
+            CustomError::Validation(message) => message.render(locale),
+            CustomError::NotFound(message) => format!("NotFound: {}", message.render(locale)),
+            CustomError::VersionConflict(message) => {
+                format!("VersionConflict: {}", message.render(locale))
+            }
+            error => error.to_string(),
+        }
+    }
+
+    /// The input field the error is about. Validation messages carry it;
+    /// untranslated errors name it first, as in `'file' has no header`.
+    pub fn field(&self) -> Option<String> {
+        match self {
+            CustomError::Validation(message) => message.field.map(str::to_string),
+            CustomError::Error(message) => message
+                .strip_prefix('\'')
+                .and_then(|rest| rest.split_once('\''))
+                .map(|(field, _)| field.to_string())
+                .filter(|field| !field.is_empty()),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +76,41 @@ mod custom_error_tests {
         let error = CustomError::RepositoryError("RepositoryError".to_string());
         assert_eq!(error.to_string(), "RepositoryError: RepositoryError");
     }
+
+    #[test]
+    fn test_custom_not_found_error() {
+        let error = CustomError::NotFound(Message::new("supply-not-found").arg("id", "s1"));
+        assert_eq!(
+            error.to_string(),
+            "NotFound: supply with id 's1' was not found"
+        );
+        assert_eq!(
+            error.render(Locale::PtBr),
+            "NotFound: insumo com id 's1' não foi encontrado"
+        );
+    }
+
+    #[test]
+    fn test_custom_version_conflict_error() {
+        let error = CustomError::VersionConflict(
+            Message::new("supply-version-conflict")
+                .arg("id", "s1")
+                .arg("current", 3i64)
+                .arg("expected", 2i64),
+        );
+        assert_eq!(
+            error.to_string(),
+            "VersionConflict: supply 's1' is at version 3, not 2"
+        );
+    }
+
+    #[test]
+    fn test_custom_validation_error() {
+        let error = CustomError::Validation(Message::new("name-empty"));
+        assert_eq!(error.to_string(), "'name' should not be empty");
+        assert_eq!(error.render(Locale::PtBr), "'name' não deve ficar vazio");
+        assert_eq!(error.render(Locale::En), error.to_string());
+    }
 }
 
 // impl<'a> std::fmt::Display for CustomError<'a> {
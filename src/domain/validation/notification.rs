@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use super::error::CustomError;
 use super::validation_handler::ValidationHandler;
+use crate::i18n::Locale;
 
 #[derive(Debug, Default)]
 pub struct Notification {
@@ -28,6 +29,15 @@ impl Notification {
             .map(|e| e.to_string())
             .collect::<Vec<String>>()
     }
+
+    /// The errors as `format_errors` shows them, with validation messages
+    /// in `locale`.
+    pub fn format_errors_in(&self, locale: Locale) -> Vec<String> {
+        self.get_errors()
+            .iter()
+            .map(|e| e.render(locale))
+            .collect::<Vec<String>>()
+    }
 }
 
 impl ValidationHandler for Notification {
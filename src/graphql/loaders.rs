@@ -0,0 +1,31 @@
+use crate::application::audit::AuditGateway;
+use crate::application::supply::price_history::PriceChange;
+use crate::application::supply::price_history::PriceHistoryUseCase;
+use crate::i18n::Locale;
+use async_graphql::dataloader::Loader;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Batches the price history lookups of a query into a single audit query.
+pub struct PriceHistoryLoader {
+    audit: Arc<dyn AuditGateway>,
+    locale: Locale,
+}
+
+impl PriceHistoryLoader {
+    pub fn new(audit: Arc<dyn AuditGateway>, locale: Locale) -> Self {
+        Self { audit, locale }
+    }
+}
+
+impl Loader<String> for PriceHistoryLoader {
+    type Value = Vec<PriceChange>;
+    type Error = Arc<async_graphql::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        PriceHistoryUseCase::new(self.audit.clone())
+            .execute(keys)
+            .await
+            .map_err(|notification| Arc::new(super::error(notification, self.locale)))
+    }
+}
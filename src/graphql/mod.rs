@@ -0,0 +1,575 @@
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditGateway;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::create_supply::CreateSupplyUseCase;
+use crate::application::supply::delete_supply::DeleteSupplyUseCase;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::application::supply::get_supply::GetSupplyUseCase;
+use crate::application::supply::list_supplies::ListSuppliesUseCase;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::update_supply::UpdateSupplyUseCase;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_gateway::SupplyKey;
+use crate::domain::supply::supply_gateway::SupplyPage;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use crate::infra::db::scope_to_tenant;
+use crate::infra::db::DbSqlx;
+use crate::middler::payload::Payload;
+use crate::posts::Post;
+use crate::problem::Problem;
+use crate::supplies;
+use crate::supply_events::SupplyEventStream;
+use async_graphql::connection;
+use async_graphql::connection::Connection;
+use async_graphql::connection::Edge;
+use async_graphql::connection::OpaqueCursor;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::http::GraphiQLSource;
+use async_graphql::Context;
+use async_graphql::EmptySubscription;
+use async_graphql::ErrorExtensions;
+use async_graphql::InputObject;
+use async_graphql::Object;
+use async_graphql::Schema;
+use async_graphql::ID;
+use loaders::PriceHistoryLoader;
+use rocket::get;
+use rocket::http::Status;
+use rocket::post;
+use rocket::response::content::RawHtml;
+use rocket::tokio;
+use rocket::State;
+use rocket_db_pools::sqlx::PgPool;
+use std::sync::Arc;
+use types::PostObject;
+use types::PriceInput;
+use types::SupplyObject;
+
+mod loaders;
+mod types;
+
+const MAX_DEPTH: usize = 12;
+const MAX_COMPLEXITY: usize = 1000;
+/// Supplies in a page when neither `first` nor `last` is given.
+const DEFAULT_PAGE_SIZE: i32 = 50;
+/// The most supplies a page may have.
+const MAX_PAGE_SIZE: i32 = 500;
+
+pub type GraphQLSchema = Schema<Query, Mutation, EmptySubscription>;
+
+pub fn schema() -> GraphQLSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// The tenant, actor, locale and gateways a GraphQL request runs with.
+pub struct RequestContext {
+    pub context: AuditContext,
+    /// The locale errors are told in.
+    pub locale: Locale,
+    pub supplies: Arc<dyn SupplyGateway>,
+    pub audit: Arc<dyn AuditGateway>,
+    pub events: Arc<dyn SupplyEventPublisher>,
+}
+
+impl RequestContext {
+    /// Adds the context, and the loaders batching its lookups, to `request`.
+    pub fn attach(self, request: async_graphql::Request) -> async_graphql::Request {
+        let loader = DataLoader::new(
+            PriceHistoryLoader::new(self.audit.clone(), self.locale),
+            tokio::spawn,
+        );
+        request.data(loader).data(self)
+    }
+}
+
+/// A GraphQL error carrying the RFC 7807 fields of `problem` as extensions.
+fn problem_error(problem: Problem) -> async_graphql::Error {
+    let errors = serde_json::to_value(&problem.errors)
+        .ok()
+        .and_then(|errors| async_graphql::Value::from_json(errors).ok());
+    async_graphql::Error::new(problem.detail.to_owned()).extend_with(|_, extensions| {
+        extensions.set("type", problem.type_uri.to_owned());
+        extensions.set("status", problem.status);
+        if let Some(errors) = errors {
+            extensions.set("errors", errors);
+        }
+    })
+}
+
+fn error(notification: Notification, locale: Locale) -> async_graphql::Error {
+    problem_error(Problem::from(notification).localize(locale))
+}
+
+/// The `[start, end)` window of `len` items selected by connection arguments.
+fn window(
+    len: usize,
+    after: Option<usize>,
+    before: Option<usize>,
+    first: Option<usize>,
+    last: Option<usize>,
+) -> (usize, usize) {
+    let mut start = after.map_or(0, |after| after + 1).min(len);
+    let mut end = before.unwrap_or(len).clamp(start, len);
+    if let Some(first) = first {
+        end = end.min(start + first);
+    }
+    if let Some(last) = last {
+        start = start.max(end.saturating_sub(last));
+    }
+    (start, end)
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn supply(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<SupplyObject> {
+        let request = ctx.data::<RequestContext>()?;
+        GetSupplyUseCase::new(request.supplies.clone())
+            .execute(&id)
+            .await
+            .map(SupplyObject)
+            .map_err(|e| error(e, request.locale))
+    }
+
+    /// Supplies ordered by name and id, paged by the name and id of the
+    /// edges. `hasPreviousPage` is only known paging backwards and
+    /// `hasNextPage` only forwards, otherwise it tells whether a cursor was
+    /// given. Pages have 50 supplies unless `first` or `last` asks for up
+    /// to 500.
+    async fn supplies(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Connection<OpaqueCursor<SupplyKey>, SupplyObject>> {
+        let request = ctx.data::<RequestContext>()?;
+        let use_case = ListSuppliesUseCase::new(request.supplies.clone());
+        if first.or(last).is_some_and(|count| count > MAX_PAGE_SIZE) {
+            return Err(problem_error(Problem::with_type(
+                Status::BadRequest,
+                "page-too-large",
+                &request.locale.text("problem-page-too-large-title"),
+                &Message::new("problem-page-too-large")
+                    .arg("max", MAX_PAGE_SIZE as i64)
+                    .render(request.locale),
+            )));
+        }
+        let first = first.or(last.is_none().then_some(DEFAULT_PAGE_SIZE));
+
+        connection::query(
+            after,
+            before,
+            first,
+            last,
+            |after: Option<OpaqueCursor<SupplyKey>>,
+             before: Option<OpaqueCursor<SupplyKey>>,
+             first,
+             last| async move {
+                // One more supply than asked for tells whether there are more
+                let page = SupplyPage {
+                    after: after.map(|cursor| cursor.0),
+                    before: before.map(|cursor| cursor.0),
+                    limit: first.or(last).unwrap_or_default() as i64 + 1,
+                    from_end: first.is_none() && last.is_some(),
+                    ..SupplyPage::default()
+                };
+                let mut supplies = use_case
+                    .page(&page)
+                    .await
+                    .map_err(|e| error(e, request.locale))?;
+                let mut has_previous = page.after.is_some();
+                let mut has_next = page.before.is_some();
+                if let Some(first) = first.filter(|first| supplies.len() > *first) {
+                    supplies.truncate(first);
+                    has_next = true;
+                }
+                if let Some(last) = last.filter(|last| supplies.len() > *last) {
+                    supplies.drain(..supplies.len() - last);
+                    has_previous = true;
+                }
+
+                let mut connection = Connection::new(has_previous, has_next);
+                connection.edges.extend(supplies.into_iter().map(|supply| {
+                    let cursor = SupplyKey {
+                        name: supply.name.to_owned(),
+                        id: supply.id.to_owned(),
+                    };
+                    Edge::new(OpaqueCursor(cursor), SupplyObject(supply))
+                }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    async fn posts(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Connection<usize, PostObject>> {
+        let request = ctx.data::<RequestContext>()?;
+        let pool = ctx.data::<PgPool>()?;
+        let tenant_id = &request.context.tenant_id;
+        let sql_error = |e: sqlx::Error| problem_error(Problem::from(e));
+
+        connection::query(
+            after,
+            before,
+            first,
+            last,
+            |after, before, first, last| async move {
+                let mut tx = pool.begin().await.map_err(sql_error)?;
+                scope_to_tenant(&mut tx, tenant_id)
+                    .await
+                    .map_err(sql_error)?;
+
+                let total =
+                    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM posts WHERE tenant_id = $1")
+                        .bind(tenant_id.get_value())
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(sql_error)?;
+                let (start, end) = window(total as usize, after, before, first, last);
+
+                let posts = sqlx::query_as::<_, Post>(
+                    r#"
+                    SELECT id, tenant_id, title, text, published
+                    FROM posts
+                    WHERE tenant_id = $1
+                    ORDER BY id
+                    LIMIT $2 OFFSET $3
+                    "#,
+                )
+                .bind(tenant_id.get_value())
+                .bind((end - start) as i64)
+                .bind(start as i64)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(sql_error)?;
+                tx.commit().await.map_err(sql_error)?;
+
+                let mut connection = Connection::new(start > 0, end < total as usize);
+                connection.edges.extend(
+                    posts
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, post)| Edge::new(start + i, PostObject::from(post))),
+                );
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct CreateSupplyGraphQLInput {
+    pub name: String,
+    pub prices: Vec<PriceInput>,
+}
+
+fn prices(prices: Vec<PriceInput>) -> async_graphql::Result<Vec<Price>> {
+    prices.into_iter().map(PriceInput::into_price).collect()
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_supply(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateSupplyGraphQLInput,
+    ) -> async_graphql::Result<SupplyObject> {
+        let request = ctx.data::<RequestContext>()?;
+        let input = CreateSupplyInput {
+            name: input.name,
+            prices: prices(input.prices)?,
+        };
+        CreateSupplyUseCase::new(request.supplies.clone(), request.events.clone())
+            .execute(&request.context, input)
+            .await
+            .map(SupplyObject)
+            .map_err(|e| error(e, request.locale))
+    }
+
+    /// Updates the fields given. `version`, when given, must be the current
+    /// version of the supply.
+    async fn update_supply(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        version: Option<i64>,
+        name: Option<String>,
+        prices: Option<Vec<PriceInput>>,
+    ) -> async_graphql::Result<SupplyObject> {
+        let request = ctx.data::<RequestContext>()?;
+        let input = UpdateSupplyInput {
+            name,
+            prices: prices.map(self::prices).transpose()?,
+        };
+        UpdateSupplyUseCase::new(request.supplies.clone(), request.events.clone())
+            .execute(&request.context, &id, version, input)
+            .await
+            .map(SupplyObject)
+            .map_err(|e| error(e, request.locale))
+    }
+
+    async fn delete_supply(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<bool> {
+        let request = ctx.data::<RequestContext>()?;
+        DeleteSupplyUseCase::new(request.supplies.clone(), request.events.clone())
+            .execute(&request.context, &id)
+            .await
+            .map(|_| true)
+            .map_err(|e| error(e, request.locale))
+    }
+}
+
+#[utoipa::path(
+    tag = "graphql",
+    context_path = "/graphql",
+    request_body(content = Object, description = "GraphQL request: `query`, `operationName` and `variables`"),
+    responses(
+        (status = 200, description = "GraphQL response, with `data` and `errors`. Errors carry the problem `type`, \
+            `status` and field `errors` as extensions", body = Object),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[post("/", data = "<request>")]
+pub async fn execute(
+    schema: &State<GraphQLSchema>,
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    context: AuditContext,
+    locale: Locale,
+    request: Payload<async_graphql::Request>,
+) -> Payload<async_graphql::Response> {
+    let tenant_id = &context.tenant_id;
+    let request = RequestContext {
+        locale,
+        supplies: supplies::supply_gateway(db, tenant_id),
+        audit: supplies::audit_gateway(db, tenant_id),
+        events: events.publisher(),
+        context,
+    }
+    .attach(request.into_inner())
+    .data(db.pool());
+
+    Payload(schema.execute(request).await)
+}
+
+#[utoipa::path(
+    tag = "graphql",
+    context_path = "/graphql",
+    responses((status = 200, description = "GraphiQL, to explore the schema and run queries", content_type = "text/html"))
+)]
+#[get("/")]
+pub fn graphiql() -> RawHtml<String> {
+    RawHtml(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+#[cfg(test)]
+mod graphql_tests {
+    use super::*;
+    use crate::application::testing::InMemoryAuditGateway;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::tenant_id::TenantId;
+    use serde_json::json;
+    use serde_json::Value;
+
+    struct Fixture {
+        schema: GraphQLSchema,
+        supplies: Arc<InMemorySupplyGateway>,
+        audit: Arc<InMemoryAuditGateway>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let supplies = Arc::new(InMemorySupplyGateway::default());
+            Self {
+                schema: schema(),
+                audit: supplies.audit(),
+                supplies,
+            }
+        }
+
+        async fn run(&self, query: &str) -> Value {
+            self.run_in(query, Locale::En).await
+        }
+
+        async fn run_in(&self, query: &str, locale: Locale) -> Value {
+            let request = RequestContext {
+                context: AuditContext::new("alice", TenantId::from_str("acme"), None),
+                locale,
+                supplies: self.supplies.clone(),
+                audit: self.audit.clone(),
+                events: Arc::new(InMemorySupplyEventPublisher::default()),
+            }
+            .attach(async_graphql::Request::new(query));
+            serde_json::to_value(self.schema.execute(request).await).unwrap()
+        }
+    }
+
+    #[test]
+    fn selects_connection_windows() {
+        assert_eq!(window(5, None, None, Some(2), None), (0, 2));
+        assert_eq!(window(5, Some(1), None, Some(2), None), (2, 4));
+        assert_eq!(window(5, None, None, None, Some(2)), (3, 5));
+        assert_eq!(window(5, None, Some(3), None, Some(2)), (1, 3));
+        assert_eq!(window(5, Some(9), None, Some(2), None), (5, 5));
+    }
+
+    #[rocket::async_test]
+    async fn mutates_and_pages_supplies_with_their_price_history() {
+        let fixture = Fixture::new();
+        for name in ["cimento", "areia", "brita"] {
+            let created = fixture
+                .run(&format!(
+                    r#"mutation {{ createSupply(input: {{name: "{}", prices: [{{unit: "sc", value: 25.0}}]}}) {{ id version }} }}"#,
+                    name
+                ))
+                .await;
+            assert_eq!(created["data"]["createSupply"]["version"], 1);
+        }
+        assert_eq!(fixture.supplies.len(), 3);
+
+        let page = fixture
+            .run(
+                r#"{ supplies(first: 2) {
+                    edges { cursor node { id name } }
+                    pageInfo { hasNextPage endCursor }
+                } }"#,
+            )
+            .await;
+        let supplies = &page["data"]["supplies"];
+        assert_eq!(supplies["edges"].as_array().unwrap().len(), 2);
+        assert_eq!(supplies["edges"][0]["node"]["name"], "areia");
+        assert_eq!(supplies["pageInfo"]["hasNextPage"], true);
+
+        let areia = supplies["edges"][0]["node"]["id"].as_str().unwrap();
+        let updated = fixture
+            .run(&format!(
+                r#"mutation {{ updateSupply(id: "{}", version: 1, prices: [{{unit: "sc", value: 27.5}}]) {{ version }} }}"#,
+                areia
+            ))
+            .await;
+        assert_eq!(updated["data"]["updateSupply"]["version"], 2);
+        // A supply added before the cursor doesn't shift the next page
+        fixture
+            .run(r#"mutation { createSupply(input: {name: "abrasivo", prices: [{unit: "un", value: 3.0}]}) { id } }"#)
+            .await;
+
+        let after = supplies["pageInfo"]["endCursor"].as_str().unwrap();
+        let rest = fixture
+            .run(&format!(
+                r#"{{ supplies(after: "{}") {{ edges {{ node {{ name }} }} pageInfo {{ hasNextPage }} }} }}"#,
+                after
+            ))
+            .await;
+        assert_eq!(
+            rest["data"]["supplies"]["edges"][0]["node"]["name"],
+            "cimento"
+        );
+        assert_eq!(rest["data"]["supplies"]["pageInfo"]["hasNextPage"], false);
+
+        let previous = fixture
+            .run(&format!(
+                r#"{{ supplies(before: "{}", last: 1) {{ edges {{ node {{ name }} }} pageInfo {{ hasPreviousPage }} }} }}"#,
+                after
+            ))
+            .await;
+        assert_eq!(
+            previous["data"]["supplies"]["edges"][0]["node"]["name"],
+            "areia"
+        );
+        assert_eq!(
+            previous["data"]["supplies"]["pageInfo"]["hasPreviousPage"],
+            true
+        );
+
+        let history = fixture
+            .run(r#"{ supplies { edges { node { name priceHistory { prices { value } actor } } } } }"#)
+            .await;
+        assert_eq!(
+            history["data"]["supplies"]["edges"][1]["node"]["priceHistory"],
+            json!([
+                {"prices": [{"value": 25.0}], "actor": "alice"},
+                {"prices": [{"value": 27.5}], "actor": "alice"}
+            ])
+        );
+    }
+
+    #[rocket::async_test]
+    async fn returns_domain_validation_errors_as_problems() {
+        let fixture = Fixture::new();
+
+        let response = fixture
+            .run(r#"mutation { createSupply(input: {name: "", prices: []}) { id } }"#)
+            .await;
+
+        let error = &response["errors"][0];
+        assert_eq!(error["extensions"]["status"], 422);
+        let fields = error["extensions"]["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(fields.contains(&"name"));
+        assert_eq!(fixture.supplies.len(), 0);
+    }
+
+    #[rocket::async_test]
+    async fn tells_errors_in_the_locale_of_the_request() {
+        let fixture = Fixture::new();
+
+        let response = fixture
+            .run_in(r#"{ supply(id: "s1") { id } }"#, Locale::PtBr)
+            .await;
+
+        let error = &response["errors"][0];
+        assert_eq!(error["message"], "insumo com id 's1' não foi encontrado");
+        assert_eq!(error["extensions"]["status"], 404);
+    }
+
+    #[rocket::async_test]
+    async fn pages_default_to_50_supplies_and_reject_more_than_500() {
+        let fixture = Fixture::new();
+        for i in 0..51 {
+            fixture
+                .run(&format!(
+                    r#"mutation {{ createSupply(input: {{name: "item {:02}", prices: [{{unit: "un", value: 1.0}}]}}) {{ id }} }}"#,
+                    i
+                ))
+                .await;
+        }
+
+        let page = fixture
+            .run(r#"{ supplies { edges { node { name } } pageInfo { hasNextPage } } }"#)
+            .await;
+        assert_eq!(
+            page["data"]["supplies"]["edges"].as_array().unwrap().len(),
+            50
+        );
+        assert_eq!(page["data"]["supplies"]["pageInfo"]["hasNextPage"], true);
+
+        let response = fixture
+            .run(r#"{ supplies(first: 501) { edges { node { name } } } }"#)
+            .await;
+        assert_eq!(response["errors"][0]["extensions"]["status"], 400);
+    }
+}
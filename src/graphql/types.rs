@@ -0,0 +1,128 @@
+use super::loaders::PriceHistoryLoader;
+use crate::application::supply::price_history::PriceChange;
+use crate::application::supply::SupplyOutput;
+use crate::domain::supply::price::Price;
+use crate::posts::Post;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::Context;
+use async_graphql::InputObject;
+use async_graphql::Object;
+use async_graphql::SimpleObject;
+use async_graphql::ID;
+use chrono::DateTime;
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Price")]
+pub struct PriceObject {
+    pub unit: String,
+    pub value: f64,
+}
+
+impl From<&Price> for PriceObject {
+    fn from(price: &Price) -> Self {
+        Self {
+            unit: price.get_unit().to_string(),
+            value: price.get_value().to_f64().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct PriceInput {
+    pub unit: String,
+    pub value: f64,
+}
+
+impl PriceInput {
+    /// Reads the price the way the REST API deserializes it, so both reach
+    /// the domain validation with the same values.
+    pub fn into_price(self) -> async_graphql::Result<Price> {
+        serde_json::from_value(serde_json::json!({ "unit": self.unit, "value": self.value }))
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "PriceChange")]
+pub struct PriceChangeObject {
+    pub prices: Vec<PriceObject>,
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<PriceChange> for PriceChangeObject {
+    fn from(change: PriceChange) -> Self {
+        Self {
+            prices: change.prices.iter().map(PriceObject::from).collect(),
+            actor: change.actor,
+            occurred_at: change.occurred_at,
+        }
+    }
+}
+
+pub struct SupplyObject(pub SupplyOutput);
+
+#[Object(name = "Supply")]
+impl SupplyObject {
+    async fn id(&self) -> ID {
+        ID(self.0.id.to_owned())
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn prices(&self) -> Vec<PriceObject> {
+        self.0.prices.iter().map(PriceObject::from).collect()
+    }
+
+    /// Optimistic concurrency version, sent back by `updateSupply`.
+    async fn version(&self) -> i64 {
+        self.0.version
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.0.updated_at
+    }
+
+    /// Prices over time, oldest first. Loaded in one batch for all the
+    /// supplies of a query.
+    async fn price_history(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<PriceChangeObject>> {
+        let history = ctx
+            .data::<DataLoader<PriceHistoryLoader>>()?
+            .load_one(self.0.id.to_owned())
+            .await
+            .map_err(|e| e.as_ref().to_owned())?
+            .unwrap_or_default();
+        Ok(history.into_iter().map(PriceChangeObject::from).collect())
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Post")]
+pub struct PostObject {
+    pub id: Option<i64>,
+    pub title: String,
+    pub text: String,
+    pub published: bool,
+}
+
+impl From<Post> for PostObject {
+    fn from(post: Post) -> Self {
+        Self {
+            id: post.id,
+            title: post.title,
+            text: post.text,
+            published: post.published,
+        }
+    }
+}
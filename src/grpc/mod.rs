@@ -0,0 +1,743 @@
+// tonic::Status is the error of every service method and interceptor.
+#![allow(clippy::result_large_err)]
+
+use crate::application::audit::AuditContext;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::create_supply::CreateSupplyUseCase;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventFilter;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::application::supply::get_supply::GetSupplyUseCase;
+use crate::application::supply::list_supplies::ListSuppliesUseCase;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::update_supply::UpdateSupplyUseCase;
+use crate::application::supply::SupplyOutput;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::infra::adapters::rabbitmq_adapter::Offset;
+use crate::infra::db::supply_gateway::SupplyPostgresGateway;
+use crate::infra::db::DbSqlx;
+use crate::middler::auth::Claims;
+use crate::middler::auth::JwtConfig;
+use crate::middler::locale::LOCALE_CLAIM;
+use crate::middler::request_id;
+use crate::middler::request_id::RequestId;
+use crate::middler::tenant::TenancyConfig;
+use crate::problem::Problem;
+use crate::supply_events::SupplyEventStream;
+use chrono::DateTime;
+use chrono::Utc;
+use proto::supply_service_server::SupplyService;
+use proto::supply_service_server::SupplyServiceServer;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::futures::stream;
+use rocket::futures::Stream;
+use rocket::tokio;
+use rocket::Build;
+use rocket::Orbit;
+use rocket::Rocket;
+use rocket_db_pools::sqlx::PgPool;
+use rocket_db_pools::Database;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Server;
+use tonic::Code;
+use tonic::Request;
+use tonic::Response;
+use tonic::Status;
+use tracing::error;
+use tracing::info;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("solution.supply.v1");
+}
+
+/*
+[default.grpc]
+enabled = true
+address = "127.0.0.1"
+port = 50051
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_address")]
+    pub address: IpAddr,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_address() -> IpAddr {
+    IpAddr::from([127, 0, 0, 1])
+}
+
+fn default_port() -> u16 {
+    50051
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            address: default_address(),
+            port: default_port(),
+        }
+    }
+}
+
+pub const TENANT_METADATA: &str = "x-tenant-id";
+pub const AUTHORIZATION_METADATA: &str = "authorization";
+pub const REQUEST_ID_METADATA: &str = "x-request-id";
+pub const ACCEPT_LANGUAGE_METADATA: &str = "accept-language";
+
+/// Authenticates calls by the bearer token of their `authorization` metadata
+/// and resolves their tenant the way the `Tenant` guard does for a token,
+/// leaving the `AuditContext` and `Locale` of the call in its extensions.
+#[derive(Clone)]
+pub struct Authenticator {
+    jwt: Option<JwtConfig>,
+    tenancy: TenancyConfig,
+}
+
+impl Authenticator {
+    pub fn new(jwt: Option<JwtConfig>, tenancy: TenancyConfig) -> Self {
+        Self { jwt, tenancy }
+    }
+
+    pub fn authenticate(&self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let (context, locale) = self.context(request.metadata())?;
+        request.extensions_mut().insert(context);
+        request.extensions_mut().insert(locale);
+        Ok(request)
+    }
+
+    /// The context of the call, and its locale: the `locale` claim of the
+    /// token, else the `accept-language` metadata.
+    fn context(&self, metadata: &MetadataMap) -> Result<(AuditContext, Locale), Status> {
+        let value = |key: &str| metadata.get(key).and_then(|value| value.to_str().ok());
+        let Some(jwt) = &self.jwt else {
+            return Err(Status::unauthenticated("no token can be verified"));
+        };
+        let token = value(AUTHORIZATION_METADATA)
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+        let claims =
+            Claims(Some(jwt.verify(token.trim()).ok_or_else(|| {
+                Status::unauthenticated("invalid bearer token")
+            })?));
+        let actor = claims
+            .get_str("sub")
+            .ok_or_else(|| Status::unauthenticated("the token has no subject"))?;
+
+        let tenant_id = match self.tenancy.tenant_of(&claims, value(TENANT_METADATA)) {
+            Some(tenant_id) if TenantId::is_valid(&tenant_id) => TenantId::from_str(&tenant_id),
+            Some(tenant_id) => {
+                return Err(Status::invalid_argument(format!(
+                    "'{}' is not a valid tenant id",
+                    tenant_id
+                )))
+            }
+            None => {
+                return Err(Status::permission_denied(
+                    "the token names no tenant, or not the one of the x-tenant-id metadata",
+                ))
+            }
+        };
+        let request_id = value(REQUEST_ID_METADATA)
+            .filter(|id| RequestId::is_valid(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| RequestId::generate().0);
+
+        let locale = claims
+            .get_str(LOCALE_CLAIM)
+            .and_then(Locale::parse)
+            .or_else(|| value(ACCEPT_LANGUAGE_METADATA).and_then(Locale::negotiate))
+            .unwrap_or_default();
+
+        Ok((
+            AuditContext::new(actor, tenant_id, Some(&request_id)),
+            locale,
+        ))
+    }
+}
+
+fn context_of<T>(request: &Request<T>) -> Result<AuditContext, Status> {
+    request
+        .extensions()
+        .get::<AuditContext>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("call was not authenticated"))
+}
+
+fn locale_of<T>(request: &Request<T>) -> Locale {
+    request
+        .extensions()
+        .get::<Locale>()
+        .copied()
+        .unwrap_or_default()
+}
+
+/// The gRPC status of the problem a notification maps to in the REST API,
+/// told in `locale`.
+fn status(notification: Notification, locale: Locale) -> Status {
+    let problem = Problem::from(notification).localize(locale);
+    let code = match problem.status {
+        400 | 422 => Code::InvalidArgument,
+        404 => Code::NotFound,
+        412 => Code::FailedPrecondition,
+        502 | 503 => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    Status::new(code, problem.detail)
+}
+
+fn timestamp(at: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn to_proto_price(price: &Price) -> proto::Price {
+    proto::Price {
+        unit: price.get_unit().to_string(),
+        value: price.get_value_formatted(),
+    }
+}
+
+fn from_proto_price(price: proto::Price) -> Result<Price, Status> {
+    let mut value = Decimal::from_str_exact(price.value.trim()).map_err(|_| {
+        Status::invalid_argument(format!("'{}' is not a decimal price", price.value))
+    })?;
+    value.rescale(2);
+    let cents = i64::try_from(value.mantissa())
+        .map_err(|_| Status::invalid_argument(format!("'{}' is out of range", price.value)))?;
+    Ok(Price::new(&price.unit, cents))
+}
+
+fn to_proto_supply(supply: SupplyOutput) -> proto::Supply {
+    proto::Supply {
+        id: supply.id,
+        tenant_id: supply.tenant_id,
+        name: supply.name,
+        prices: supply.prices.iter().map(to_proto_price).collect(),
+        version: supply.version,
+        created_at: Some(timestamp(supply.created_at)),
+        updated_at: supply.updated_at.map(timestamp),
+    }
+}
+
+/// The price event of `event`, if it changed prices.
+fn to_price_event(offset: u64, event: SupplyEvent) -> Option<proto::PriceEvent> {
+    let prices = |prices: &[Price]| prices.iter().map(to_proto_price).collect();
+    let event = match event {
+        SupplyEvent::SupplyCreated {
+            supply_id,
+            name,
+            prices: current,
+            occurred_at,
+            ..
+        } => proto::PriceEvent {
+            offset,
+            kind: proto::price_event::Kind::Created.into(),
+            supply_id,
+            name,
+            previous_prices: Vec::new(),
+            prices: prices(&current),
+            occurred_at: Some(timestamp(occurred_at)),
+        },
+        SupplyEvent::SupplyPriceChanged {
+            supply_id,
+            name,
+            previous_prices,
+            prices: current,
+            occurred_at,
+            ..
+        } => proto::PriceEvent {
+            offset,
+            kind: proto::price_event::Kind::PriceChanged.into(),
+            supply_id,
+            name,
+            previous_prices: prices(&previous_prices),
+            prices: prices(&current),
+            occurred_at: Some(timestamp(occurred_at)),
+        },
+        SupplyEvent::SupplyRenamed { .. } | SupplyEvent::SupplyDeleted { .. } => return None,
+    };
+    Some(event)
+}
+
+/// Builds the gateways of the tenant of a call.
+pub trait TenantGateways: Send + Sync + 'static {
+    fn supplies(&self, tenant_id: &TenantId) -> Arc<dyn SupplyGateway>;
+}
+
+pub struct PostgresGateways(pub PgPool);
+
+impl TenantGateways for PostgresGateways {
+    fn supplies(&self, tenant_id: &TenantId) -> Arc<dyn SupplyGateway> {
+        Arc::new(SupplyPostgresGateway::new(
+            self.0.to_owned(),
+            tenant_id.to_owned(),
+        ))
+    }
+}
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// The supply catalog over gRPC, running the same use cases as the REST API.
+pub struct SupplyGrpcService {
+    gateways: Arc<dyn TenantGateways>,
+    events: Arc<dyn SupplyEventPublisher>,
+    stream: SupplyEventStream,
+}
+
+impl SupplyGrpcService {
+    pub fn new(
+        gateways: Arc<dyn TenantGateways>,
+        events: Arc<dyn SupplyEventPublisher>,
+        stream: SupplyEventStream,
+    ) -> Self {
+        Self {
+            gateways,
+            events,
+            stream,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SupplyService for SupplyGrpcService {
+    type ListSuppliesStream = ResponseStream<proto::Supply>;
+    type WatchPricesStream = ResponseStream<proto::PriceEvent>;
+
+    async fn get_supply(
+        &self,
+        request: Request<proto::GetSupplyRequest>,
+    ) -> Result<Response<proto::Supply>, Status> {
+        let context = context_of(&request)?;
+        let locale = locale_of(&request);
+        GetSupplyUseCase::new(self.gateways.supplies(&context.tenant_id))
+            .execute(&request.into_inner().id)
+            .await
+            .map(|supply| Response::new(to_proto_supply(supply)))
+            .map_err(|e| status(e, locale))
+    }
+
+    async fn list_supplies(
+        &self,
+        request: Request<proto::ListSuppliesRequest>,
+    ) -> Result<Response<Self::ListSuppliesStream>, Status> {
+        let context = context_of(&request)?;
+        let locale = locale_of(&request);
+        let supplies = ListSuppliesUseCase::new(self.gateways.supplies(&context.tenant_id))
+            .execute()
+            .await
+            .map_err(|e| status(e, locale))?;
+
+        let supplies = supplies
+            .into_iter()
+            .map(|supply| Ok(to_proto_supply(supply)));
+        Ok(Response::new(Box::pin(stream::iter(supplies))))
+    }
+
+    async fn upsert_supply(
+        &self,
+        request: Request<proto::UpsertSupplyRequest>,
+    ) -> Result<Response<proto::Supply>, Status> {
+        let context = context_of(&request)?;
+        let locale = locale_of(&request);
+        let request = request.into_inner();
+        let prices = request
+            .prices
+            .into_iter()
+            .map(from_proto_price)
+            .collect::<Result<Vec<_>, _>>()?;
+        let tenant_id = &context.tenant_id;
+        let supplies = self.gateways.supplies(tenant_id);
+
+        let upsert = async {
+            match request.id {
+                None => {
+                    let input = CreateSupplyInput {
+                        name: request.name,
+                        prices,
+                    };
+                    CreateSupplyUseCase::new(supplies, self.events.clone())
+                        .execute(&context, input)
+                        .await
+                }
+                Some(id) => {
+                    let input = UpdateSupplyInput {
+                        name: Some(request.name),
+                        prices: Some(prices),
+                    };
+                    UpdateSupplyUseCase::new(supplies, self.events.clone())
+                        .execute(&context, &id, request.version, input)
+                        .await
+                }
+            }
+        };
+        let request_id = context.request_id.to_owned().unwrap_or_default();
+        request_id::scope(request_id, upsert)
+            .await
+            .map(|supply| Response::new(to_proto_supply(supply)))
+            .map_err(|e| status(e, locale))
+    }
+
+    async fn watch_prices(
+        &self,
+        request: Request<proto::WatchPricesRequest>,
+    ) -> Result<Response<Self::WatchPricesStream>, Status> {
+        let context = context_of(&request)?;
+        let request = request.into_inner();
+        let filter = SupplyEventFilter {
+            tenant_id: context.tenant_id.get_value().to_string(),
+            supply_id: request.supply_id,
+            unit: request.unit,
+        };
+        let offset = match request.after_offset {
+            Some(offset) => Offset::Offset(offset + 1),
+            None => Offset::Next,
+        };
+        let subscription = self
+            .stream
+            .subscribe(filter, offset)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let events = stream::unfold(subscription, |mut subscription| async move {
+            loop {
+                let (offset, event) = subscription.next().await?;
+                if let Some(event) = to_price_event(offset, event) {
+                    return Some((Ok(event), subscription));
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Serves the gRPC API next to Rocket once it lifts off, until it shuts
+/// down. Must be attached after `DbSqlx::init()` and `SupplyEvents`.
+pub struct Grpc;
+
+#[rocket::async_trait]
+impl Fairing for Grpc {
+    fn info(&self) -> Info {
+        Info {
+            name: "gRPC server",
+            kind: Kind::Ignite | Kind::Liftoff,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket.figment().extract_inner::<GrpcConfig>("grpc") {
+            Ok(config) => config,
+            Err(e) if e.missing() => GrpcConfig::default(),
+            Err(e) => {
+                error!("invalid grpc config: {}", e);
+                return Err(rocket);
+            }
+        };
+        Ok(rocket.manage(config))
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(config) = rocket.state::<GrpcConfig>().filter(|config| config.enabled) else {
+            return;
+        };
+        let (Some(db), Some(stream)) = (DbSqlx::fetch(rocket), rocket.state::<SupplyEventStream>())
+        else {
+            error!("gRPC server needs the sqlx pool and supply events");
+            return;
+        };
+
+        let service = SupplyGrpcService::new(
+            Arc::new(PostgresGateways(db.pool())),
+            stream.publisher(),
+            stream.to_owned(),
+        );
+        let authenticator = Authenticator::new(
+            rocket.state::<JwtConfig>().cloned(),
+            rocket.state::<TenancyConfig>().cloned().unwrap_or_default(),
+        );
+        let address = SocketAddr::new(config.address, config.port);
+        let shutdown = rocket.shutdown();
+        tokio::spawn(async move {
+            info!(%address, "gRPC server listening");
+            let result = Server::builder()
+                .add_service(SupplyServiceServer::with_interceptor(
+                    service,
+                    move |request| authenticator.authenticate(request),
+                ))
+                .serve_with_shutdown(address, shutdown)
+                .await;
+            if let Err(e) = result {
+                error!(%address, error = ?e, "gRPC server failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod grpc_tests {
+    use super::*;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use rocket::futures::StreamExt;
+    use serde_json::json;
+
+    struct InMemoryGateways {
+        supplies: Arc<InMemorySupplyGateway>,
+    }
+
+    impl TenantGateways for InMemoryGateways {
+        fn supplies(&self, _: &TenantId) -> Arc<dyn SupplyGateway> {
+            self.supplies.clone()
+        }
+    }
+
+    fn service(events: Arc<InMemorySupplyEventPublisher>) -> SupplyGrpcService {
+        let gateways = InMemoryGateways {
+            supplies: Arc::new(InMemorySupplyGateway::default()),
+        };
+        SupplyGrpcService::new(
+            Arc::new(gateways),
+            events,
+            SupplyEventStream::new("supplies"),
+        )
+    }
+
+    fn call<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(AuditContext::new(
+            "alice",
+            TenantId::from_str("acme"),
+            None,
+        ));
+        request
+    }
+
+    fn price(unit: &str, value: &str) -> proto::Price {
+        proto::Price {
+            unit: unit.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn authenticator() -> Authenticator {
+        let jwt = JwtConfig {
+            secret: "secret".to_string(),
+            issuer: None,
+            audience: None,
+        };
+        Authenticator::new(Some(jwt), TenancyConfig::default())
+    }
+
+    fn authenticated(
+        token: Option<serde_json::Value>,
+        tenant: Option<&'static str>,
+    ) -> Result<AuditContext, Status> {
+        let request = authenticate(token, tenant, None)?;
+        Ok(request.extensions().get::<AuditContext>().unwrap().clone())
+    }
+
+    fn authenticate(
+        token: Option<serde_json::Value>,
+        tenant: Option<&'static str>,
+        accept_language: Option<&'static str>,
+    ) -> Result<Request<()>, Status> {
+        let mut request = Request::new(());
+        if let Some(claims) = token {
+            let token = jsonwebtoken::encode(
+                &jsonwebtoken::Header::default(),
+                &claims,
+                &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+            )
+            .unwrap();
+            request.metadata_mut().insert(
+                AUTHORIZATION_METADATA,
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+        }
+        if let Some(tenant) = tenant {
+            request
+                .metadata_mut()
+                .insert(TENANT_METADATA, tenant.parse().unwrap());
+        }
+        if let Some(accept_language) = accept_language {
+            request
+                .metadata_mut()
+                .insert(ACCEPT_LANGUAGE_METADATA, accept_language.parse().unwrap());
+        }
+        authenticator().authenticate(request)
+    }
+
+    #[test]
+    fn authenticates_by_token_and_only_acts_on_granted_tenants() {
+        let exp = 4102444800u64;
+        assert_eq!(
+            authenticated(None, Some("acme")).unwrap_err().code(),
+            Code::Unauthenticated
+        );
+
+        let context = authenticated(
+            Some(json!({"sub": "alice", "tenant_id": "acme", "exp": exp})),
+            None,
+        )
+        .unwrap();
+        assert_eq!(context.actor, "alice");
+        assert_eq!(context.tenant_id, TenantId::from_str("acme"));
+        assert!(context.request_id.is_some());
+
+        let granted = json!({"sub": "bob", "tenants": ["acme", "globex"], "exp": exp});
+        let context = authenticated(Some(granted.clone()), Some("globex")).unwrap();
+        assert_eq!(context.tenant_id, TenantId::from_str("globex"));
+        assert_eq!(
+            authenticated(Some(granted), Some("initech"))
+                .unwrap_err()
+                .code(),
+            Code::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn takes_the_locale_from_the_token_then_accept_language() {
+        let exp = 4102444800u64;
+        let locale = |token, accept_language| {
+            let request = authenticate(Some(token), Some("acme"), accept_language).unwrap();
+            locale_of(&request)
+        };
+
+        let claims = json!({"sub": "alice", "tenant_id": "acme", "exp": exp});
+        assert_eq!(locale(claims.clone(), None), Locale::En);
+        assert_eq!(locale(claims, Some("pt-BR,pt;q=0.9")), Locale::PtBr);
+        let claims = json!({"sub": "alice", "tenant_id": "acme", "locale": "en", "exp": exp});
+        assert_eq!(locale(claims, Some("pt-BR")), Locale::En);
+    }
+
+    #[rocket::async_test]
+    async fn upserts_gets_and_lists_supplies() {
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let service = service(events.clone());
+
+        let created = service
+            .upsert_supply(call(proto::UpsertSupplyRequest {
+                id: None,
+                version: None,
+                name: "cimento".to_string(),
+                prices: vec![price("sc", "25.5")],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(created.prices, vec![price("sc", "25.50")]);
+        assert_eq!(created.version, 1);
+
+        let updated = service
+            .upsert_supply(call(proto::UpsertSupplyRequest {
+                id: Some(created.id.to_owned()),
+                version: Some(1),
+                name: "cimento cp2".to_string(),
+                prices: vec![price("sc", "27")],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(updated.version, 2);
+
+        let stale = service
+            .upsert_supply(call(proto::UpsertSupplyRequest {
+                id: Some(created.id.to_owned()),
+                version: Some(1),
+                name: "cimento".to_string(),
+                prices: vec![price("sc", "27")],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(stale.code(), Code::FailedPrecondition);
+
+        let found = service
+            .get_supply(call(proto::GetSupplyRequest {
+                id: created.id.to_owned(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(found.name, "cimento cp2");
+        assert_eq!(found.prices, vec![price("sc", "27.00")]);
+
+        let listed = service
+            .list_supplies(call(proto::ListSuppliesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(listed.len(), 1);
+
+        let names = events
+            .events()
+            .iter()
+            .map(|event| event.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["SupplyCreated", "SupplyPriceChanged", "SupplyRenamed"]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn maps_validation_and_missing_supplies_to_status_codes() {
+        let service = service(Arc::new(InMemorySupplyEventPublisher::default()));
+
+        let invalid = service
+            .upsert_supply(call(proto::UpsertSupplyRequest {
+                id: None,
+                version: None,
+                name: "".to_string(),
+                prices: vec![price("sc", "25")],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+
+        let malformed = service
+            .upsert_supply(call(proto::UpsertSupplyRequest {
+                id: None,
+                version: None,
+                name: "cimento".to_string(),
+                prices: vec![price("sc", "vinte")],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(malformed.code(), Code::InvalidArgument);
+
+        let mut request = call(proto::GetSupplyRequest {
+            id: "nope".to_string(),
+        });
+        request.extensions_mut().insert(Locale::PtBr);
+        let missing = service.get_supply(request).await.unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+        assert_eq!(missing.message(), "insumo com id 'nope' não foi encontrado");
+    }
+}
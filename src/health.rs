@@ -0,0 +1,205 @@
+use crate::application::health;
+use crate::application::health::HealthCheck;
+use crate::application::health::HealthReport;
+use crate::application::health::HealthStatus;
+use crate::infra::adapters::rabbitmq_adapter::RabbitMqHealthCheck;
+use crate::infra::db::health::DieselHealthCheck;
+use crate::infra::db::health::MigrationsHealthCheck;
+use crate::infra::db::health::SqlxHealthCheck;
+use crate::infra::db::Db;
+use crate::infra::db::DbSqlx;
+use crate::middler::payload::Payload;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::get;
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::Build;
+use rocket::Rocket;
+use rocket::State;
+use rocket_db_pools::Database;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::error;
+
+/*
+[default.health]
+timeout_ms = 2000
+rabbitmq = true
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthConfig {
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_rabbitmq")]
+    pub rabbitmq: bool,
+}
+
+fn default_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_rabbitmq() -> bool {
+    true
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout_ms(),
+            rabbitmq: default_rabbitmq(),
+        }
+    }
+}
+
+/// The checks run by `/health/ready`. Subsystems add their own with
+/// `register`, either from a fairing attached after `Health` or at runtime.
+pub struct HealthChecks {
+    timeout: Duration,
+    checks: RwLock<Vec<Arc<dyn HealthCheck>>>,
+}
+
+impl HealthChecks {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            checks: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn register(&self, check: Arc<dyn HealthCheck>) {
+        self.checks.write().unwrap().push(check);
+    }
+
+    pub async fn run(&self) -> HealthReport {
+        let checks = self.checks.read().unwrap().clone();
+        health::run(&checks, self.timeout).await
+    }
+}
+
+/// Registers the readiness checks of the database pools, migrations and
+/// RabbitMQ. Must be attached after the `Database` fairings it checks.
+pub struct Health;
+
+#[rocket::async_trait]
+impl Fairing for Health {
+    fn info(&self) -> Info {
+        Info {
+            name: "Health checks",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket.figment().extract_inner::<HealthConfig>("health") {
+            Ok(config) => config,
+            Err(e) if e.missing() => HealthConfig::default(),
+            Err(e) => {
+                error!("invalid health config: {}", e);
+                return Err(rocket);
+            }
+        };
+
+        let checks = HealthChecks::new(Duration::from_millis(config.timeout_ms));
+        if let Some(db) = DbSqlx::fetch(&rocket) {
+            checks.register(Arc::new(SqlxHealthCheck(db.pool())));
+            checks.register(Arc::new(MigrationsHealthCheck::new(db)));
+        }
+        if let Some(db) = Db::fetch(&rocket) {
+            checks.register(Arc::new(DieselHealthCheck::new(db)));
+        }
+        if config.rabbitmq {
+            checks.register(Arc::new(RabbitMqHealthCheck));
+        }
+
+        Ok(rocket.manage(checks))
+    }
+}
+
+#[utoipa::path(
+    tag = "health",
+    context_path = "/health",
+    responses((status = 200, description = "The process is running", body = HealthReport))
+)]
+#[get("/live")]
+pub fn live() -> Payload<HealthReport> {
+    Payload(HealthReport {
+        status: HealthStatus::Up,
+        checks: BTreeMap::new(),
+    })
+}
+
+#[utoipa::path(
+    tag = "health",
+    context_path = "/health",
+    responses(
+        (status = 200, description = "Every dependency is up", body = HealthReport),
+        (status = 503, description = "At least one dependency is down", body = HealthReport)
+    )
+)]
+#[get("/ready")]
+pub async fn ready(checks: &State<HealthChecks>) -> Custom<Payload<HealthReport>> {
+    let report = checks.run().await;
+    let status = if report.is_up() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    Custom(status, Payload(report))
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+    use serde_json::Value;
+
+    struct Broken;
+
+    #[async_trait]
+    impl HealthCheck for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            Err("unreachable".to_string())
+        }
+    }
+
+    #[test]
+    fn ready_reports_registered_checks() {
+        let figment = rocket::Config::figment().merge(("health.rabbitmq", false));
+        let rocket = rocket::custom(figment)
+            .attach(Health)
+            .mount("/health", routes![live, ready]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client.get("/health/live").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client.get("/health/ready").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<Value>().unwrap()["status"], "up");
+
+        client
+            .rocket()
+            .state::<HealthChecks>()
+            .unwrap()
+            .register(Arc::new(Broken));
+
+        let response = client.get("/health/ready").dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let body = response.into_json::<Value>().unwrap();
+        assert_eq!(body["status"], "down");
+        assert_eq!(body["checks"]["broken"]["error"], "unreachable");
+        assert!(body["checks"]["broken"]["latency_ms"].is_u64());
+    }
+}
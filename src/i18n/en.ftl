@@ -0,0 +1,111 @@
+## Validation. Messages about a field start with its name in quotes; the
+## field itself is given by the code raising them, not read from here.
+
+id-empty = 'id' should not be empty
+tenant-id-invalid = 'tenant_id' must be a non-empty lowercase identifier
+name-empty = 'name' should not be empty
+name-length = 'name' must be between { $min } and { $max } characters
+price-empty = 'price' should not be empty
+price-not-a-number = 'price' of '{ $unit }' must be a number, like 12.50
+price-invalid = 'price' is not a valid number: '{ $value }'
+price-out-of-range = 'price' is out of range
+unit-empty = 'unit' should not be empty
+supplier-invalid = 'supplier' must be a non-empty lowercase identifier
+
+## Price list imports and their mapping profiles
+
+file-unreadable = 'file' could not be read: { $error }
+file-no-header = 'file' has no header
+row-unreadable = the row could not be read: { $error }
+column-missing = '{ $field }' column is missing from the header
+column-missing-named = '{ $field }' column '{ $column }' is missing from the header
+skip-column-missing = 'skip' column '{ $column }' is missing from the header
+delimiter-invalid = 'format' delimiter must be a single ASCII character
+columns-empty = 'columns' should not be empty
+unit-aliases-empty = 'unit_aliases' should not have empty units
+transforms-multiply-prices-only = 'transforms' can only multiply prices
+transforms-factor-not-positive = 'transforms' factors must be positive
+skip-columns-empty = 'skip' columns should not be empty
+
+## Exports
+
+export-format-invalid = 'format' must be csv, ndjson or xlsx, not '{ $value }'
+export-column-unknown = 'columns' has no '{ $name }'
+export-locale-invalid = 'locale' must be en or pt-BR, not '{ $value }'
+
+## Audit trail
+
+audit-action-invalid = 'action' must be one of create, update or delete, got '{ $value }'
+timestamp-invalid = '{ $name }' must be an RFC 3339 timestamp
+
+## Attachments
+
+file-empty = 'file' should not be empty
+file-type-unsupported = 'file' must be a PDF, JPEG, PNG or WebP file
+file-type-mismatch = 'file' is { $detected } but was sent as { $declared }
+file-malformed = 'file' is a malformed { $type } file
+file-name-too-long = 'name' must have at most { $max } characters
+
+## Missing and conflicting resources
+
+supply-not-found = supply with id '{ $id }' was not found
+supply-not-on-list = supply with id '{ $id }' is not on this list
+supply-version-conflict = supply '{ $id }' is at version { $current }, not { $expected }
+supplies-changed = { $count } of the supplies changed while they were being saved
+mapping-profile-not-found = mapping profile of supplier '{ $supplier }' was not found
+attachment-not-found = attachment with id '{ $id }' was not found for supply '{ $supply_id }'
+derivative-not-found = attachment '{ $id }' has no derivative '{ $name }'
+derivative-not-ready = derivative '{ $name }' of attachment '{ $id }' is not ready yet
+
+## Problem bodies
+
+problem-validation-title = Validation failed
+problem-validation-detail = the request has { $count } invalid field(s)
+problem-not-found-title = Resource not found
+problem-version-conflict-title = Version conflict
+problem-page-too-large-title = Page too large
+problem-page-too-large = 'first' and 'last' may ask for up to { $max } supplies
+
+## Admin UI
+
+admin-brand = Supply catalog
+admin-log-in = Log in
+admin-log-out = Log out
+admin-user = User
+admin-password = Password
+admin-login-failed = Unknown user or wrong password
+admin-logged-out = Logged out
+admin-supplies = Supplies
+admin-search = Search
+admin-search-label = Search by name
+admin-search-placeholder = Name contains
+admin-new-supply = New supply
+admin-edit-supply = Edit supply
+admin-name = Name
+admin-prices = Prices
+admin-unit = Unit
+admin-price = Price
+admin-updated = Updated
+admin-no-supplies = No supplies.
+admin-no-supplies-match = No supplies match "{ $q }".
+admin-previous = Previous
+admin-next = Next
+admin-page = Page { $page } of { $pages }, { $total ->
+        [one] 1 supply
+       *[other] { $total } supplies
+    }
+admin-supply-created = Version { $version }, created { $created_at }
+admin-supply-updated = Version { $version }, created { $created_at }, updated { $updated_at }
+admin-edit = Edit
+admin-delete = Delete
+admin-save = Save
+admin-cancel = Cancel
+admin-current-prices = Current prices
+admin-price-history = Price history
+admin-since = Since
+admin-changed-by = Changed by
+admin-no-price-changes = No price changes recorded.
+admin-back = Back to supplies
+admin-created = Created '{ $name }'
+admin-saved = Saved '{ $name }'
+admin-deleted = Supply deleted
@@ -0,0 +1,321 @@
+use fluent::concurrent::FluentBundle;
+use fluent::FluentArgs;
+use fluent::FluentResource;
+use rocket_dyn_templates::handlebars::html_escape;
+use rocket_dyn_templates::handlebars::Context;
+use rocket_dyn_templates::handlebars::Handlebars;
+use rocket_dyn_templates::handlebars::Helper;
+use rocket_dyn_templates::handlebars::HelperResult;
+use rocket_dyn_templates::handlebars::Output;
+use rocket_dyn_templates::handlebars::RenderContext;
+use rocket_dyn_templates::handlebars::RenderError;
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt;
+use std::sync::LazyLock;
+use unic_langid::langid;
+use unic_langid::LanguageIdentifier;
+
+/// The languages messages are written in. English is the fallback for
+/// anything the others do not translate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    PtBr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::PtBr];
+
+    /// The locale of a language tag. Any Portuguese reads pt-BR and any
+    /// English reads en.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Self::En),
+            "pt" => Some(Self::PtBr),
+            _ => None,
+        }
+    }
+
+    /// The supported locale the client prefers most in an `Accept-Language`
+    /// header, as in `pt-BR,pt;q=0.9,en;q=0.8`.
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut ranges = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((tag, quality))
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges.into_iter().find_map(|(tag, _)| Self::parse(tag))
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::PtBr => "pt-BR",
+        }
+    }
+
+    fn langid(&self) -> LanguageIdentifier {
+        match self {
+            Self::En => langid!("en"),
+            Self::PtBr => langid!("pt-BR"),
+        }
+    }
+
+    fn catalog(&self) -> &'static str {
+        match self {
+            Self::En => include_str!("en.ftl"),
+            Self::PtBr => include_str!("pt-BR.ftl"),
+        }
+    }
+
+    /// The text of `code` without arguments.
+    pub fn text(&self, code: &'static str) -> String {
+        Message::new(code).render(*self)
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl Serialize for Locale {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.tag())
+    }
+}
+
+static CATALOGS: LazyLock<Vec<(Locale, FluentBundle<FluentResource>)>> = LazyLock::new(|| {
+    Locale::ALL
+        .iter()
+        .map(|locale| {
+            let resource = FluentResource::try_new(locale.catalog().to_string())
+                .unwrap_or_else(|(_, errors)| panic!("invalid {} catalog: {:?}", locale, errors));
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.langid()]);
+            // Messages go to JSON bodies and HTML, where Unicode isolation
+            // marks around the arguments would only get in the way.
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|errors| panic!("invalid {} catalog: {:?}", locale, errors));
+            (*locale, bundle)
+        })
+        .collect()
+});
+
+fn bundle(locale: Locale) -> &'static FluentBundle<FluentResource> {
+    CATALOGS
+        .iter()
+        .find(|(candidate, _)| *candidate == locale)
+        .map(|(_, bundle)| bundle)
+        .expect("every locale has a catalog")
+}
+
+/// A value a message is formatted with. Numbers pick the plural form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arg {
+    Text(String),
+    Number(i64),
+}
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::Text(value.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::Text(value)
+    }
+}
+
+impl From<i64> for Arg {
+    fn from(value: i64) -> Self {
+        Arg::Number(value)
+    }
+}
+
+impl From<usize> for Arg {
+    fn from(value: usize) -> Self {
+        Arg::Number(value as i64)
+    }
+}
+
+/// A message of the catalogs by its code, with the arguments it is formatted
+/// with and the input field it is about. It is rendered in the locale of
+/// whoever reads it, and in English when displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub code: &'static str,
+    pub args: Vec<(&'static str, Arg)>,
+    pub field: Option<&'static str>,
+}
+
+impl Message {
+    pub fn new(code: &'static str) -> Self {
+        Self {
+            code,
+            args: Vec::new(),
+            field: None,
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl Into<Arg>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+
+    /// The message about the input `field`, whatever its translations say.
+    pub fn for_field(mut self, field: &'static str) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    /// The message in `locale`.
+    pub fn render(&self, locale: Locale) -> String {
+        render(self.code, &self.args, locale)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Locale::En))
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// `{{t "code" name=value}}` renders a message of the catalogs in the
+/// `locale` of the template context. The message is HTML escaped, as its
+/// arguments may come from the visitor.
+fn translate(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let code = h
+        .param(0)
+        .and_then(|param| param.value().as_str())
+        .ok_or_else(|| RenderError::new("t needs a message code"))?;
+    let locale = ctx
+        .data()
+        .get("locale")
+        .and_then(|locale| locale.as_str())
+        .and_then(Locale::parse)
+        .unwrap_or_default();
+    let args = h
+        .hash()
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.value() {
+                serde_json::Value::Number(number) => match number.as_i64() {
+                    Some(number) => Arg::Number(number),
+                    None => Arg::Text(number.to_string()),
+                },
+                serde_json::Value::String(text) => Arg::Text(text.to_owned()),
+                other => Arg::Text(other.to_string()),
+            };
+            (*name, value)
+        })
+        .collect::<Vec<_>>();
+    out.write(&html_escape(&render(code, &args, locale)))?;
+    Ok(())
+}
+
+/// `code` in `locale`, or in English when `locale` does not translate it.
+/// Unknown codes render as themselves.
+fn render(code: &str, args: &[(&str, Arg)], locale: Locale) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        match value {
+            Arg::Text(text) => fluent_args.set(*name, text.as_str()),
+            Arg::Number(number) => fluent_args.set(*name, *number),
+        }
+    }
+    [locale, Locale::En]
+        .into_iter()
+        .find_map(|locale| {
+            let bundle = bundle(locale);
+            let pattern = bundle.get_message(code)?.value()?;
+            let mut errors = Vec::new();
+            Some(
+                bundle
+                    .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| code.to_string())
+}
+
+pub fn register_helpers(handlebars: &mut Handlebars<'static>) {
+    handlebars.register_helper("t", Box::new(translate));
+}
+
+#[cfg(test)]
+mod i18n_tests {
+    use super::*;
+
+    #[test]
+    fn translates_every_message_of_the_english_catalog() {
+        let missing = Locale::En
+            .catalog()
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(code, _)| code)
+            .filter(|code| !code.starts_with(' ') && !bundle(Locale::PtBr).has_message(code))
+            .collect::<Vec<_>>();
+        assert!(missing.is_empty(), "missing in pt-BR: {:?}", missing);
+    }
+
+    #[test]
+    fn renders_messages_with_their_arguments() {
+        let message = Message::new("price-not-a-number").arg("unit", "sc");
+        assert_eq!(
+            message.to_string(),
+            "'price' of 'sc' must be a number, like 12.50"
+        );
+        assert_eq!(
+            message.render(Locale::PtBr),
+            "'price' de 'sc' deve ser um número, como 12,50"
+        );
+
+        let detail = |count: usize| {
+            Message::new("problem-validation-detail")
+                .arg("count", count)
+                .render(Locale::PtBr)
+        };
+        assert_eq!(detail(1), "a requisição tem 1 campo inválido");
+        assert_eq!(detail(3), "a requisição tem 3 campos inválidos");
+
+        assert_eq!(
+            Message::new("no-such-code").render(Locale::PtBr),
+            "no-such-code"
+        );
+    }
+
+    #[test]
+    fn reads_language_tags() {
+        assert_eq!(Locale::parse("pt-BR"), Some(Locale::PtBr));
+        assert_eq!(Locale::parse("pt_PT"), Some(Locale::PtBr));
+        assert_eq!(Locale::parse("EN-us"), Some(Locale::En));
+        assert_eq!(Locale::parse("es"), None);
+    }
+}
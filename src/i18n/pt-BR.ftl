@@ -0,0 +1,117 @@
+## Validação. Mensagens sobre um campo começam com o nome dele entre aspas;
+## o campo em si vem do código que as gera, não é lido daqui.
+
+id-empty = 'id' não deve ficar vazio
+tenant-id-invalid = 'tenant_id' deve ser um identificador em minúsculas, não vazio
+name-empty = 'name' não deve ficar vazio
+name-length = 'name' deve ter entre { $min } e { $max } caracteres
+price-empty = 'price' não deve ficar vazio
+price-not-a-number = 'price' de '{ $unit }' deve ser um número, como 12,50
+price-invalid = 'price' não é um número válido: '{ $value }'
+price-out-of-range = 'price' está fora do intervalo permitido
+unit-empty = 'unit' não deve ficar vazio
+supplier-invalid = 'supplier' deve ser um identificador em minúsculas, não vazio
+
+## Importação de listas de preços e seus perfis de mapeamento
+
+file-unreadable = 'file' não pôde ser lido: { $error }
+file-no-header = 'file' não tem cabeçalho
+row-unreadable = a linha não pôde ser lida: { $error }
+column-missing = a coluna '{ $field }' não está no cabeçalho
+column-missing-named = a coluna '{ $column }' de '{ $field }' não está no cabeçalho
+skip-column-missing = a coluna '{ $column }' de 'skip' não está no cabeçalho
+delimiter-invalid = o delimitador de 'format' deve ser um único caractere ASCII
+columns-empty = 'columns' não deve ficar vazio
+unit-aliases-empty = 'unit_aliases' não deve ter unidades vazias
+transforms-multiply-prices-only = 'transforms' só pode multiplicar preços
+transforms-factor-not-positive = os fatores de 'transforms' devem ser positivos
+skip-columns-empty = as colunas de 'skip' não devem ficar vazias
+
+## Exportações
+
+export-format-invalid = 'format' deve ser csv, ndjson ou xlsx, não '{ $value }'
+export-column-unknown = 'columns' não tem '{ $name }'
+export-locale-invalid = 'locale' deve ser en ou pt-BR, não '{ $value }'
+
+## Trilha de auditoria
+
+audit-action-invalid = 'action' deve ser create, update ou delete, não '{ $value }'
+timestamp-invalid = '{ $name }' deve ser um instante RFC 3339
+
+## Anexos
+
+file-empty = 'file' não deve ficar vazio
+file-type-unsupported = 'file' deve ser um arquivo PDF, JPEG, PNG ou WebP
+file-type-mismatch = 'file' é { $detected }, mas foi enviado como { $declared }
+file-malformed = 'file' é um arquivo { $type } malformado
+file-name-too-long = 'name' deve ter no máximo { $max } caracteres
+
+## Recursos ausentes e em conflito
+
+supply-not-found = insumo com id '{ $id }' não foi encontrado
+supply-not-on-list = o insumo com id '{ $id }' não está nesta lista
+supply-version-conflict = o insumo '{ $id }' está na versão { $current }, não na { $expected }
+supplies-changed = { $count ->
+        [one] 1 dos insumos mudou enquanto era salvo
+       *[other] { $count } dos insumos mudaram enquanto eram salvos
+    }
+mapping-profile-not-found = perfil de mapeamento do fornecedor '{ $supplier }' não foi encontrado
+attachment-not-found = anexo com id '{ $id }' não foi encontrado para o insumo '{ $supply_id }'
+derivative-not-found = o anexo '{ $id }' não tem a derivada '{ $name }'
+derivative-not-ready = a derivada '{ $name }' do anexo '{ $id }' ainda não está pronta
+
+## Corpos de problema
+
+problem-validation-title = Falha na validação
+problem-validation-detail = { $count ->
+        [one] a requisição tem 1 campo inválido
+       *[other] a requisição tem { $count } campos inválidos
+    }
+problem-not-found-title = Recurso não encontrado
+problem-version-conflict-title = Conflito de versão
+problem-page-too-large-title = Página grande demais
+problem-page-too-large = 'first' e 'last' podem pedir até { $max } insumos
+
+## Interface de administração
+
+admin-brand = Catálogo de insumos
+admin-log-in = Entrar
+admin-log-out = Sair
+admin-user = Usuário
+admin-password = Senha
+admin-login-failed = Usuário desconhecido ou senha incorreta
+admin-logged-out = Sessão encerrada
+admin-supplies = Insumos
+admin-search = Buscar
+admin-search-label = Buscar pelo nome
+admin-search-placeholder = Nome contém
+admin-new-supply = Novo insumo
+admin-edit-supply = Editar insumo
+admin-name = Nome
+admin-prices = Preços
+admin-unit = Unidade
+admin-price = Preço
+admin-updated = Atualizado
+admin-no-supplies = Nenhum insumo.
+admin-no-supplies-match = Nenhum insumo corresponde a "{ $q }".
+admin-previous = Anterior
+admin-next = Próxima
+admin-page = Página { $page } de { $pages }, { $total ->
+        [one] 1 insumo
+       *[other] { $total } insumos
+    }
+admin-supply-created = Versão { $version }, criado em { $created_at }
+admin-supply-updated = Versão { $version }, criado em { $created_at }, atualizado em { $updated_at }
+admin-edit = Editar
+admin-delete = Excluir
+admin-save = Salvar
+admin-cancel = Cancelar
+admin-current-prices = Preços atuais
+admin-price-history = Histórico de preços
+admin-since = Desde
+admin-changed-by = Alterado por
+admin-no-price-changes = Nenhuma alteração de preço registrada.
+admin-back = Voltar aos insumos
+admin-created = '{ $name }' criado
+admin-saved = '{ $name }' salvo
+admin-deleted = Insumo excluído
@@ -1 +1,2 @@
 pub mod rabbitmq_adapter;
+pub mod supply_event_publisher;
@@ -1,21 +1,72 @@
 #![allow(dead_code)]
+use crate::application::health::HealthCheck;
+use crate::metrics;
+use crate::middler::request_id;
+use crate::middler::request_id::RequestId;
+use async_trait::async_trait;
 #[allow(clippy::useless_attribute)]
 use chrono::Utc;
+use rabbitmq_stream_client::error::ConsumerCreateError;
+use rabbitmq_stream_client::error::ProducerCreateError;
 use rabbitmq_stream_client::error::ProducerPublishError;
 use rabbitmq_stream_client::error::StreamCreateError;
 use rabbitmq_stream_client::types::ByteCapacity;
 use rabbitmq_stream_client::types::Delivery;
 use rabbitmq_stream_client::types::Message;
 use rabbitmq_stream_client::types::OffsetSpecification;
+use rabbitmq_stream_client::types::SimpleValue;
 use rabbitmq_stream_client::ConsumerHandle;
 use rabbitmq_stream_client::Environment;
+use rabbitmq_stream_client::NoDedup;
+use rabbitmq_stream_client::Producer;
+use rabbitmq_stream_client::RabbitMQStreamResult;
 use rabbitmq_stream_client::TlsConfiguration;
+use rabbitmq_stream_protocol::ResponseCode;
 use rocket::futures::StreamExt;
+use rocket::tokio::sync::Mutex;
 use rocket::tokio::task;
+use std::collections::HashMap;
 use std::future::Future;
+use thiserror::Error;
+use tracing::error;
+use tracing::info;
+use tracing::info_span;
+use tracing::Instrument;
+
+/// Application property holding the publish time in milliseconds, used to
+/// measure consumer lag.
+const PUBLISHED_AT: &str = "published_at";
+
+fn published_at(delivery: &Delivery) -> Option<i64> {
+    match delivery
+        .message()
+        .application_properties()?
+        .get(PUBLISHED_AT)?
+    {
+        SimpleValue::Long(millis) => Some(*millis),
+        _ => None,
+    }
+}
+
+/// The request id the message was published under, restored by consumers.
+fn correlation_id(delivery: &Delivery) -> Option<String> {
+    let id = delivery.message().properties()?.correlation_id.as_ref()?;
+    <&String>::try_from(id).ok().cloned()
+}
+
+/// Why a message was not published.
+#[derive(Debug, Error)]
+pub enum PublishError {
+    #[error(transparent)]
+    Producer(#[from] ProducerCreateError),
+    #[error(transparent)]
+    Publish(#[from] ProducerPublishError),
+}
 
 pub struct RabbitMqAdapter {
     pub environment: Environment,
+    /// One producer per stream, shared by every publish to it.
+    producers: Mutex<HashMap<String, Producer<NoDedup>>>,
 }
 
 #[allow(dead_code)]
@@ -30,6 +81,12 @@ pub enum Offset {
 
 impl RabbitMqAdapter {
     pub async fn new() -> Self {
+        Self::try_new().await.expect("Failed to create environment")
+    }
+
+    /// Connects to the broker, failing instead of panicking when it is not
+    /// reachable.
+    pub async fn try_new() -> RabbitMQStreamResult<Self> {
         // let tls_configuration = Self::tls_config();
         let environment = Environment::builder()
             .host("localhost")
@@ -39,67 +96,108 @@ impl RabbitMqAdapter {
             .heartbeat(60)
             // .tls(tls_configuration)
             .build()
-            .await
-            .expect("Failed to create environment");
+            .await?;
 
-        Self { environment }
+        Ok(Self {
+            environment,
+            producers: Mutex::new(HashMap::new()),
+        })
     }
 
-    #[allow(dead_code)]
-    pub async fn publish(
-        &self,
-        message: &[u8],
-        stream_name: &str,
-    ) -> Result<(), ProducerPublishError> {
-        let producer_builder = self
-            .environment
-            .producer()
-            .build(stream_name)
-            .await
-            .expect("Failed to create producer");
+    /// Publishes `message` and waits for the broker to confirm it. Failures
+    /// are returned, never panicked on.
+    pub async fn publish(&self, message: &[u8], stream_name: &str) -> Result<(), PublishError> {
+        let producer = match self.producer(stream_name).await {
+            Ok(producer) => producer,
+            Err(e) => {
+                metrics::record_publish(stream_name, false);
+                error!(stream = stream_name, error = ?e, "failed to create producer");
+                return Err(e.into());
+            }
+        };
 
-        let message_build = Message::builder()
+        let mut properties = Message::builder()
             .message_annotations()
             .insert("test", 1)
             .message_builder()
             .application_properties()
             .insert("test", 2)
+            .insert(PUBLISHED_AT, Utc::now().timestamp_millis())
             .message_builder()
             .properties()
             .content_encoding("application/json")
-            .absolute_expiry_time(Utc::now())
-            .message_builder()
-            .body(message)
-            .build();
+            .absolute_expiry_time(Utc::now());
+        if let Some(id) = request_id::current() {
+            properties = properties.correlation_id(id);
+        }
+        let message_build = properties.message_builder().body(message).build();
 
-        let publish_result = producer_builder.send_with_confirm(message_build).await;
+        let publish_result = producer.send_with_confirm(message_build).await;
+        metrics::record_publish(stream_name, publish_result.is_ok());
 
         match publish_result {
             Ok(status) => {
-                println!(
-                    "Message published {} {}",
-                    status.confirmed(),
-                    status.publishing_id()
+                info!(
+                    stream = stream_name,
+                    confirmed = status.confirmed(),
+                    publishing_id = status.publishing_id(),
+                    "message published"
                 );
-                producer_builder
-                    .close()
-                    .await
-                    .expect("Failed to close producer");
                 Ok(())
             }
             Err(e) => {
-                println!("Error publishing message: {:?}", e);
-                Err(e)
+                error!(stream = stream_name, error = ?e, "failed to publish message");
+                self.discard_producer(stream_name).await;
+                Err(e.into())
             }
         }
     }
 
+    /// The producer of `stream_name`, created on first use.
+    async fn producer(&self, stream_name: &str) -> Result<Producer<NoDedup>, ProducerCreateError> {
+        let mut producers = self.producers.lock().await;
+        if let Some(producer) = producers.get(stream_name) {
+            return Ok(producer.clone());
+        }
+        let producer = self.environment.producer().build(stream_name).await?;
+        producers.insert(stream_name.to_string(), producer.clone());
+        Ok(producer)
+    }
+
+    /// Closes the producer of `stream_name` after a failure, so the next
+    /// publish creates a new one.
+    async fn discard_producer(&self, stream_name: &str) {
+        let Some(producer) = self.producers.lock().await.remove(stream_name) else {
+            return;
+        };
+        if let Err(e) = producer.close().await {
+            error!(stream = stream_name, error = ?e, "failed to close producer");
+        }
+    }
+
     pub async fn consumer<F, Fut>(
         &self,
         stream_name: &str,
         offset: Option<Offset>,
         callback: F,
     ) -> ConsumerHandle
+    where
+        F: Fn(Delivery) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.try_consumer(stream_name, offset, callback)
+            .await
+            .expect("failed to create consumer")
+    }
+
+    /// Like `consumer`, failing instead of panicking when the stream cannot
+    /// be consumed.
+    pub async fn try_consumer<F, Fut>(
+        &self,
+        stream_name: &str,
+        offset: Option<Offset>,
+        callback: F,
+    ) -> Result<ConsumerHandle, ConsumerCreateError>
     where
         F: Fn(Delivery) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = ()> + Send + 'static,
@@ -119,23 +217,36 @@ impl RabbitMqAdapter {
             // .name("tag")
             .offset(offset)
             .build(stream_name)
-            .await
-            .expect("failed to create consumer");
+            .await?;
 
         let handle = consumer.handle();
+        let stream_name = stream_name.to_string();
         task::spawn(async move {
             while let Some(delivery) = consumer.next().await {
                 match delivery {
                     Ok(delivery) => {
-                        callback(delivery).await;
+                        metrics::record_delivery(
+                            &stream_name,
+                            delivery.offset(),
+                            published_at(&delivery),
+                        );
+                        let id =
+                            correlation_id(&delivery).unwrap_or_else(|| RequestId::generate().0);
+                        let span = info_span!(
+                            "message",
+                            stream = %stream_name,
+                            offset = delivery.offset(),
+                            request_id = %id,
+                        );
+                        request_id::scope(id, callback(delivery).instrument(span)).await;
                     }
                     Err(e) => {
-                        println!("Error receiving message: {:?}", e);
+                        error!(stream = %stream_name, error = ?e, "failed to receive message");
                     }
                 }
             }
         });
-        handle
+        Ok(handle)
     }
 
     pub async fn create_stream(
@@ -150,6 +261,21 @@ impl RabbitMqAdapter {
             .await
     }
 
+    /// Creates the stream unless it already exists.
+    pub async fn ensure_stream(
+        &self,
+        stream_name: &str,
+        capacity_gb: u64,
+    ) -> Result<(), StreamCreateError> {
+        match self.create_stream(stream_name, capacity_gb).await {
+            Err(StreamCreateError::Create {
+                status: ResponseCode::StreamAlreadyExists,
+                ..
+            }) => Ok(()),
+            result => result,
+        }
+    }
+
     #[allow(dead_code)]
     fn tls_config() -> TlsConfiguration {
         // TlsConfiguration::builder()
@@ -172,3 +298,20 @@ impl RabbitMqAdapter {
         TlsConfiguration::builder().trust_certificates(true).build()
     }
 }
+
+/// Opens and closes a connection to the stream broker.
+pub struct RabbitMqHealthCheck;
+
+#[async_trait]
+impl HealthCheck for RabbitMqHealthCheck {
+    fn name(&self) -> &str {
+        "rabbitmq"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        RabbitMqAdapter::try_new()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,56 @@
+use super::rabbitmq_adapter::RabbitMqAdapter;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use rocket::tokio::sync::OnceCell;
+
+const STREAM_CAPACITY_GB: u64 = 1;
+
+/// Publishes supply events as JSON to a RabbitMQ stream. Connects on first
+/// use and creates the stream if needed; a failed connection is retried by
+/// the next publish.
+pub struct RabbitMqSupplyEventPublisher {
+    stream: String,
+    adapter: OnceCell<RabbitMqAdapter>,
+}
+
+impl RabbitMqSupplyEventPublisher {
+    pub fn new(stream: &str) -> Self {
+        Self {
+            stream: stream.to_string(),
+            adapter: OnceCell::new(),
+        }
+    }
+
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    pub async fn adapter(&self) -> Result<&RabbitMqAdapter, CustomError> {
+        self.adapter
+            .get_or_try_init(|| async {
+                let adapter = RabbitMqAdapter::try_new()
+                    .await
+                    .map_err(|e| CustomError::ApiError(e.to_string()))?;
+                adapter
+                    .ensure_stream(&self.stream, STREAM_CAPACITY_GB)
+                    .await
+                    .map_err(|e| CustomError::ApiError(e.to_string()))?;
+                Ok(adapter)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl SupplyEventPublisher for RabbitMqSupplyEventPublisher {
+    async fn publish(&self, event: &SupplyEvent) -> Result<(), CustomError> {
+        let body = serde_json::to_vec(event).map_err(|e| CustomError::Error(e.to_string()))?;
+        self.adapter()
+            .await?
+            .publish(&body, &self.stream)
+            .await
+            .map_err(|e| CustomError::ApiError(e.to_string()))
+    }
+}
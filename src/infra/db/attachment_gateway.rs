@@ -0,0 +1,166 @@
+use super::audit_gateway;
+use super::scope_to_tenant;
+use super::supply_gateway::repository_error;
+use crate::application::attachment::Attachment;
+use crate::application::attachment::AttachmentGateway;
+use crate::application::audit::AuditEntry;
+use crate::domain::audit::Audit;
+use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use rocket_db_pools::sqlx::Postgres;
+use rocket_db_pools::sqlx::Transaction;
+
+#[derive(Debug, sqlx::FromRow)]
+struct AttachmentRow {
+    id: String,
+    supply_id: String,
+    file_name: String,
+    content_type: String,
+    size: i64,
+    checksum: String,
+    storage_key: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<AttachmentRow> for Attachment {
+    fn from(row: AttachmentRow) -> Self {
+        Attachment {
+            id: row.id,
+            supply_id: row.supply_id,
+            file_name: row.file_name,
+            content_type: row.content_type,
+            size: row.size,
+            checksum: row.checksum,
+            storage_key: row.storage_key,
+            created_at: row.created_at,
+        }
+    }
+}
+
+const COLUMNS: &str =
+    "id, supply_id, file_name, content_type, size, checksum, storage_key, created_at";
+
+/// Attachment metadata of a single tenant.
+pub struct AttachmentPostgresGateway {
+    pool: PgPool,
+    tenant_id: TenantId,
+}
+
+impl AttachmentPostgresGateway {
+    pub fn new(pool: PgPool, tenant_id: TenantId) -> Self {
+        Self { pool, tenant_id }
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, Postgres>, CustomError> {
+        let mut tx = self.pool.begin().await.map_err(repository_error)?;
+        scope_to_tenant(&mut tx, &self.tenant_id)
+            .await
+            .map_err(repository_error)?;
+        Ok(tx)
+    }
+}
+
+#[async_trait]
+impl AttachmentGateway for AttachmentPostgresGateway {
+    async fn create(
+        &self,
+        attachment: &Attachment,
+        audit: Audit<'_, Attachment>,
+    ) -> Result<Attachment, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, AttachmentRow>(&format!(
+            r#"
+            INSERT INTO supply_attachments
+                (id, tenant_id, supply_id, file_name, content_type, size, checksum, storage_key, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(&attachment.id)
+        .bind(self.tenant_id.get_value())
+        .bind(&attachment.supply_id)
+        .bind(&attachment.file_name)
+        .bind(&attachment.content_type)
+        .bind(attachment.size)
+        .bind(&attachment.checksum)
+        .bind(&attachment.storage_key)
+        .bind(attachment.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        let created = Attachment::from(row);
+        audit_gateway::insert_all(&mut tx, &self.tenant_id, &[audit(&created)]).await?;
+        tx.commit().await.map_err(repository_error)?;
+        Ok(created)
+    }
+
+    async fn find(&self, supply_id: &str, id: &str) -> Result<Option<Attachment>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, AttachmentRow>(&format!(
+            r#"
+            SELECT {}
+            FROM supply_attachments
+            WHERE tenant_id = $1 AND supply_id = $2 AND id = $3
+            "#,
+            COLUMNS
+        ))
+        .bind(self.tenant_id.get_value())
+        .bind(supply_id)
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(row.map(Attachment::from))
+    }
+
+    async fn find_by_supply(&self, supply_id: &str) -> Result<Vec<Attachment>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let rows = sqlx::query_as::<_, AttachmentRow>(&format!(
+            r#"
+            SELECT {}
+            FROM supply_attachments
+            WHERE tenant_id = $1 AND supply_id = $2
+            ORDER BY created_at, id
+            "#,
+            COLUMNS
+        ))
+        .bind(self.tenant_id.get_value())
+        .bind(supply_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(rows.into_iter().map(Attachment::from).collect())
+    }
+
+    async fn delete(&self, id: &str, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut tx = self.begin().await?;
+
+        let deleted =
+            sqlx::query("DELETE FROM supply_attachments WHERE tenant_id = $1 AND id = $2")
+                .bind(self.tenant_id.get_value())
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(repository_error)?;
+
+        if deleted.rows_affected() > 0 {
+            audit_gateway::insert_all(&mut tx, &self.tenant_id, std::slice::from_ref(audit))
+                .await?;
+        }
+        tx.commit().await.map_err(repository_error)
+    }
+}
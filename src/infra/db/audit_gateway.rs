@@ -0,0 +1,178 @@
+use super::scope_to_tenant;
+use super::supply_gateway::repository_error;
+use crate::application::audit;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditEntry;
+use crate::application::audit::AuditFilter;
+use crate::application::audit::AuditGateway;
+use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+#[derive(Debug, sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    actor: String,
+    action: String,
+    aggregate_type: String,
+    aggregate_id: String,
+    before: Option<Json<Value>>,
+    after: Option<Json<Value>>,
+    request_id: Option<String>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<AuditRow> for AuditEntry {
+    type Error = CustomError;
+
+    fn try_from(row: AuditRow) -> Result<Self, Self::Error> {
+        let action = AuditAction::parse(&row.action).ok_or_else(|| {
+            CustomError::RepositoryError(format!("unknown audit action '{}'", row.action))
+        })?;
+        Ok(AuditEntry {
+            id: Some(row.id),
+            actor: row.actor,
+            action,
+            aggregate_type: row.aggregate_type,
+            aggregate_id: row.aggregate_id,
+            before: row.before.map(|v| v.0),
+            after: row.after.map(|v| v.0),
+            request_id: row.request_id,
+            occurred_at: row.occurred_at,
+        })
+    }
+}
+
+/// Audit log of a single tenant.
+pub struct AuditPostgresGateway {
+    pool: PgPool,
+    tenant_id: TenantId,
+}
+
+impl AuditPostgresGateway {
+    pub fn new(pool: PgPool, tenant_id: TenantId) -> Self {
+        Self { pool, tenant_id }
+    }
+}
+
+/// Writes `entries` to the audit log of `tenant_id` on `conn`, so they can
+/// share the transaction of the change they describe.
+pub async fn insert_all(
+    conn: &mut PgConnection,
+    tenant_id: &TenantId,
+    entries: &[AuditEntry],
+) -> Result<(), CustomError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO audit_log
+            (tenant_id, actor, action, aggregate_type, aggregate_id, before, after, request_id, occurred_at)
+        SELECT $1::VARCHAR, * FROM UNNEST(
+            $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::VARCHAR[],
+            $6::JSONB[], $7::JSONB[], $8::VARCHAR[], $9::TIMESTAMPTZ[]
+        )
+        "#,
+    )
+    .bind(tenant_id.get_value())
+    .bind(entries.iter().map(|e| e.actor.to_owned()).collect::<Vec<_>>())
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.action.as_str().to_string())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.aggregate_type.to_owned())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.aggregate_id.to_owned())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.before.to_owned().map(Json))
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.after.to_owned().map(Json))
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        entries
+            .iter()
+            .map(|e| e.request_id.to_owned())
+            .collect::<Vec<_>>(),
+    )
+    .bind(entries.iter().map(|e| e.occurred_at).collect::<Vec<_>>())
+    .execute(conn)
+    .await
+    .map(|_| ())
+    .map_err(repository_error);
+
+    audit::count(entries, result.is_ok());
+    result
+}
+
+#[async_trait]
+impl AuditGateway for AuditPostgresGateway {
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, CustomError> {
+        let mut tx = self.pool.begin().await.map_err(repository_error)?;
+        scope_to_tenant(&mut tx, &self.tenant_id)
+            .await
+            .map_err(repository_error)?;
+
+        let rows = sqlx::query_as::<_, AuditRow>(
+            r#"
+            SELECT id, actor, action, aggregate_type, aggregate_id, before, after, request_id, occurred_at
+            FROM audit_log
+            WHERE tenant_id = $10
+              AND ($1::VARCHAR IS NULL OR actor = $1)
+              AND ($2::VARCHAR IS NULL OR action = $2)
+              AND ($3::VARCHAR IS NULL OR aggregate_type = $3)
+              AND ($4::VARCHAR IS NULL OR aggregate_id = $4)
+              AND (cardinality($11::VARCHAR[]) = 0 OR aggregate_id = ANY($11))
+              AND ($5::VARCHAR IS NULL OR request_id = $5)
+              AND ($6::TIMESTAMPTZ IS NULL OR occurred_at >= $6)
+              AND ($7::TIMESTAMPTZ IS NULL OR occurred_at <= $7)
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT $8 OFFSET $9
+            "#,
+        )
+        .bind(&filter.actor)
+        .bind(filter.action.map(|action| action.as_str()))
+        .bind(&filter.aggregate_type)
+        .bind(&filter.aggregate_id)
+        .bind(&filter.request_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .bind(self.tenant_id.get_value())
+        .bind(&filter.aggregate_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+
+        rows.into_iter().map(AuditEntry::try_from).collect()
+    }
+}
@@ -0,0 +1,111 @@
+use super::Db;
+use super::DbSqlx;
+use crate::application::health::HealthCheck;
+use async_trait::async_trait;
+use diesel::migration::MigrationSource;
+use diesel::pg::Pg;
+use diesel_migrations::embed_migrations;
+use diesel_migrations::EmbeddedMigrations;
+use rocket_db_pools::diesel::prelude::RunQueryDsl;
+use std::collections::HashSet;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Acquires a connection from the sqlx pool and runs `SELECT 1`.
+pub struct SqlxHealthCheck(pub sqlx::PgPool);
+
+#[async_trait]
+impl HealthCheck for SqlxHealthCheck {
+    fn name(&self) -> &str {
+        "sqlx"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        sqlx::query("SELECT 1")
+            .execute(&self.0)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Acquires a connection from the diesel pool and runs `SELECT 1`.
+pub struct DieselHealthCheck(pub rocket_db_pools::diesel::PgPool);
+
+impl DieselHealthCheck {
+    pub fn new(db: &Db) -> Self {
+        Self((**db).clone())
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DieselHealthCheck {
+    fn name(&self) -> &str {
+        "diesel"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let mut conn = self.0.get().await.map_err(|e| e.to_string())?;
+        diesel::sql_query("SELECT 1")
+            .execute(&mut conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Fails while migrations embedded in the binary have not been applied to
+/// the database.
+pub struct MigrationsHealthCheck(pub sqlx::PgPool);
+
+impl MigrationsHealthCheck {
+    pub fn new(db: &DbSqlx) -> Self {
+        Self(db.pool())
+    }
+}
+
+/// Versions of the embedded migrations missing from `applied`.
+pub fn pending_migrations(applied: &HashSet<String>) -> Result<Vec<String>, String> {
+    let migrations = MigrationSource::<Pg>::migrations(&MIGRATIONS).map_err(|e| e.to_string())?;
+    Ok(migrations
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
+#[async_trait]
+impl HealthCheck for MigrationsHealthCheck {
+    fn name(&self) -> &str {
+        "migrations"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let applied: Vec<String> =
+            sqlx::query_scalar("SELECT version FROM __diesel_schema_migrations")
+                .fetch_all(&self.0)
+                .await
+                .map_err(|e| e.to_string())?;
+
+        let pending = pending_migrations(&applied.into_iter().collect())?;
+        if pending.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("pending migrations: {}", pending.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod db_health_tests {
+    use super::*;
+
+    #[test]
+    fn lists_embedded_migrations_not_yet_applied() {
+        let all = pending_migrations(&HashSet::new()).unwrap();
+        assert!(all.contains(&"20231113022508".to_string()));
+
+        let applied = all.iter().skip(1).cloned().collect::<HashSet<_>>();
+        assert_eq!(pending_migrations(&applied).unwrap(), vec![all[0].clone()]);
+    }
+}
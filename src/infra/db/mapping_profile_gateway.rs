@@ -0,0 +1,146 @@
+use super::audit_gateway;
+use super::scope_to_tenant;
+use super::supply_gateway::repository_error;
+use crate::application::audit::AuditEntry;
+use crate::application::mapping_profile::MappingProfile;
+use crate::application::mapping_profile::MappingProfileGateway;
+use crate::application::mapping_profile::SupplierProfile;
+use crate::domain::audit::Audit;
+use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use rocket_db_pools::sqlx::Postgres;
+use rocket_db_pools::sqlx::Transaction;
+use sqlx::types::Json;
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProfileRow {
+    supplier: String,
+    profile: Json<MappingProfile>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<ProfileRow> for SupplierProfile {
+    fn from(row: ProfileRow) -> Self {
+        SupplierProfile {
+            supplier: row.supplier,
+            profile: row.profile.0,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Mapping profiles of a single tenant.
+pub struct MappingProfilePostgresGateway {
+    pool: PgPool,
+    tenant_id: TenantId,
+}
+
+impl MappingProfilePostgresGateway {
+    pub fn new(pool: PgPool, tenant_id: TenantId) -> Self {
+        Self { pool, tenant_id }
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, Postgres>, CustomError> {
+        let mut tx = self.pool.begin().await.map_err(repository_error)?;
+        scope_to_tenant(&mut tx, &self.tenant_id)
+            .await
+            .map_err(repository_error)?;
+        Ok(tx)
+    }
+}
+
+#[async_trait]
+impl MappingProfileGateway for MappingProfilePostgresGateway {
+    async fn save(
+        &self,
+        profile: &SupplierProfile,
+        audit: Audit<'_, SupplierProfile>,
+    ) -> Result<SupplierProfile, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, ProfileRow>(
+            r#"
+            INSERT INTO mapping_profiles (tenant_id, supplier, profile, updated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (tenant_id, supplier) DO UPDATE
+            SET profile = EXCLUDED.profile, updated_at = EXCLUDED.updated_at
+            RETURNING supplier, profile, updated_at
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(&profile.supplier)
+        .bind(Json(&profile.profile))
+        .bind(profile.updated_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        let saved = SupplierProfile::from(row);
+        audit_gateway::insert_all(&mut tx, &self.tenant_id, &[audit(&saved)]).await?;
+        tx.commit().await.map_err(repository_error)?;
+        Ok(saved)
+    }
+
+    async fn find(&self, supplier: &str) -> Result<Option<SupplierProfile>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, ProfileRow>(
+            r#"
+            SELECT supplier, profile, updated_at
+            FROM mapping_profiles
+            WHERE tenant_id = $1 AND supplier = $2
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(supplier)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(row.map(SupplierProfile::from))
+    }
+
+    async fn find_all(&self) -> Result<Vec<SupplierProfile>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let rows = sqlx::query_as::<_, ProfileRow>(
+            r#"
+            SELECT supplier, profile, updated_at
+            FROM mapping_profiles
+            WHERE tenant_id = $1
+            ORDER BY supplier
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(rows.into_iter().map(SupplierProfile::from).collect())
+    }
+
+    async fn delete(&self, supplier: &str, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut tx = self.begin().await?;
+
+        let deleted =
+            sqlx::query("DELETE FROM mapping_profiles WHERE tenant_id = $1 AND supplier = $2")
+                .bind(self.tenant_id.get_value())
+                .bind(supplier)
+                .execute(&mut *tx)
+                .await
+                .map_err(repository_error)?;
+
+        if deleted.rows_affected() > 0 {
+            audit_gateway::insert_all(&mut tx, &self.tenant_id, std::slice::from_ref(audit))
+                .await?;
+        }
+        tx.commit().await.map_err(repository_error)
+    }
+}
@@ -1,8 +1,15 @@
+use crate::domain::identifier::Identifier;
+use crate::domain::tenant_id::TenantId;
 use rocket_db_pools::diesel::PgPool;
 use rocket_db_pools::sqlx;
 use rocket_db_pools::Database;
 
+pub mod attachment_gateway;
+pub mod audit_gateway;
+pub mod health;
+pub mod mapping_profile_gateway;
 pub mod schema;
+pub mod supply_gateway;
 
 #[derive(Database)]
 #[database("diesel_postgres")]
@@ -11,3 +18,23 @@ pub struct Db(PgPool);
 #[derive(Database)]
 #[database("sqlx")]
 pub struct DbSqlx(sqlx::PgPool);
+
+impl DbSqlx {
+    pub fn pool(&self) -> sqlx::PgPool {
+        self.0.clone()
+    }
+}
+
+/// Sets `app.tenant_id` for the current transaction, which the row-level
+/// security policies compare against every row. Must run inside a
+/// transaction, the setting is reset when it ends.
+pub async fn scope_to_tenant(
+    conn: &mut sqlx::PgConnection,
+    tenant_id: &TenantId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT set_config('app.tenant_id', $1, true)")
+        .bind(tenant_id.get_value())
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
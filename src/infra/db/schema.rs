@@ -4,5 +4,6 @@ diesel::table! {
       title -> Text,
       text -> Text,
       published -> Bool,
+      tenant_id -> Text,
   }
 }
@@ -0,0 +1,458 @@
+use super::audit_gateway;
+use super::scope_to_tenant;
+use crate::domain::audit::Audit;
+use crate::domain::audit::AuditEntry;
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::price::Price;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_gateway::SupplyPage;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::supply::Supply;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use crate::i18n::Message;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use rocket_db_pools::sqlx::Postgres;
+use rocket_db_pools::sqlx::Transaction;
+use sqlx::types::Json;
+
+#[derive(Debug, sqlx::FromRow)]
+struct SupplyRow {
+    id: String,
+    tenant_id: String,
+    name: String,
+    prices: Json<Vec<Price>>,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    version: i64,
+}
+
+impl From<SupplyRow> for Supply {
+    fn from(row: SupplyRow) -> Self {
+        Supply::restore(
+            SupplyId::from_str(&row.id),
+            TenantId::from_str(&row.tenant_id),
+            row.name,
+            row.prices.0,
+            row.created_at,
+            row.updated_at,
+            row.version,
+        )
+    }
+}
+
+/// Rows sent to Postgres per statement by `upsert_all`.
+const UPSERT_BATCH_SIZE: usize = 500;
+
+/// `text` escaped to match literally in a `LIKE` pattern.
+pub fn like_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+pub fn repository_error(e: sqlx::Error) -> CustomError {
+    CustomError::RepositoryError(e.to_string())
+}
+
+/// Supplies of a single tenant. Every query filters by `tenant_id` and runs in
+/// a transaction scoped for the row-level security policies.
+pub struct SupplyPostgresGateway {
+    pool: PgPool,
+    tenant_id: TenantId,
+}
+
+impl SupplyPostgresGateway {
+    pub fn new(pool: PgPool, tenant_id: TenantId) -> Self {
+        Self { pool, tenant_id }
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, Postgres>, CustomError> {
+        let mut tx = self.pool.begin().await.map_err(repository_error)?;
+        scope_to_tenant(&mut tx, &self.tenant_id)
+            .await
+            .map_err(repository_error)?;
+        Ok(tx)
+    }
+
+    fn check_tenant(&self, supply: &Supply) -> Result<(), CustomError> {
+        if supply.get_tenant_id() != &self.tenant_id {
+            return Err(CustomError::RepositoryError(format!(
+                "supply '{}' does not belong to tenant '{}'",
+                supply.get_id().get_value(),
+                self.tenant_id.get_value()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SupplyGateway for SupplyPostgresGateway {
+    async fn create(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError> {
+        self.check_tenant(supply)?;
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            INSERT INTO supplies (id, tenant_id, name, prices, created_at, updated_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, tenant_id, name, prices, created_at, updated_at, version
+            "#,
+        )
+        .bind(supply.get_id().get_value())
+        .bind(self.tenant_id.get_value())
+        .bind(supply.get_name())
+        .bind(Json(supply.get_prices()))
+        .bind(supply.get_created_at())
+        .bind(supply.get_updated_at())
+        .bind(supply.get_version())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        let created = Supply::from(row);
+        audit_gateway::insert_all(&mut tx, &self.tenant_id, &[audit(&created)]).await?;
+        tx.commit().await.map_err(repository_error)?;
+        Ok(created)
+    }
+
+    async fn find_by_id(&self, id: &SupplyId) -> Result<Option<Supply>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            SELECT id, tenant_id, name, prices, created_at, updated_at, version
+            FROM supplies
+            WHERE id = $1 AND tenant_id = $2
+            "#,
+        )
+        .bind(id.get_value())
+        .bind(self.tenant_id.get_value())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(row.map(Supply::from))
+    }
+
+    async fn find_all(&self) -> Result<Vec<Supply>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let rows = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            SELECT id, tenant_id, name, prices, created_at, updated_at, version
+            FROM supplies
+            WHERE tenant_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(rows.into_iter().map(Supply::from).collect())
+    }
+
+    async fn find_page(&self, page: &SupplyPage) -> Result<Vec<Supply>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        // From the end, the page is read backwards and turned around
+        let query = if page.from_end {
+            r#"
+            SELECT id, tenant_id, name, prices, created_at, updated_at, version
+            FROM supplies
+            WHERE tenant_id = $1
+                AND ($2::VARCHAR IS NULL OR (name, id) > ($2, $3))
+                AND ($4::VARCHAR IS NULL OR (name, id) < ($4, $5))
+                AND ($7::VARCHAR IS NULL OR name ILIKE '%' || $7 || '%')
+                AND ($8::VARCHAR IS NULL OR prices @> jsonb_build_array(jsonb_build_object('unit', $8::VARCHAR)))
+            ORDER BY name DESC, id DESC
+            LIMIT $6 OFFSET $9
+            "#
+        } else {
+            r#"
+            SELECT id, tenant_id, name, prices, created_at, updated_at, version
+            FROM supplies
+            WHERE tenant_id = $1
+                AND ($2::VARCHAR IS NULL OR (name, id) > ($2, $3))
+                AND ($4::VARCHAR IS NULL OR (name, id) < ($4, $5))
+                AND ($7::VARCHAR IS NULL OR name ILIKE '%' || $7 || '%')
+                AND ($8::VARCHAR IS NULL OR prices @> jsonb_build_array(jsonb_build_object('unit', $8::VARCHAR)))
+            ORDER BY name, id
+            LIMIT $6 OFFSET $9
+            "#
+        };
+        let mut rows = sqlx::query_as::<_, SupplyRow>(query)
+            .bind(self.tenant_id.get_value())
+            .bind(page.after.as_ref().map(|key| &key.name))
+            .bind(page.after.as_ref().map(|key| &key.id))
+            .bind(page.before.as_ref().map(|key| &key.name))
+            .bind(page.before.as_ref().map(|key| &key.id))
+            .bind(page.limit)
+            .bind(page.name.as_deref().map(like_escape))
+            .bind(page.unit.as_deref())
+            .bind(page.offset)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        if page.from_end {
+            rows.reverse();
+        }
+        Ok(rows.into_iter().map(Supply::from).collect())
+    }
+
+    async fn count(&self, page: &SupplyPage) -> Result<i64, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM supplies
+            WHERE tenant_id = $1
+                AND ($2::VARCHAR IS NULL OR name ILIKE '%' || $2 || '%')
+                AND ($3::VARCHAR IS NULL OR prices @> jsonb_build_array(jsonb_build_object('unit', $3::VARCHAR)))
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(page.name.as_deref().map(like_escape))
+        .bind(page.unit.as_deref())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(count)
+    }
+
+    async fn update(
+        &self,
+        supply: &Supply,
+        audit: Audit<'_, Supply>,
+    ) -> Result<Supply, CustomError> {
+        self.check_tenant(supply)?;
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            UPDATE supplies
+            SET name = $3, prices = $4, updated_at = $5, version = version + 1
+            WHERE id = $1 AND tenant_id = $2 AND version = $6
+            RETURNING id, tenant_id, name, prices, created_at, updated_at, version
+            "#,
+        )
+        .bind(supply.get_id().get_value())
+        .bind(self.tenant_id.get_value())
+        .bind(supply.get_name())
+        .bind(Json(supply.get_prices()))
+        .bind(supply.get_updated_at())
+        .bind(supply.get_version())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        if let Some(row) = row {
+            let updated = Supply::from(row);
+            audit_gateway::insert_all(&mut tx, &self.tenant_id, &[audit(&updated)]).await?;
+            tx.commit().await.map_err(repository_error)?;
+            return Ok(updated);
+        }
+
+        // Nothing matched: either the supply is gone or its version moved on
+        let current = sqlx::query_scalar::<_, i64>(
+            "SELECT version FROM supplies WHERE id = $1 AND tenant_id = $2",
+        )
+        .bind(supply.get_id().get_value())
+        .bind(self.tenant_id.get_value())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Err(match current {
+            Some(version) => CustomError::VersionConflict(
+                Message::new("supply-version-conflict")
+                    .arg("id", supply.get_id().get_value())
+                    .arg("current", version)
+                    .arg("expected", supply.get_version()),
+            ),
+            None => CustomError::NotFound(
+                Message::new("supply-not-found").arg("id", supply.get_id().get_value()),
+            ),
+        })
+    }
+
+    async fn delete_by_id(&self, id: &SupplyId, audit: &AuditEntry) -> Result<(), CustomError> {
+        let mut tx = self.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM supplies WHERE id = $1 AND tenant_id = $2")
+            .bind(id.get_value())
+            .bind(self.tenant_id.get_value())
+            .execute(&mut *tx)
+            .await
+            .map_err(repository_error)?;
+
+        if deleted.rows_affected() > 0 {
+            audit_gateway::insert_all(&mut tx, &self.tenant_id, std::slice::from_ref(audit))
+                .await?;
+        }
+        tx.commit().await.map_err(repository_error)
+    }
+
+    async fn find_in_list(&self, list: &str) -> Result<Vec<Supply>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let rows = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            SELECT s.id, s.tenant_id, s.name, s.prices, s.created_at, s.updated_at, s.version
+            FROM supplies s
+            JOIN supply_list_items l ON l.tenant_id = s.tenant_id AND l.supply_id = s.id
+            WHERE l.tenant_id = $1 AND l.list = $2
+            ORDER BY s.name
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(list)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(rows.into_iter().map(Supply::from).collect())
+    }
+
+    async fn add_to_list(&self, list: &str, id: &SupplyId) -> Result<Option<Supply>, CustomError> {
+        let mut tx = self.begin().await?;
+
+        let row = sqlx::query_as::<_, SupplyRow>(
+            r#"
+            WITH listed AS (
+                INSERT INTO supply_list_items (tenant_id, list, supply_id)
+                SELECT tenant_id, $2, id FROM supplies WHERE id = $3 AND tenant_id = $1
+                ON CONFLICT DO NOTHING
+            )
+            SELECT id, tenant_id, name, prices, created_at, updated_at, version
+            FROM supplies
+            WHERE id = $3 AND tenant_id = $1
+            "#,
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(list)
+        .bind(id.get_value())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(row.map(Supply::from))
+    }
+
+    async fn remove_from_list(&self, list: &str, id: &SupplyId) -> Result<(), CustomError> {
+        let mut tx = self.begin().await?;
+
+        sqlx::query(
+            "DELETE FROM supply_list_items WHERE tenant_id = $1 AND list = $2 AND supply_id = $3",
+        )
+        .bind(self.tenant_id.get_value())
+        .bind(list)
+        .bind(id.get_value())
+        .execute(&mut *tx)
+        .await
+        .map_err(repository_error)?;
+
+        tx.commit().await.map_err(repository_error)
+    }
+
+    async fn upsert_all(
+        &self,
+        supplies: &[Supply],
+        audit: Audit<'_, Supply>,
+    ) -> Result<Vec<Supply>, CustomError> {
+        for supply in supplies {
+            self.check_tenant(supply)?;
+        }
+        let mut tx = self.begin().await?;
+        let mut saved = Vec::with_capacity(supplies.len());
+
+        for batch in supplies.chunks(UPSERT_BATCH_SIZE) {
+            let rows = sqlx::query_as::<_, SupplyRow>(
+                r#"
+                INSERT INTO supplies (id, tenant_id, name, prices, created_at, updated_at, version)
+                SELECT * FROM UNNEST(
+                    $1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::JSONB[],
+                    $5::TIMESTAMPTZ[], $6::TIMESTAMPTZ[], $7::BIGINT[]
+                )
+                ON CONFLICT (id) DO UPDATE
+                SET name = EXCLUDED.name, prices = EXCLUDED.prices,
+                    updated_at = EXCLUDED.updated_at, version = supplies.version + 1
+                WHERE supplies.tenant_id = EXCLUDED.tenant_id
+                    AND supplies.version = EXCLUDED.version
+                RETURNING id, tenant_id, name, prices, created_at, updated_at, version
+                "#,
+            )
+            .bind(
+                batch
+                    .iter()
+                    .map(|s| s.get_id().get_value().to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(vec![self.tenant_id.get_value().to_string(); batch.len()])
+            .bind(
+                batch
+                    .iter()
+                    .map(|s| s.get_name().to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(
+                batch
+                    .iter()
+                    .map(|s| Json(s.get_prices().to_owned()))
+                    .collect::<Vec<_>>(),
+            )
+            .bind(
+                batch
+                    .iter()
+                    .map(|s| s.get_created_at().to_owned())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(
+                batch
+                    .iter()
+                    .map(|s| s.get_updated_at().cloned())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(batch.iter().map(|s| s.get_version()).collect::<Vec<_>>())
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(repository_error)?;
+
+            // A skipped row is a supply whose version moved on; dropping the
+            // transaction rolls back the batches already written
+            if rows.len() != batch.len() {
+                return Err(CustomError::VersionConflict(
+                    Message::new("supplies-changed").arg("count", batch.len() - rows.len()),
+                ));
+            }
+            let rows = rows.into_iter().map(Supply::from).collect::<Vec<_>>();
+            let entries = rows.iter().map(audit).collect::<Vec<_>>();
+            audit_gateway::insert_all(&mut tx, &self.tenant_id, &entries).await?;
+            saved.extend(rows);
+        }
+
+        tx.commit().await.map_err(repository_error)?;
+        Ok(saved)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::application::attachment::ObjectStorage;
+use crate::domain::validation::error::CustomError;
+use async_trait::async_trait;
+use rocket::tokio::fs;
+use std::io;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn storage_error(e: io::Error) -> CustomError {
+    CustomError::RepositoryError(format!("attachment storage: {}", e))
+}
+
+/// Keeps each object in a file under `root`, at the path of its key.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Keys are relative paths of plain segments, so they can't leave `root`.
+    fn path(&self, key: &str) -> Result<PathBuf, CustomError> {
+        let valid = key.split('/').all(|segment| {
+            !segment.is_empty() && segment != "." && segment != ".." && !segment.contains('\\')
+        });
+        if !valid {
+            return Err(CustomError::RepositoryError(format!(
+                "'{}' is not a valid storage key",
+                key
+            )));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for LocalStorage {
+    async fn put(&self, key: &str, content: &[u8], _: &str) -> Result<(), CustomError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(storage_error)?;
+        }
+        // Written aside and renamed, so readers never see a partial file
+        let partial = path.with_file_name(format!(".{}.partial", Uuid::new_v4()));
+        fs::write(&partial, content).await.map_err(storage_error)?;
+        if let Err(e) = fs::rename(&partial, &path).await {
+            let _ = fs::remove_file(&partial).await;
+            return Err(storage_error(e));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CustomError> {
+        match fs::read(self.path(key)?).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CustomError> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(storage_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_storage_tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn stores_objects_as_files_under_the_root() {
+        let root = std::env::temp_dir().join(format!("attachments-{}", Uuid::new_v4()));
+        let storage = LocalStorage::new(root.clone());
+
+        storage
+            .put("acme/attachments/a1", b"%PDF-1.7", "application/pdf")
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(root.join("acme/attachments/a1")).unwrap(),
+            b"%PDF-1.7"
+        );
+        assert_eq!(
+            storage.get("acme/attachments/a1").await.unwrap(),
+            Some(b"%PDF-1.7".to_vec())
+        );
+
+        storage.delete("acme/attachments/a1").await.unwrap();
+        storage.delete("acme/attachments/a1").await.unwrap();
+        assert_eq!(storage.get("acme/attachments/a1").await.unwrap(), None);
+
+        assert!(storage.get("acme/../../etc/passwd").await.is_err());
+        assert!(storage.put("/etc/passwd", b"", "text/plain").await.is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+pub mod local;
+pub mod s3;
+
+use self::local::LocalStorage;
+use self::s3::S3Config;
+use self::s3::S3Storage;
+use crate::application::attachment::ObjectStorage;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/*
+[default.attachments]
+storage = "s3"
+root = "tmp/attachments"
+
+[default.attachments.s3]
+endpoint = "http://localhost:9000"
+bucket = "attachments"
+region = "us-east-1"
+access_key = "minioadmin"
+secret_key = "minioadmin"
+path_style = true
+*/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Local,
+    S3,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub storage: Backend,
+    /// Directory of the local storage.
+    #[serde(default = "default_root")]
+    pub root: PathBuf,
+    pub s3: Option<S3Config>,
+}
+
+fn default_root() -> PathBuf {
+    PathBuf::from("tmp/attachments")
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            storage: Backend::default(),
+            root: default_root(),
+            s3: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    pub fn build(&self) -> Result<Arc<dyn ObjectStorage>, String> {
+        match (self.storage, &self.s3) {
+            (Backend::Local, _) => Ok(Arc::new(LocalStorage::new(self.root.to_owned()))),
+            (Backend::S3, Some(config)) => Ok(Arc::new(S3Storage::new(config.to_owned())?)),
+            (Backend::S3, None) => {
+                Err("the s3 storage needs an [attachments.s3] table".to_string())
+            }
+        }
+    }
+}
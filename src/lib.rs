@@ -1,5 +1,28 @@
+#[macro_use]
+extern crate rocket;
+
+mod admin;
 mod application;
+mod attachments;
+mod audit;
+pub mod cli;
 pub mod create_app;
+// Parts of the baseline domain are only used by its own tests.
+#[allow(dead_code)]
 mod domain;
+mod graphql;
+mod grpc;
+mod health;
+mod i18n;
 mod infra;
+mod logging;
+mod main_example;
+mod mapping_profiles;
+mod metrics;
 mod middler;
+mod openapi;
+mod posts;
+mod problem;
+mod supplies;
+mod supply_events;
+mod supply_rooms;
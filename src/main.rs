@@ -1,9 +1,18 @@
+use clap::Parser;
+use solution::cli;
+use solution::cli::Cli;
+use solution::cli::Command;
 use solution::create_app::start_app;
+use std::error::Error;
 
-#[macro_use]
-extern crate rocket;
-
-#[launch]
-async fn rocket() -> _ {
-    start_app()
+#[rocket::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Some(Command::Export(args)) => cli::export(args).await,
+        Some(Command::HashPassword) => cli::hash_password(),
+        Some(Command::Serve) | None => {
+            start_app().launch().await?;
+            Ok(())
+        }
+    }
 }
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::middler::csrf::CsrfToken;
 use crate::middler::ApiKey;
 use crate::middler::ApiKeyError;
 use crate::middler::MyResult;
@@ -25,6 +26,7 @@ use crate::posts::Post;
 use rocket::response::{status::Created, Debug};
 use rocket_db_pools::diesel::prelude::*;
 use rocket_db_pools::Connection;
+use tracing::info;
 
 #[get("/")]
 fn index(cookies: &CookieJar<'_>) -> &'static str {
@@ -61,8 +63,8 @@ struct Task<'r> {
 }
 
 #[post("/todo", data = "<task>")]
-fn new(task: Json<Task<'_>>) -> Json<Task> {
-    println!("Task: {} - {}", task.description, task.complete);
+fn new(task: Json<Task<'_>>) -> Json<Task<'_>> {
+    info!(description = %task.description, complete = task.complete, "task received");
     task
 }
 
@@ -85,14 +87,17 @@ async fn debug(data: Data<'_>) -> std::io::Result<()> {
 struct Todo<'r> {
     complete: bool,
     r#type: &'r str,
+    #[serde(skip)]
+    csrf_token: Option<&'r str>,
 }
 
 #[post("/new_todo", data = "<task>")]
-fn new_todo(task: Form<Strict<Todo<'_>>>) -> Json<Todo> {
-    println!("Task: {:?} ", task);
+fn new_todo(task: Form<Strict<Todo<'_>>>) -> Json<Todo<'_>> {
+    info!(task = ?task, "task received");
     let todo = Todo {
         complete: task.complete,
         r#type: task.r#type,
+        csrf_token: None,
     };
     Json(todo)
 }
@@ -106,7 +111,7 @@ struct UploadM<'r> {
 #[post("/upload_form", data = "<upload>")]
 async fn upload_form(mut upload: Form<UploadM<'_>>) {
     let _r = upload.file.persist_to("tmp/texto2.txt").await;
-    println!("Upload: {:?}", upload.save);
+    info!(save = ?upload.save, "upload received");
 }
 
 #[derive(Responder)]
@@ -119,9 +124,10 @@ fn json() -> RawTeapotJson {
 }
 
 #[get("/template?<name>", data = "<post>")]
-fn template(name: Option<&str>, post: Json<Post>) -> Template {
+fn template(name: Option<&str>, post: Json<Post>, csrf: CsrfToken) -> Template {
     let context = context! {
     name:name.or(Some("Guest")),
+    csrf_token: csrf.value(),
     title:post.title.to_owned(),
     items: vec![post.title.to_owned(), post.text.to_owned()]
     };
@@ -131,7 +137,7 @@ fn template(name: Option<&str>, post: Json<Post>) -> Template {
 #[get("/<__id>/<name>?<__age>")]
 fn person(__id: Option<usize>, name: Option<&str>, __age: Option<u8>) -> Redirect {
     let url = uri!("/", template(name));
-    println!("{}", url);
+    info!(url = %url, "redirecting");
     Redirect::to(url)
 }
 
@@ -0,0 +1,119 @@
+use crate::application::audit::AuditContext;
+use crate::application::mapping_profile::delete_mapping_profile::DeleteMappingProfileUseCase;
+use crate::application::mapping_profile::get_mapping_profile::GetMappingProfileUseCase;
+use crate::application::mapping_profile::list_mapping_profiles::ListMappingProfilesUseCase;
+use crate::application::mapping_profile::save_mapping_profile::SaveMappingProfileUseCase;
+use crate::application::mapping_profile::MappingProfile;
+use crate::application::mapping_profile::MappingProfileGateway;
+use crate::application::mapping_profile::SupplierProfile;
+use crate::domain::tenant_id::TenantId;
+use crate::infra::db::mapping_profile_gateway::MappingProfilePostgresGateway;
+use crate::infra::db::DbSqlx;
+use crate::middler::payload::Payload;
+use crate::middler::tenant::Tenant;
+use crate::problem::Problem;
+use rocket::delete;
+use rocket::get;
+use rocket::put;
+use rocket::response::status::NoContent;
+use rocket::State;
+use std::sync::Arc;
+
+type Result<T, E = Problem> = std::result::Result<T, E>;
+
+pub(crate) fn mapping_profile_gateway(
+    db: &DbSqlx,
+    tenant_id: &TenantId,
+) -> Arc<dyn MappingProfileGateway> {
+    Arc::new(MappingProfilePostgresGateway::new(
+        db.pool(),
+        tenant_id.to_owned(),
+    ))
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies/import/profiles",
+    responses(
+        (status = 200, description = "Mapping profiles of the tenant, by supplier", body = Vec<SupplierProfile>),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/")]
+pub async fn list(db: &State<DbSqlx>, tenant: Tenant) -> Result<Payload<Vec<SupplierProfile>>> {
+    ListMappingProfilesUseCase::new(mapping_profile_gateway(db, &tenant.0))
+        .execute()
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies/import/profiles",
+    params(("supplier" = String, Path, description = "Supplier the profile belongs to")),
+    responses(
+        (status = 200, description = "The mapping profile", body = SupplierProfile),
+        (status = 404, description = "No profile for the supplier", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/<supplier>")]
+pub async fn find(
+    db: &State<DbSqlx>,
+    tenant: Tenant,
+    supplier: &str,
+) -> Result<Payload<SupplierProfile>> {
+    GetMappingProfileUseCase::new(mapping_profile_gateway(db, &tenant.0))
+        .execute(supplier)
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies/import/profiles",
+    params(("supplier" = String, Path, description = "Supplier the profile belongs to")),
+    request_body = MappingProfile,
+    responses(
+        (status = 200, description = "Profile created or replaced", body = SupplierProfile),
+        (status = 422, description = "Validation failed", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[put("/<supplier>", data = "<profile>")]
+pub async fn save(
+    db: &State<DbSqlx>,
+    context: AuditContext,
+    supplier: &str,
+    profile: Payload<MappingProfile>,
+) -> Result<Payload<SupplierProfile>> {
+    let tenant_id = &context.tenant_id;
+    SaveMappingProfileUseCase::new(mapping_profile_gateway(db, tenant_id))
+        .execute(&context, supplier, profile.into_inner())
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies/import/profiles",
+    params(("supplier" = String, Path, description = "Supplier the profile belongs to")),
+    responses(
+        (status = 204, description = "Profile deleted"),
+        (status = 404, description = "No profile for the supplier", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[delete("/<supplier>")]
+pub async fn delete(
+    db: &State<DbSqlx>,
+    context: AuditContext,
+    supplier: &str,
+) -> Result<NoContent> {
+    let tenant_id = &context.tenant_id;
+    DeleteMappingProfileUseCase::new(mapping_profile_gateway(db, tenant_id))
+        .execute(&context, supplier)
+        .await
+        .map(|_| NoContent)
+        .map_err(Problem::from)
+}
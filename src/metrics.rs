@@ -0,0 +1,374 @@
+use crate::infra::db::Db;
+use crate::infra::db::DbSqlx;
+use chrono::Utc;
+use prometheus::Encoder;
+use prometheus::GaugeVec;
+use prometheus::HistogramOpts;
+use prometheus::HistogramVec;
+use prometheus::IntCounterVec;
+use prometheus::IntGauge;
+use prometheus::IntGaugeVec;
+use prometheus::Opts;
+use prometheus::Registry;
+use prometheus::TextEncoder;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::get;
+use rocket::http::ContentType;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::Data;
+use rocket::Request;
+use rocket::Response;
+use rocket_db_pools::Database;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::error;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+static COUNTERS: LazyLock<Mutex<HashMap<String, IntCounterVec>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(metric: T) -> T {
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+/// Returns the counter `name`, registering it on first use. Calling it again
+/// with the same name returns the same counter, so code can look its counters
+/// up where it increments them:
+///
+/// ```text
+/// metrics::counter("supplies_created_total", "Supplies created", &["tenant"])
+///     .with_label_values(&[tenant])
+///     .inc();
+/// ```
+///
+/// A name or label Prometheus rejects, or a name taken by another metric, is
+/// logged once and gets a counter that is not exported.
+pub fn counter(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let mut counters = COUNTERS.lock().unwrap();
+    if let Some(counter) = counters.get(name) {
+        return counter.clone();
+    }
+
+    // Built-in metrics keep their names even when a counter asks first
+    register_builtins();
+    let counter = IntCounterVec::new(Opts::new(name, help), labels)
+        .and_then(|counter| {
+            REGISTRY.register(Box::new(counter.clone()))?;
+            Ok(counter)
+        })
+        .unwrap_or_else(|e| {
+            error!("counter '{}' is not exported: {}", name, e);
+            unregistered(labels.len())
+        });
+    counters.insert(name.to_string(), counter.clone());
+    counter
+}
+
+/// A counter taking `labels` label values that no registry exports.
+fn unregistered(labels: usize) -> IntCounterVec {
+    let names = (0..labels)
+        .map(|i| format!("label_{}", i))
+        .collect::<Vec<_>>();
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+    IntCounterVec::new(Opts::new("unregistered_total", "Not exported"), &names)
+        .expect("placeholder names are valid")
+}
+
+struct HttpMetrics {
+    requests: IntCounterVec,
+    duration: HistogramVec,
+    in_flight: IntGauge,
+}
+
+static HTTP: LazyLock<HttpMetrics> = LazyLock::new(|| HttpMetrics {
+    requests: register(
+        IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap(),
+    ),
+    duration: register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap(),
+    ),
+    in_flight: register(
+        IntGauge::new("http_requests_in_flight", "HTTP requests being served").unwrap(),
+    ),
+});
+
+struct PoolMetrics {
+    size: IntGaugeVec,
+    idle: IntGaugeVec,
+}
+
+static POOLS: LazyLock<PoolMetrics> = LazyLock::new(|| PoolMetrics {
+    size: register(
+        IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open connections of the pool"),
+            &["pool"],
+        )
+        .unwrap(),
+    ),
+    idle: register(
+        IntGaugeVec::new(
+            Opts::new("db_pool_idle_connections", "Idle connections of the pool"),
+            &["pool"],
+        )
+        .unwrap(),
+    ),
+});
+
+struct RabbitMqMetrics {
+    published: IntCounterVec,
+    consumer_offset: IntGaugeVec,
+    consumer_lag: GaugeVec,
+}
+
+static RABBITMQ: LazyLock<RabbitMqMetrics> = LazyLock::new(|| RabbitMqMetrics {
+    published: register(
+        IntCounterVec::new(
+            Opts::new("rabbitmq_publish_total", "Messages published by result"),
+            &["stream", "result"],
+        )
+        .unwrap(),
+    ),
+    consumer_offset: register(
+        IntGaugeVec::new(
+            Opts::new("rabbitmq_consumer_offset", "Offset of the last delivery"),
+            &["stream"],
+        )
+        .unwrap(),
+    ),
+    consumer_lag: register(
+        GaugeVec::new(
+            Opts::new(
+                "rabbitmq_consumer_lag_seconds",
+                "Time between publishing and consuming the last delivery",
+            ),
+            &["stream"],
+        )
+        .unwrap(),
+    ),
+});
+
+pub fn record_publish(stream: &str, success: bool) {
+    let result = if success { "success" } else { "failure" };
+    RABBITMQ
+        .published
+        .with_label_values(&[stream, result])
+        .inc();
+}
+
+/// Records a delivery of `stream`. `published_at` is the publish time in
+/// milliseconds, when the message carries it.
+pub fn record_delivery(stream: &str, offset: u64, published_at: Option<i64>) {
+    RABBITMQ
+        .consumer_offset
+        .with_label_values(&[stream])
+        .set(offset as i64);
+    if let Some(published_at) = published_at {
+        let lag = (Utc::now().timestamp_millis() - published_at).max(0) as f64 / 1000.0;
+        RABBITMQ.consumer_lag.with_label_values(&[stream]).set(lag);
+    }
+}
+
+/// The database pools attached to the application, if any. Unlike
+/// `Option<&DbSqlx>` it does not require the pools to be attached.
+pub struct Pools<'r> {
+    sqlx: Option<&'r DbSqlx>,
+    diesel: Option<&'r Db>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Pools<'r> {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Pools {
+            sqlx: DbSqlx::fetch(req.rocket()),
+            diesel: Db::fetch(req.rocket()),
+        })
+    }
+}
+
+fn observe_pools(pools: &Pools<'_>) {
+    if let Some(db) = pools.sqlx {
+        let pool = db.pool();
+        POOLS
+            .size
+            .with_label_values(&["sqlx"])
+            .set(pool.size() as i64);
+        POOLS
+            .idle
+            .with_label_values(&["sqlx"])
+            .set(pool.num_idle() as i64);
+    }
+    if let Some(db) = pools.diesel {
+        let status = db.status();
+        POOLS
+            .size
+            .with_label_values(&["diesel"])
+            .set(status.size as i64);
+        POOLS
+            .idle
+            .with_label_values(&["diesel"])
+            .set(status.available.max(0) as i64);
+    }
+}
+
+/// Registers the built-in metrics, which are created on first use.
+fn register_builtins() {
+    LazyLock::force(&HTTP);
+    LazyLock::force(&POOLS);
+    LazyLock::force(&RABBITMQ);
+}
+
+/// Renders every registered metric in the Prometheus text format.
+pub fn gather() -> String {
+    register_builtins();
+
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        error!("failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[derive(Clone, Copy)]
+struct RequestStart(Option<Instant>);
+
+/// Counts requests and their latency by route template and status, and the
+/// requests in flight.
+pub struct Metrics;
+
+#[rocket::async_trait]
+impl Fairing for Metrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Prometheus metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        HTTP.in_flight.inc();
+        req.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestStart(Some(started)) = *req.local_cache(|| RequestStart(None)) else {
+            return;
+        };
+        HTTP.in_flight.dec();
+
+        let route = req
+            .route()
+            .map(|route| route.uri.as_str())
+            .unwrap_or("unmatched");
+        let status = response.status().code.to_string();
+        let labels = [req.method().as_str(), route, status.as_str()];
+
+        HTTP.requests.with_label_values(&labels).inc();
+        HTTP.duration
+            .with_label_values(&labels)
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+#[utoipa::path(
+    tag = "metrics",
+    responses((status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"))
+)]
+#[get("/metrics")]
+pub fn metrics(pools: Pools<'_>) -> (ContentType, String) {
+    observe_pools(&pools);
+    (
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        gather(),
+    )
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::*;
+    use rocket::http::Status;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+
+    #[get("/items/<_id>")]
+    fn item(_id: u32) -> &'static str {
+        "item"
+    }
+
+    #[test]
+    fn counter_is_registered_once() {
+        let first = counter("metrics_test_total", "test counter", &["kind"]);
+        first.with_label_values(&["a"]).inc();
+
+        let second = counter("metrics_test_total", "test counter", &["kind"]);
+        second.with_label_values(&["a"]).inc();
+
+        assert_eq!(first.with_label_values(&["a"]).get(), 2);
+        assert!(gather().contains("metrics_test_total{kind=\"a\"} 2"));
+    }
+
+    #[test]
+    fn invalid_or_taken_counters_are_not_exported() {
+        for (name, labels) in [
+            ("metrics test total", &["kind"][..]),
+            ("metrics_invalid_label_total", &["not a label"][..]),
+            ("http_requests_in_flight", &["kind"][..]),
+        ] {
+            let counter = counter(name, "test counter", labels);
+            counter.with_label_values(&["a"]).inc();
+            assert_eq!(counter.with_label_values(&["a"]).get(), 1);
+        }
+
+        let exported = gather();
+        assert!(!exported.contains("metrics_invalid_label_total"));
+        assert!(!exported.contains("http_requests_in_flight{"));
+    }
+
+    #[test]
+    fn exposes_request_metrics_by_route_template() {
+        let rocket = rocket::build()
+            .attach(Metrics)
+            .mount("/", routes![item, metrics]);
+        let client = Client::tracked(rocket).unwrap();
+
+        assert_eq!(client.get("/items/1").dispatch().status(), Status::Ok);
+        assert_eq!(client.get("/items/2").dispatch().status(), Status::Ok);
+        record_publish("metrics-test", false);
+        record_delivery("metrics-test", 7, Some(Utc::now().timestamp_millis()));
+
+        let response = client.get("/metrics").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().unwrap();
+
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",route=\"/items/<_id>\",status=\"200\"} 2"
+        ));
+        assert!(body.contains("http_request_duration_seconds_bucket"));
+        assert!(body.contains("http_requests_in_flight"));
+        assert!(
+            body.contains("rabbitmq_publish_total{result=\"failure\",stream=\"metrics-test\"} 1")
+        );
+        assert!(body.contains("rabbitmq_consumer_offset{stream=\"metrics-test\"} 7"));
+    }
+}
@@ -0,0 +1,121 @@
+use super::Subject;
+use jsonwebtoken::decode;
+use jsonwebtoken::Algorithm;
+use jsonwebtoken::DecodingKey;
+use jsonwebtoken::Validation;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::Build;
+use rocket::Data;
+use rocket::Request;
+use rocket::Rocket;
+use serde::Deserialize;
+use serde_json::Map;
+use serde_json::Value;
+use tracing::error;
+
+/*
+[default.jwt]
+secret = "change-me"
+issuer = "https://auth.example.com"
+audience = "solution"
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtConfig {
+    pub secret: String,
+    #[serde(default)]
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub audience: Option<String>,
+}
+
+impl JwtConfig {
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(Algorithm::HS256);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        validation
+    }
+
+    pub fn verify(&self, token: &str) -> Option<Map<String, Value>> {
+        let key = DecodingKey::from_secret(self.secret.as_bytes());
+        decode::<Map<String, Value>>(token, &key, &self.validation())
+            .map(|data| data.claims)
+            .ok()
+    }
+}
+
+/// Claims of a verified `Authorization: Bearer` token, cached on the request.
+#[derive(Debug, Clone, Default)]
+pub struct Claims(pub Option<Map<String, Value>>);
+
+impl Claims {
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.0.as_ref()?.get(name)?.as_str()
+    }
+
+    /// The strings of the array claim `name`, empty when there is none.
+    pub fn get_strs(&self, name: &str) -> Vec<&str> {
+        self.0
+            .as_ref()
+            .and_then(|claims| claims.get(name)?.as_array())
+            .map(|values| values.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Verifies HS256 bearer tokens and caches their `Claims` and `Subject` on the
+/// request. Invalid or missing tokens leave the request unauthenticated. Must
+/// be attached before the fairings that read the subject.
+pub struct Authentication;
+
+#[rocket::async_trait]
+impl Fairing for Authentication {
+    fn info(&self) -> Info {
+        Info {
+            name: "JWT authentication",
+            kind: Kind::Ignite | Kind::Request,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        match rocket.figment().extract_inner::<JwtConfig>("jwt") {
+            Ok(config) => Ok(rocket.manage(config)),
+            Err(e) if e.missing() => Ok(rocket),
+            Err(e) => {
+                error!("invalid jwt config: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let Some(config) = req.rocket().state::<JwtConfig>() else {
+            return;
+        };
+        let Some(token) = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return;
+        };
+        let Some(claims) = config.verify(token.trim()) else {
+            return;
+        };
+
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        req.local_cache(|| Subject(subject));
+        req.local_cache(|| Claims(Some(claims)));
+    }
+}
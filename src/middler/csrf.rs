@@ -0,0 +1,422 @@
+use crate::problem;
+use crate::problem::Problem;
+use rocket::catch;
+use rocket::catchers;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::form::Form;
+use rocket::http::uri::Origin;
+use rocket::http::ContentType;
+use rocket::http::Cookie;
+use rocket::http::CookieJar;
+use rocket::http::Method;
+use rocket::http::SameSite;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::Build;
+use rocket::Data;
+use rocket::Request;
+use rocket::Rocket;
+use rocket_dyn_templates::handlebars::html_escape;
+use rocket_dyn_templates::handlebars::Context;
+use rocket_dyn_templates::handlebars::Handlebars;
+use rocket_dyn_templates::handlebars::Helper;
+use rocket_dyn_templates::handlebars::HelperResult;
+use rocket_dyn_templates::handlebars::Output;
+use rocket_dyn_templates::handlebars::RenderContext;
+use serde::Deserialize;
+use tracing::error;
+use uuid::Uuid;
+
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_FIELD: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Where rejected requests are sent so that no handler runs for them. No
+/// route is mounted there: its 404 catcher answers 403 to the requests the
+/// fairing rejected, and 404 to anyone asking for it directly.
+const CSRF_REJECTED_PATH: &str = "/__csrf_rejected";
+/// The most of a body Rocket lets a fairing peek at.
+const PEEK_LIMIT: usize = 512;
+
+/*
+[default.csrf]
+enabled = true
+exempt = ["/api"]
+*/
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CsrfConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub exempt: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for CsrfConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            exempt: Vec::new(),
+        }
+    }
+}
+
+/// The CSRF token of the current client. The token lives in a private
+/// (encrypted and signed) cookie and is created on first use.
+#[derive(Debug, Clone)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+
+    fn from_cookies(cookies: &CookieJar<'_>) -> Self {
+        if let Some(cookie) = cookies.get_private(CSRF_COOKIE) {
+            return CsrfToken(cookie.value().to_string());
+        }
+
+        let token = Uuid::new_v4().simple().to_string();
+        let cookie = Cookie::build((CSRF_COOKIE, token.clone()))
+            .http_only(true)
+            .same_site(SameSite::Strict);
+        cookies.add_private(cookie);
+        CsrfToken(token)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfToken {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(CsrfToken::from_cookies(req.cookies()))
+    }
+}
+
+/// Rejects `POST`, `PUT`, `PATCH` and `DELETE` requests that could have been
+/// sent cross-site (cookies or form bodies) unless they carry the token from
+/// the CSRF cookie in the `X-CSRF-Token` header or in a `csrf_token` form
+/// field.
+///
+/// A fairing only sees the first 512 bytes of a body, so the form field must
+/// end within them. `{{csrf_field}}` at the top of a form does that for
+/// urlencoded forms. Multipart bodies carrying files should send the header,
+/// since a field after the first file part is past what can be read.
+pub struct CsrfProtection;
+
+/// Marks a request the fairing rejected, for the catcher it is sent to.
+#[derive(Debug, Clone, Copy, Default)]
+struct Rejected(bool);
+
+#[rocket::async_trait]
+impl Fairing for CsrfProtection {
+    fn info(&self) -> Info {
+        Info {
+            name: "CSRF protection",
+            kind: Kind::Ignite | Kind::Request,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket.figment().extract_inner::<CsrfConfig>("csrf") {
+            Ok(config) => config,
+            Err(e) if e.missing() => CsrfConfig::default(),
+            Err(e) => {
+                error!("invalid csrf config: {}", e);
+                return Err(rocket);
+            }
+        };
+
+        Ok(rocket
+            .manage(config)
+            .register(CSRF_REJECTED_PATH, catchers![csrf_rejected]))
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, data: &mut Data<'_>) {
+        let Some(config) = req.rocket().state::<CsrfConfig>() else {
+            return;
+        };
+        if !config.enabled || !requires_token(req, config) {
+            return;
+        }
+
+        let expected = req
+            .cookies()
+            .get_private(CSRF_COOKIE)
+            .map(|cookie| cookie.value().to_string());
+        let submitted = match req.headers().get_one(CSRF_HEADER) {
+            Some(token) => Some(token.to_string()),
+            None => submitted_form_token(req.content_type(), data.peek(PEEK_LIMIT).await),
+        };
+
+        let valid = match (expected, submitted) {
+            (Some(expected), Some(submitted)) => constant_time_eq(&expected, &submitted),
+            _ => false,
+        };
+
+        if !valid {
+            req.local_cache(|| Rejected(true));
+            req.set_method(Method::Get);
+            req.set_uri(Origin::parse(CSRF_REJECTED_PATH).unwrap());
+        }
+    }
+}
+
+#[catch(404)]
+fn csrf_rejected(req: &Request) -> Problem {
+    match req.local_cache(Rejected::default) {
+        Rejected(true) => Problem::with_type(
+            Status::Forbidden,
+            "csrf-rejected",
+            "CSRF check failed",
+            "missing or invalid csrf token",
+        ),
+        Rejected(false) => problem::not_found(req),
+    }
+}
+
+fn requires_token(req: &Request<'_>, config: &CsrfConfig) -> bool {
+    let unsafe_method = matches!(
+        req.method(),
+        Method::Post | Method::Put | Method::Patch | Method::Delete
+    );
+    if !unsafe_method {
+        return false;
+    }
+
+    let path = req.uri().path();
+    if config
+        .exempt
+        .iter()
+        .any(|prefix| path.starts_with(prefix.as_str()))
+    {
+        return false;
+    }
+
+    let has_cookies = req.cookies().iter().next().is_some();
+    let simple_body = req
+        .content_type()
+        .is_some_and(|ct| ct.is_form() || ct.is_form_data() || ct.is_plain());
+    has_cookies || simple_body
+}
+
+fn submitted_form_token(content_type: Option<&ContentType>, body: &[u8]) -> Option<String> {
+    let content_type = content_type?;
+    let body = String::from_utf8_lossy(body);
+
+    if content_type.is_form() {
+        return Form::values(&body)
+            .find(|field| field.name == CSRF_FIELD)
+            .map(|field| field.value.to_string());
+    }
+
+    if content_type.is_form_data() {
+        let boundary = content_type.param("boundary")?;
+        return multipart_field(&body, boundary, CSRF_FIELD);
+    }
+
+    None
+}
+
+/// The value of the text field `name` among the complete parts of a
+/// multipart body, which may be cut short.
+fn multipart_field(body: &str, boundary: &str, name: &str) -> Option<String> {
+    let delimiter = format!("--{}", boundary);
+    body.split(delimiter.as_str())
+        .skip(1)
+        .filter_map(|part| part.strip_prefix("\r\n"))
+        .filter_map(|part| part.split_once("\r\n\r\n"))
+        .find_map(|(headers, content)| {
+            let field = headers.split("\r\n").find_map(|header| {
+                let (header, value) = header.split_once(':')?;
+                header
+                    .trim()
+                    .eq_ignore_ascii_case("content-disposition")
+                    .then(|| disposition_name(value))
+                    .flatten()
+            })?;
+            // A complete part ends with the CRLF before the next delimiter.
+            let value = content.strip_suffix("\r\n")?;
+            (field == name).then(|| value.to_string())
+        })
+}
+
+/// The `name` parameter of a `Content-Disposition: form-data` header, unless
+/// the part is a file.
+fn disposition_name(value: &str) -> Option<&str> {
+    let mut params = value.split(';').map(str::trim);
+    if !params.next()?.eq_ignore_ascii_case("form-data") {
+        return None;
+    }
+    let mut name = None;
+    for param in params {
+        let (key, value) = param.split_once('=')?;
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => name = Some(value.trim().trim_matches('"')),
+            "filename" => return None,
+            _ => {}
+        }
+    }
+    name
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// `{{csrf_field}}` renders a hidden input with the `csrf_token` value of the
+/// template context.
+fn csrf_field(
+    _: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(token) = ctx.data().get(CSRF_FIELD).and_then(|v| v.as_str()) {
+        out.write(&format!(
+            r#"<input type="hidden" name="{}" value="{}">"#,
+            CSRF_FIELD,
+            html_escape(token)
+        ))?;
+    }
+    Ok(())
+}
+
+pub fn register_helpers(handlebars: &mut Handlebars<'static>) {
+    handlebars.register_helper("csrf_field", Box::new(csrf_field));
+}
+
+#[cfg(test)]
+mod csrf_tests {
+    use super::*;
+    use rocket::get;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::post;
+    use rocket::routes;
+
+    #[get("/token")]
+    fn token(token: CsrfToken) -> String {
+        token.value().to_string()
+    }
+
+    #[post("/submit")]
+    fn submit() -> &'static str {
+        "ok"
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(CsrfProtection)
+            .mount("/", routes![token, submit]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn rejects_form_post_without_token() {
+        let client = client();
+        let response = client
+            .post("/submit")
+            .header(ContentType::Form)
+            .body("a=1")
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[test]
+    fn accepts_matching_header_and_form_field() {
+        let client = client();
+        let token = client.get("/token").dispatch().into_string().unwrap();
+
+        let response = client
+            .post("/submit")
+            .header(Header::new(CSRF_HEADER, token.clone()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/submit")
+            .header(ContentType::Form)
+            .body(format!("{}={}&a=1", CSRF_FIELD, token))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/submit")
+            .header(ContentType::Form)
+            .body(format!("{}=not-the-token&a=1", CSRF_FIELD))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[test]
+    fn ignores_requests_without_cookies_or_form_body() {
+        let client = Client::untracked(
+            rocket::build()
+                .attach(CsrfProtection)
+                .mount("/", routes![submit]),
+        )
+        .unwrap();
+        let response = client
+            .post("/submit")
+            .header(ContentType::JSON)
+            .body("{}")
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn reads_token_from_multipart_body() {
+        let ct = ContentType::new("multipart", "form-data").with_params(("boundary", "X"));
+        let token = |body: &str| submitted_form_token(Some(&ct), body.as_bytes());
+
+        let body = "--X\r\nContent-Disposition: form-data; name=\"csrf_token\"\r\n\r\nabc\r\n--X--";
+        assert_eq!(token(body), Some("abc".to_string()));
+
+        let body = concat!(
+            "--X\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\ncimento\r\n",
+            "--X\r\nContent-Disposition: form-data; name=\"csrf_token\"\r\n\r\nabc\r\n--X--"
+        );
+        assert_eq!(token(body), Some("abc".to_string()));
+
+        let body =
+            "--X\r\nContent-Disposition: form-data; name=\"csrf_token_old\"\r\n\r\nabc\r\n--X--";
+        assert_eq!(token(body), None);
+
+        let body = "--X\r\nContent-Disposition: form-data; name=\"csrf_token\"; filename=\"t\"\r\n\r\nabc\r\n--X--";
+        assert_eq!(token(body), None);
+
+        // Cut short by the peek limit before the part ends.
+        let body = "--X\r\nContent-Disposition: form-data; name=\"csrf_token\"\r\n\r\nab";
+        assert_eq!(token(body), None);
+    }
+
+    #[test]
+    fn reads_token_after_other_form_fields() {
+        let client = client();
+        let token = client.get("/token").dispatch().into_string().unwrap();
+
+        let response = client
+            .post("/submit")
+            .header(ContentType::Form)
+            .body(format!("name=cimento&price=12.50&{}={}", CSRF_FIELD, token))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client.get(CSRF_REJECTED_PATH).dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
@@ -0,0 +1,64 @@
+use super::IdempotencyRecord;
+use super::IdempotencyStore;
+use super::StoredResponse;
+use chrono::DateTime;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Keys kept in process memory. Retries are only recognised by the replica
+/// that handled the first request.
+#[derive(Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, IdempotencyRecord>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[rocket::async_trait]
+impl IdempotencyStore for MemoryStore {
+    async fn reserve(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let mut records = self.records.lock().unwrap();
+        if let Some(record) = records.get(key) {
+            let abandoned = record.response.is_none() && record.created_at < abandoned_before;
+            if record.created_at >= expired_before && !abandoned {
+                return Ok(Some(record.to_owned()));
+            }
+        }
+
+        records.insert(
+            key.to_string(),
+            IdempotencyRecord {
+                fingerprint: fingerprint.to_string(),
+                response: None,
+                created_at: now,
+            },
+        );
+        Ok(None)
+    }
+
+    async fn complete(&self, key: &str, response: &StoredResponse) -> anyhow::Result<()> {
+        if let Some(record) = self.records.lock().unwrap().get_mut(key) {
+            record.response = Some(response.to_owned());
+        }
+        Ok(())
+    }
+
+    async fn purge(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|_, record| record.created_at >= expired_before);
+        Ok((before - records.len()) as u64)
+    }
+}
@@ -0,0 +1,662 @@
+pub mod memory;
+pub mod postgres;
+
+use self::memory::MemoryStore;
+use self::postgres::PostgresStore;
+use super::caller;
+use super::payload;
+use super::payload::PayloadError;
+use super::tenant;
+use crate::infra::db::DbSqlx;
+use crate::problem;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use rocket::data::FromData;
+use rocket::data::Outcome as DataOutcome;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::Header;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::tokio;
+use rocket::Build;
+use rocket::Data;
+use rocket::Orbit;
+use rocket::Request;
+use rocket::Response;
+use rocket::Rocket;
+use rocket_db_pools::Database;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::io::Cursor;
+use std::sync::Arc;
+use tracing::error;
+use tracing::info;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Response headers kept with the stored response and replayed with it.
+const STORED_HEADERS: [&str; 3] = ["Content-Type", "Location", "ETag"];
+
+/*
+[default.idempotency]
+backend = "postgres"
+ttl_seconds = 86400
+reservation_timeout_seconds = 60
+purge_interval_seconds = 3600
+*/
+
+/// Where keys and responses are kept. Postgres shares them between replicas
+/// and keeps them across restarts; memory is for a single process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdempotencyBackend {
+    #[default]
+    Postgres,
+    Memory,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    #[serde(default)]
+    pub backend: IdempotencyBackend,
+    /// How long a key is remembered. Reusing it after that runs the request
+    /// again.
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+    /// How long a key stays reserved by a request that never stored its
+    /// response, because storing it failed or the process died. Retries get
+    /// 409 until then and run the request again after.
+    #[serde(default = "default_reservation_timeout_seconds")]
+    pub reservation_timeout_seconds: u64,
+    #[serde(default = "default_purge_interval_seconds")]
+    pub purge_interval_seconds: u64,
+}
+
+fn default_ttl_seconds() -> u64 {
+    86400
+}
+
+fn default_reservation_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_purge_interval_seconds() -> u64 {
+    3600
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            backend: IdempotencyBackend::default(),
+            ttl_seconds: default_ttl_seconds(),
+            reservation_timeout_seconds: default_reservation_timeout_seconds(),
+            purge_interval_seconds: default_purge_interval_seconds(),
+        }
+    }
+}
+
+impl IdempotencyConfig {
+    fn ttl(&self) -> Duration {
+        Duration::seconds(self.ttl_seconds.min(i64::MAX as u64) as i64)
+    }
+
+    fn reservation_timeout(&self) -> Duration {
+        Duration::seconds(self.reservation_timeout_seconds.min(i64::MAX as u64) as i64)
+    }
+}
+
+/// A response stored under an idempotency key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// A key and the fingerprint of the request that used it. `response` stays
+/// empty while that request is being handled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyRecord {
+    pub fingerprint: String,
+    pub response: Option<StoredResponse>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[rocket::async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Reserves `key` for the request with `fingerprint`. Returns the record
+    /// of an earlier request instead when the key was used after
+    /// `expired_before`, unless that request reserved it before
+    /// `abandoned_before` and never stored its response.
+    async fn reserve(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>>;
+
+    async fn complete(&self, key: &str, response: &StoredResponse) -> anyhow::Result<()>;
+
+    /// Deletes the keys used before `expired_before`.
+    async fn purge(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64>;
+}
+
+struct IdempotencyState {
+    config: IdempotencyConfig,
+    store: Arc<dyn IdempotencyStore>,
+}
+
+/// The `Idempotency-Key` header of a request. Keys are scoped to the tenant
+/// of the request and to its caller, so no one gets another caller's
+/// responses replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyKey(pub String);
+
+impl IdempotencyKey {
+    pub fn is_valid(key: &str) -> bool {
+        !key.is_empty() && key.len() <= MAX_KEY_LENGTH && key.bytes().all(|b| b.is_ascii_graphic())
+    }
+}
+
+#[derive(Debug)]
+pub enum IdempotencyError {
+    Invalid,
+    TooLarge,
+    Malformed(String),
+    InProgress,
+    Mismatch,
+    Replayed,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = IdempotencyError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one(IDEMPOTENCY_KEY_HEADER) {
+            None => Outcome::Forward(Status::BadRequest),
+            Some(key) if IdempotencyKey::is_valid(key) => {
+                let tenant = tenant::resolve(req).unwrap_or_else(|| "-".to_string());
+                let caller = caller(req).unwrap_or_else(|| "-".to_string());
+                Outcome::Success(IdempotencyKey(format!("{}:{}:{}", tenant, caller, key)))
+            }
+            Some(_) => {
+                problem::reject(
+                    req,
+                    "the Idempotency-Key header must have 1 to 255 visible ASCII characters",
+                );
+                Outcome::Error((Status::BadRequest, IdempotencyError::Invalid))
+            }
+        }
+    }
+}
+
+/// Hash of what makes two requests the same: method, path and body.
+pub fn fingerprint(req: &Request<'_>, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update([0]);
+    hasher.update(req.uri().path().as_str());
+    hasher.update([0]);
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Set on a request that holds the reservation of its key, so the fairing
+/// stores its response.
+#[derive(Debug, Clone, Default)]
+struct Reserved(Option<String>);
+
+/// Set on a request whose key already has a response, so the fairing replays
+/// it.
+#[derive(Debug, Clone, Default)]
+struct Replay(Option<StoredResponse>);
+
+/// A JSON or MessagePack body made idempotent by an optional
+/// `Idempotency-Key` header. Without the header it behaves like `Payload<T>`.
+/// With it, the first request runs and its response is stored; a retry with
+/// the same body gets the stored response back, and a reuse of the key with
+/// another body is rejected with 422.
+#[derive(Debug, Clone)]
+pub struct Idempotent<T>(pub T);
+
+impl<T> Idempotent<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Idempotent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+fn fail<'r, T>(
+    req: &'r Request<'_>,
+    status: Status,
+    error: IdempotencyError,
+    detail: &str,
+) -> DataOutcome<'r, T, IdempotencyError> {
+    problem::reject(req, detail);
+    DataOutcome::Error((status, error))
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + Send> FromData<'r> for Idempotent<T> {
+    type Error = IdempotencyError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> DataOutcome<'r, Self, Self::Error> {
+        let key = match req.guard::<IdempotencyKey>().await {
+            Outcome::Success(key) => Some(key),
+            Outcome::Forward(_) => None,
+            Outcome::Error((status, error)) => return DataOutcome::Error((status, error)),
+        };
+
+        let (value, body) = match payload::read::<T>(req, data).await {
+            Ok(read) => read,
+            Err((status, PayloadError::TooLarge)) => {
+                return DataOutcome::Error((status, IdempotencyError::TooLarge))
+            }
+            Err((status, PayloadError::Malformed(e))) => {
+                return DataOutcome::Error((status, IdempotencyError::Malformed(e)))
+            }
+        };
+
+        let (Some(IdempotencyKey(key)), Some(state)) =
+            (key, req.rocket().state::<IdempotencyState>())
+        else {
+            return DataOutcome::Success(Idempotent(value));
+        };
+
+        let now = Utc::now();
+        let fingerprint = fingerprint(req, &body);
+        let record = match state
+            .store
+            .reserve(
+                &key,
+                &fingerprint,
+                now,
+                now - state.config.ttl(),
+                now - state.config.reservation_timeout(),
+            )
+            .await
+        {
+            Ok(record) => record,
+            Err(e) => {
+                error!(error = ?e, "idempotency store failed, handling request without it");
+                return DataOutcome::Success(Idempotent(value));
+            }
+        };
+
+        match record {
+            None => {
+                req.local_cache(|| Reserved(Some(key)));
+                DataOutcome::Success(Idempotent(value))
+            }
+            Some(record) if record.fingerprint != fingerprint => fail(
+                req,
+                Status::UnprocessableEntity,
+                IdempotencyError::Mismatch,
+                "the Idempotency-Key was already used with a different request",
+            ),
+            Some(IdempotencyRecord { response: None, .. }) => fail(
+                req,
+                Status::Conflict,
+                IdempotencyError::InProgress,
+                "a request with this Idempotency-Key is still being processed",
+            ),
+            Some(IdempotencyRecord {
+                response: Some(response),
+                ..
+            }) => {
+                req.local_cache(|| Replay(Some(response)));
+                // The fairing replaces the error response with the stored one
+                DataOutcome::Error((Status::Conflict, IdempotencyError::Replayed))
+            }
+        }
+    }
+}
+
+/// Stores the responses of requests made with an `Idempotency-Key` through
+/// an `Idempotent` body, replays them to retries and purges expired keys.
+/// Must be attached after `DbSqlx::init()` unless the memory backend is
+/// configured, and before fairings that compress responses.
+pub struct Idempotency;
+
+#[rocket::async_trait]
+impl Fairing for Idempotency {
+    fn info(&self) -> Info {
+        Info {
+            name: "Idempotency keys",
+            kind: Kind::Ignite | Kind::Liftoff | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket
+            .figment()
+            .extract_inner::<IdempotencyConfig>("idempotency")
+        {
+            Ok(config) => config,
+            Err(e) if e.missing() => IdempotencyConfig::default(),
+            Err(e) => {
+                error!("invalid idempotency config: {}", e);
+                return Err(rocket);
+            }
+        };
+
+        let store: Arc<dyn IdempotencyStore> = match config.backend {
+            IdempotencyBackend::Memory => Arc::new(MemoryStore::new()),
+            IdempotencyBackend::Postgres => match DbSqlx::fetch(&rocket) {
+                Some(db) => Arc::new(PostgresStore::new((**db).clone())),
+                None => {
+                    error!("idempotency postgres backend requires the sqlx database");
+                    return Err(rocket);
+                }
+            },
+        };
+
+        Ok(rocket.manage(IdempotencyState { config, store }))
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(state) = rocket.state::<IdempotencyState>() else {
+            return;
+        };
+        let store = state.store.clone();
+        let ttl = state.config.ttl();
+        let period = std::time::Duration::from_secs(state.config.purge_interval_seconds.max(1));
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = &mut shutdown => break,
+                }
+                match store.purge(Utc::now() - ttl).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!(purged, "purged expired idempotency keys"),
+                    Err(e) => error!(error = ?e, "failed to purge idempotency keys"),
+                }
+            }
+        });
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, response: &mut Response<'r>) {
+        if let Replay(Some(stored)) = req.local_cache(Replay::default) {
+            replay(stored, response);
+            return;
+        }
+        let Reserved(Some(key)) = req.local_cache(Reserved::default) else {
+            return;
+        };
+        let Some(state) = req.rocket().state::<IdempotencyState>() else {
+            return;
+        };
+
+        // Server errors are stored too: the handler may have committed its
+        // change before failing, and running it again would repeat it
+        let body = match response.body_mut().to_bytes().await {
+            Ok(body) => body,
+            Err(e) => {
+                error!(error = ?e, "failed to read response for idempotency key");
+                return;
+            }
+        };
+        let stored = StoredResponse {
+            status: response.status().code,
+            headers: STORED_HEADERS
+                .iter()
+                .filter_map(|name| {
+                    response
+                        .headers()
+                        .get_one(name)
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect(),
+            body,
+        };
+        if let Err(e) = state.store.complete(key, &stored).await {
+            error!(error = ?e, "failed to store response for idempotency key");
+        }
+        response.set_sized_body(stored.body.len(), Cursor::new(stored.body));
+    }
+}
+
+fn replay(stored: &StoredResponse, response: &mut Response<'_>) {
+    response.set_status(Status::from_code(stored.status).unwrap_or(Status::Ok));
+    for (name, value) in &stored.headers {
+        response.set_header(Header::new(name.to_owned(), value.to_owned()));
+    }
+    response.set_header(Header::new(REPLAYED_HEADER, "true"));
+    response.set_sized_body(stored.body.len(), Cursor::new(stored.body.to_owned()));
+}
+
+#[cfg(test)]
+mod idempotency_tests {
+    use super::*;
+    use rocket::catchers;
+    use rocket::local::blocking::Client;
+    use rocket::post;
+    use rocket::routes;
+    use rocket::serde::json::Json;
+    use serde_json::json;
+    use serde_json::Value;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    #[post("/items", data = "<item>")]
+    fn create(item: Idempotent<Value>) -> (Status, Json<Value>) {
+        let id = CREATED.fetch_add(1, Ordering::SeqCst);
+        (Status::Created, Json(json!({ "id": id, "item": item.0 })))
+    }
+
+    /// Commits its change, then fails.
+    #[post("/failing", data = "<item>")]
+    fn failing(item: Idempotent<Value>) -> (Status, Json<Value>) {
+        let id = CREATED.fetch_add(1, Ordering::SeqCst);
+        (
+            Status::InternalServerError,
+            Json(json!({ "id": id, "item": item.0 })),
+        )
+    }
+
+    fn client() -> Client {
+        let figment = rocket::Config::figment().merge(("idempotency.backend", "memory"));
+        let rocket = rocket::custom(figment)
+            .attach(Idempotency)
+            .mount("/", routes![create, failing])
+            .register("/", catchers![crate::problem::default]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn post(client: &Client, key: Option<&str>, body: &str) -> (Status, Option<String>, Value) {
+        post_to(client, "/items", key, body)
+    }
+
+    fn post_to(
+        client: &Client,
+        path: &'static str,
+        key: Option<&str>,
+        body: &str,
+    ) -> (Status, Option<String>, Value) {
+        let mut request = client.post(path).body(body);
+        if let Some(key) = key {
+            request = request.header(Header::new(IDEMPOTENCY_KEY_HEADER, key.to_string()));
+        }
+        let response = request.dispatch();
+        let replayed = response
+            .headers()
+            .get_one(REPLAYED_HEADER)
+            .map(str::to_string);
+        (response.status(), replayed, response.into_json().unwrap())
+    }
+
+    #[test]
+    fn replays_the_stored_response_for_a_retry() {
+        let client = client();
+
+        let (status, replayed, first) = post(&client, Some("retry-1"), r#"{"name":"cimento"}"#);
+        assert_eq!(status, Status::Created);
+        assert_eq!(replayed, None);
+
+        let (status, replayed, second) = post(&client, Some("retry-1"), r#"{"name":"cimento"}"#);
+        assert_eq!(status, Status::Created);
+        assert_eq!(replayed.as_deref(), Some("true"));
+        assert_eq!(second, first);
+
+        let (_, _, other) = post(&client, None, r#"{"name":"cimento"}"#);
+        assert_ne!(other["id"], first["id"]);
+    }
+
+    #[test]
+    fn replays_server_errors_instead_of_running_the_request_again() {
+        let client = client();
+
+        let (status, _, first) = post_to(&client, "/failing", Some("fail-1"), "{}");
+        assert_eq!(status, Status::InternalServerError);
+
+        let (status, replayed, second) = post_to(&client, "/failing", Some("fail-1"), "{}");
+        assert_eq!(status, Status::InternalServerError);
+        assert_eq!(replayed.as_deref(), Some("true"));
+        assert_eq!(second, first);
+    }
+
+    #[rocket::async_test]
+    async fn defaults_to_the_postgres_backend() {
+        assert_eq!(
+            IdempotencyConfig::default().backend,
+            IdempotencyBackend::Postgres
+        );
+        let error = rocket::build()
+            .attach(Idempotency)
+            .ignite()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            rocket::error::ErrorKind::FailedFairings(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_reused_key_with_another_body() {
+        let client = client();
+
+        post(&client, Some("reuse-1"), r#"{"name":"cimento"}"#);
+        let (status, _, problem) = post(&client, Some("reuse-1"), r#"{"name":"areia"}"#);
+
+        assert_eq!(status, Status::UnprocessableEntity);
+        assert!(problem["detail"]
+            .as_str()
+            .unwrap()
+            .contains("different request"));
+
+        let (status, _, _) = post(&client, Some("bad key"), r#"{}"#);
+        assert_eq!(status, Status::BadRequest);
+    }
+
+    #[test]
+    fn scopes_keys_to_the_caller() {
+        let client = client();
+        let body = r#"{"name":"cimento"}"#;
+
+        let (_, _, anonymous) = post(&client, Some("scope-1"), body);
+        let response = client
+            .post("/items")
+            .body(body)
+            .header(Header::new(IDEMPOTENCY_KEY_HEADER, "scope-1"))
+            .header(Header::new("x-api-key", "123456"))
+            .dispatch();
+
+        assert_eq!(response.headers().get_one(REPLAYED_HEADER), None);
+        assert_ne!(
+            response.into_json::<Value>().unwrap()["id"],
+            anonymous["id"]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn memory_store_expires_keys() {
+        let store = MemoryStore::new();
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+        let minute = Duration::minutes(1);
+
+        assert_eq!(
+            store
+                .reserve("k", "a", now, now - hour, now - minute)
+                .await
+                .unwrap(),
+            None
+        );
+        let pending = store
+            .reserve("k", "a", now, now - hour, now - minute)
+            .await
+            .unwrap();
+        assert_eq!(pending.unwrap().response, None);
+
+        let later = now + hour * 2;
+        assert_eq!(
+            store
+                .reserve("k", "c", later, later - hour, later - minute)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(store.purge(later + hour * 2).await.unwrap(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn memory_store_releases_abandoned_reservations() {
+        let store = MemoryStore::new();
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+        let minute = Duration::minutes(1);
+        let response = StoredResponse {
+            status: 201,
+            headers: vec![],
+            body: vec![],
+        };
+
+        store
+            .reserve("k", "a", now, now - hour, now - minute)
+            .await
+            .unwrap();
+        let later = now + minute * 2;
+        assert_eq!(
+            store
+                .reserve("k", "a", later, later - hour, later - minute)
+                .await
+                .unwrap(),
+            None
+        );
+
+        store.complete("k", &response).await.unwrap();
+        let much_later = later + minute * 2;
+        let stored = store
+            .reserve("k", "a", much_later, much_later - hour, much_later - minute)
+            .await
+            .unwrap();
+        assert_eq!(stored.unwrap().response, Some(response));
+    }
+}
@@ -0,0 +1,133 @@
+use super::IdempotencyRecord;
+use super::IdempotencyStore;
+use super::StoredResponse;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use sqlx::types::Json;
+
+/// Keys stored in the `idempotency_keys` table so a retry is recognised by
+/// every replica. The primary key makes concurrent reservations race safely.
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct IdempotencyRow {
+    fingerprint: String,
+    status: Option<i16>,
+    headers: Option<Json<Vec<(String, String)>>>,
+    body: Option<Vec<u8>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<IdempotencyRow> for IdempotencyRecord {
+    fn from(row: IdempotencyRow) -> Self {
+        let response = row.status.map(|status| StoredResponse {
+            status: status as u16,
+            headers: row.headers.map(|headers| headers.0).unwrap_or_default(),
+            body: row.body.unwrap_or_default(),
+        });
+        Self {
+            fingerprint: row.fingerprint,
+            response,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl IdempotencyStore for PostgresStore {
+    async fn reserve(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE key = $1
+              AND (created_at < $2 OR (status IS NULL AND created_at < $3))
+            "#,
+        )
+        .bind(key)
+        .bind(expired_before)
+        .bind(abandoned_before)
+        .execute(&mut *tx)
+        .await?;
+
+        let reserved = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (key, fingerprint, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+        )
+        .bind(key)
+        .bind(fingerprint)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            == 1;
+
+        let record = if reserved {
+            None
+        } else {
+            let row = sqlx::query_as::<_, IdempotencyRow>(
+                r#"
+                SELECT fingerprint, status, headers, body, created_at
+                FROM idempotency_keys
+                WHERE key = $1
+                "#,
+            )
+            .bind(key)
+            .fetch_one(&mut *tx)
+            .await?;
+            Some(IdempotencyRecord::from(row))
+        };
+
+        tx.commit().await?;
+
+        Ok(record)
+    }
+
+    async fn complete(&self, key: &str, response: &StoredResponse) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET status = $2, headers = $3, body = $4
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(response.status as i16)
+        .bind(Json(&response.headers))
+        .bind(&response.body)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn purge(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let purged = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+            .bind(expired_before)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(purged)
+    }
+}
@@ -0,0 +1,124 @@
+use super::auth::Claims;
+use crate::i18n::Locale;
+use rocket::http::Cookie;
+use rocket::http::CookieJar;
+use rocket::http::SameSite;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::time::Duration;
+use rocket::Request;
+use std::convert::Infallible;
+
+/// Cookie holding the locale a user picked, which wins over what the
+/// browser asks for.
+pub const LOCALE_COOKIE: &str = "locale";
+
+/// Claim of a verified JWT naming the preferred locale of its subject.
+pub const LOCALE_CLAIM: &str = "locale";
+
+/// The locale of the request, from the first of: the `locale` cookie, the
+/// `locale` claim of a verified JWT and `Accept-Language`. English
+/// otherwise.
+pub fn detect(req: &Request<'_>) -> Locale {
+    req.cookies()
+        .get(LOCALE_COOKIE)
+        .and_then(|cookie| Locale::parse(cookie.value()))
+        .or_else(|| {
+            req.local_cache(Claims::default)
+                .get_str(LOCALE_CLAIM)
+                .and_then(Locale::parse)
+        })
+        .or_else(|| {
+            req.headers()
+                .get_one("Accept-Language")
+                .and_then(Locale::negotiate)
+        })
+        .unwrap_or_default()
+}
+
+/// Remembers `locale` as the preference of the client for a year.
+pub fn remember(cookies: &CookieJar<'_>, locale: Locale) {
+    let cookie = Cookie::build((LOCALE_COOKIE, locale.tag()))
+        .path("/")
+        .max_age(Duration::days(365))
+        .same_site(SameSite::Lax);
+    cookies.add(cookie);
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Locale {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(detect(req))
+    }
+}
+
+#[cfg(test)]
+mod locale_tests {
+    use super::*;
+    use crate::middler::auth::Authentication;
+    use jsonwebtoken::encode;
+    use jsonwebtoken::EncodingKey;
+    use rocket::get;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+    use serde_json::json;
+
+    #[get("/")]
+    fn locale(locale: Locale) -> String {
+        locale.to_string()
+    }
+
+    #[test]
+    fn negotiates_accept_language() {
+        assert_eq!(Locale::negotiate("pt-BR,pt;q=0.9"), Some(Locale::PtBr));
+        assert_eq!(
+            Locale::negotiate("fr;q=1, en-US;q=0.5, pt;q=0.8"),
+            Some(Locale::PtBr)
+        );
+        assert_eq!(Locale::negotiate("pt;q=0, en-GB"), Some(Locale::En));
+        assert_eq!(Locale::negotiate("de, *;q=0.1"), None);
+        assert_eq!(Locale::negotiate(""), None);
+    }
+
+    #[test]
+    fn prefers_the_cookie_then_the_token_then_the_header() {
+        let figment = rocket::Config::figment().merge(("jwt.secret", "secret"));
+        let rocket = rocket::custom(figment)
+            .attach(Authentication)
+            .mount("/", routes![locale]);
+        let client = Client::tracked(rocket).unwrap();
+        let token = encode(
+            &jsonwebtoken::Header::default(),
+            &json!({ "sub": "alice", "locale": "en", "exp": 4102444800u64 }),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let response = client.get("/").dispatch();
+        assert_eq!(response.into_string().unwrap(), "en");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Accept-Language", "pt-BR,en;q=0.5"))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "pt-BR");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Accept-Language", "pt-BR"))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "en");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Accept-Language", "en"))
+            .header(Header::new("Authorization", format!("Bearer {}", token)))
+            .cookie(Cookie::new(LOCALE_COOKIE, "pt-BR"))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "pt-BR");
+    }
+}
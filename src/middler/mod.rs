@@ -0,0 +1,153 @@
+#![allow(dead_code)]
+use self::request_id::RequestId;
+use self::tenant::Tenant;
+use self::tenant::TenantError;
+use crate::application::audit::AuditContext;
+use crate::problem;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response;
+use rocket::response::Redirect;
+use rocket::response::Responder;
+use rocket::Request;
+use rocket::Response;
+use sha2::Digest;
+use sha2::Sha256;
+use std::io::Cursor;
+
+pub mod auth;
+pub mod csrf;
+pub mod idempotency;
+pub mod locale;
+pub mod payload;
+pub mod preconditions;
+pub mod rate_limit;
+pub mod request_id;
+pub mod security_headers;
+pub mod tenant;
+pub mod websocket;
+
+#[derive(Debug)]
+pub struct ApiKey<'a>(pub &'a str);
+
+impl<'a> ApiKey<'a> {
+    pub fn is_valid(key: &str) -> bool {
+        key == "123456"
+    }
+}
+
+/// Identity of the authenticated caller, cached on the request by the fairing
+/// or guard that performed authentication.
+#[derive(Debug, Clone, Default)]
+pub struct Subject(pub Option<String>);
+
+/// Names who is calling, for state kept per caller: a hash of a valid API
+/// key, else the authenticated subject, else the client IP. The hash is the
+/// same on every replica, so the name can be shared through Postgres.
+pub fn caller(req: &Request<'_>) -> Option<String> {
+    if let Some(key) = req
+        .headers()
+        .get_one("x-api-key")
+        .filter(|key| ApiKey::is_valid(key))
+    {
+        return Some(format!("key:{}", hex::encode(Sha256::digest(key))));
+    }
+
+    if let Subject(Some(subject)) = req.local_cache(Subject::default) {
+        return Some(format!("sub:{}", subject));
+    }
+
+    req.client_ip().map(|ip| format!("ip:{}", ip))
+}
+
+/// Resolves the actor of a mutation from the authenticated subject or the API
+/// key, falling back to `anonymous`, and the tenant it acts on.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuditContext {
+    type Error = TenantError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let tenant = match req.guard::<Tenant>().await {
+            Outcome::Success(Tenant(tenant)) => tenant,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        let actor = match req.local_cache(Subject::default) {
+            Subject(Some(subject)) => subject.to_owned(),
+            Subject(None) => match req.headers().get_one("x-api-key") {
+                Some(key) if ApiKey::is_valid(key) => "api-key".to_string(),
+                _ => "anonymous".to_string(),
+            },
+        };
+        let request_id = RequestId::of(req).as_str();
+
+        Outcome::Success(AuditContext::new(&actor, tenant, Some(request_id)))
+    }
+}
+
+#[derive(Debug)]
+pub enum ApiKeyError {
+    Missing,
+    Invalid,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKey<'r> {
+    type Error = ApiKeyError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one("x-api-key") {
+            None => {
+                problem::reject(req, "missing x-api-key header");
+                Outcome::Error((Status::BadRequest, ApiKeyError::Missing))
+            }
+            Some(key) if ApiKey::is_valid(key) => Outcome::Success(ApiKey(key)),
+            Some(_) => {
+                problem::reject(req, "invalid x-api-key header");
+                Outcome::Error((Status::BadRequest, ApiKeyError::Invalid))
+            }
+        }
+    }
+}
+
+pub enum MyResult {
+    Ok(String),
+    Err(String),
+    Redirect(Box<Redirect>),
+}
+
+#[rocket::async_trait]
+impl<'r> Responder<'r, 'static> for MyResult {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            MyResult::Ok(s) => Response::build()
+                .status(Status::Ok)
+                .sized_body(s.len(), Cursor::new(s))
+                .ok(),
+            MyResult::Err(s) => Response::build()
+                .status(Status::BadRequest)
+                .sized_body(s.len(), Cursor::new(s))
+                .ok(),
+            MyResult::Redirect(r) => r.respond_to(req),
+        }
+    }
+}
+
+pub struct RemoveServerHeader;
+
+#[rocket::async_trait]
+impl Fairing for RemoveServerHeader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Remove Server header",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, _req: &'r Request<'_>, response: &mut Response<'r>) {
+        response.remove_header("Server");
+    }
+}
@@ -0,0 +1,257 @@
+use crate::problem;
+use rocket::data::ByteUnit;
+use rocket::data::FromData;
+use rocket::data::Limits;
+use rocket::data::Outcome;
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::response;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket::serde::msgpack;
+use rocket::Data;
+use rocket::Request;
+use rocket::Response;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::error;
+
+/// How a body is encoded. Both carry the same serde models; MessagePack
+/// writes structs as maps, so fields are named as in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    MsgPack,
+}
+
+impl Format {
+    /// The format of the request body, from its `Content-Type`. JSON unless
+    /// it is `application/msgpack`.
+    pub fn of_body(req: &Request<'_>) -> Self {
+        match req.content_type() {
+            Some(content_type) if content_type.is_msgpack() => Format::MsgPack,
+            _ => Format::Json,
+        }
+    }
+
+    /// The format the client prefers in its `Accept` header. JSON unless
+    /// `application/msgpack` ranks first.
+    pub fn accepted(req: &Request<'_>) -> Self {
+        match req.accept() {
+            Some(accept) if accept.preferred().media_type().is_msgpack() => Format::MsgPack,
+            _ => Format::Json,
+        }
+    }
+
+    /// The `limits` entry bounding bodies of this format.
+    pub fn limit(&self, req: &Request<'_>) -> ByteUnit {
+        match self {
+            Format::Json => req.limits().get("json").unwrap_or(Limits::JSON),
+            Format::MsgPack => req.limits().get("msgpack").unwrap_or(Limits::MESSAGE_PACK),
+        }
+    }
+
+    /// Decodes `body`, returning 422 for well formed bodies that don't match
+    /// `T` and 400 for the others.
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, (Status, String)> {
+        match self {
+            Format::Json => serde_json::from_slice(body).map_err(|e| {
+                let status = match e.classify() {
+                    serde_json::error::Category::Data => Status::UnprocessableEntity,
+                    _ => Status::BadRequest,
+                };
+                (status, format!("invalid JSON body: {}", e))
+            }),
+            Format::MsgPack => msgpack::from_slice(body).map_err(|e| {
+                let status = match e {
+                    msgpack::Error::InvalidMarkerRead(_)
+                    | msgpack::Error::InvalidDataRead(_)
+                    | msgpack::Error::DepthLimitExceeded => Status::BadRequest,
+                    _ => Status::UnprocessableEntity,
+                };
+                (status, format!("invalid MessagePack body: {}", e))
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PayloadError {
+    TooLarge,
+    Malformed(String),
+}
+
+/// Reads the body of `req` in the format of its `Content-Type`, keeping the
+/// raw bytes for guards that need them. Failures leave their detail for the
+/// catcher.
+pub async fn read<T: DeserializeOwned>(
+    req: &Request<'_>,
+    data: Data<'_>,
+) -> Result<(T, Vec<u8>), (Status, PayloadError)> {
+    let format = Format::of_body(req);
+    let limit = format.limit(req);
+    let body = match data.open(limit).into_bytes().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => {
+            problem::reject(
+                req,
+                &format!("the body is larger than the limit of {}", limit),
+            );
+            return Err((Status::PayloadTooLarge, PayloadError::TooLarge));
+        }
+        Err(e) => {
+            problem::reject(req, &format!("failed to read the body: {}", e));
+            return Err((Status::BadRequest, PayloadError::Malformed(e.to_string())));
+        }
+    };
+
+    match format.decode(&body) {
+        Ok(value) => Ok((value, body)),
+        Err((status, detail)) => {
+            problem::reject(req, &detail);
+            Err((status, PayloadError::Malformed(detail)))
+        }
+    }
+}
+
+/// A JSON or MessagePack API payload. As a request body it is decoded by its
+/// `Content-Type`; as a response it is encoded as the `Accept` header
+/// prefers, JSON by default. Problems stay `application/problem+json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payload<T>(pub T);
+
+impl<T> Payload<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Payload<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + Send> FromData<'r> for Payload<T> {
+    type Error = PayloadError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        match read(req, data).await {
+            Ok((value, _)) => Outcome::Success(Payload(value)),
+            Err(failure) => Outcome::Error(failure),
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Payload<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let body = match Format::accepted(req) {
+            Format::Json => Json(self.0).respond_to(req)?,
+            Format::MsgPack => {
+                let bytes = msgpack::to_vec(&self.0).map_err(|e| {
+                    error!(error = ?e, "failed to serialize MessagePack response");
+                    Status::InternalServerError
+                })?;
+                (ContentType::MsgPack, bytes).respond_to(req)?
+            }
+        };
+        Response::build_from(body).raw_header("Vary", "Accept").ok()
+    }
+}
+
+#[cfg(test)]
+mod payload_tests {
+    use super::*;
+    use crate::problem::default as default_catcher;
+    use rocket::catchers;
+    use rocket::http::Accept;
+    use rocket::local::blocking::Client;
+    use rocket::post;
+    use rocket::routes;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        quantity: u32,
+    }
+
+    #[post("/", data = "<item>")]
+    fn echo(item: Payload<Item>) -> Payload<Item> {
+        item
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![echo])
+            .register("/", catchers![default_catcher]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn item() -> Item {
+        Item {
+            name: "cimento".to_string(),
+            quantity: 2,
+        }
+    }
+
+    #[test]
+    fn negotiates_json_and_msgpack_bodies_independently() {
+        let client = client();
+
+        let response = client
+            .post("/")
+            .header(ContentType::MsgPack)
+            .header(Accept::JSON)
+            .body(msgpack::to_vec(&item()).unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        assert_eq!(response.headers().get_one("Vary"), Some("Accept"));
+        assert_eq!(response.into_json::<Item>(), Some(item()));
+
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .header(Accept::MsgPack)
+            .body(r#"{"name":"cimento","quantity":2}"#)
+            .dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::MsgPack));
+        let bytes = response.into_bytes().unwrap();
+        assert_eq!(bytes, msgpack::to_vec(&item()).unwrap());
+        assert_eq!(msgpack::from_slice::<Item>(&bytes).unwrap(), item());
+
+        let response = client
+            .post("/")
+            .body(r#"{"name":"cimento","quantity":2}"#)
+            .dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+    }
+
+    #[test]
+    fn rejects_msgpack_bodies_that_do_not_match_the_model() {
+        let client = client();
+
+        let response = client
+            .post("/")
+            .header(ContentType::MsgPack)
+            .body(msgpack::to_vec(&("cimento", "two")).unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let problem = response.into_json::<serde_json::Value>().unwrap();
+        assert!(problem["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid MessagePack body"));
+
+        let response = client
+            .post("/")
+            .header(ContentType::MsgPack)
+            .body(Vec::<u8>::new())
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::problem;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::response;
+use rocket::response::Responder;
+use rocket::Request;
+use rocket::Response;
+
+pub const ETAG_HEADER: &str = "ETag";
+
+/// The strong entity tag of `version` of a resource.
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// The entity tags of an `If-Match` or `If-None-Match` header.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityTags {
+    Any,
+    Tags(Vec<String>),
+}
+
+impl EntityTags {
+    pub fn parse(value: &str) -> Self {
+        if value.trim() == "*" {
+            return EntityTags::Any;
+        }
+        EntityTags::Tags(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    /// Weak comparison, which ignores the `W/` prefix of weak tags.
+    pub fn matches(&self, etag: &str) -> bool {
+        let opaque = |tag: &str| tag.strip_prefix("W/").unwrap_or(tag).to_string();
+        match self {
+            EntityTags::Any => true,
+            EntityTags::Tags(tags) => tags.iter().any(|tag| opaque(tag) == opaque(etag)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PreconditionError {
+    Missing,
+    Invalid(String),
+}
+
+/// The version an update applies to, from its required `If-Match` header.
+/// `None` when the header is `*` and any version may be replaced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IfMatch(pub Option<i64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = PreconditionError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(value) = req.headers().get_one("If-Match") else {
+            problem::reject(req, "updates require an If-Match header with the ETag read");
+            return Outcome::Error((Status::PreconditionRequired, PreconditionError::Missing));
+        };
+
+        // Versions are compared strongly and one at a time, so only a single
+        // strong tag can ever match
+        let version = match EntityTags::parse(value) {
+            EntityTags::Any => return Outcome::Success(IfMatch(None)),
+            EntityTags::Tags(tags) => match tags.as_slice() {
+                [tag] => tag
+                    .strip_prefix('"')
+                    .and_then(|tag| tag.strip_suffix('"'))
+                    .and_then(|version| version.parse::<i64>().ok()),
+                _ => None,
+            },
+        };
+        match version {
+            Some(version) => Outcome::Success(IfMatch(Some(version))),
+            None => {
+                problem::reject(req, &format!("'{}' does not match any version", value));
+                Outcome::Error((
+                    Status::PreconditionFailed,
+                    PreconditionError::Invalid(value.to_string()),
+                ))
+            }
+        }
+    }
+}
+
+/// The optional `If-None-Match` header of a read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfNoneMatch(pub Option<EntityTags>);
+
+impl IfNoneMatch {
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0.as_ref().is_some_and(|tags| tags.matches(etag))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfNoneMatch(
+            req.headers()
+                .get_one("If-None-Match")
+                .map(EntityTags::parse),
+        ))
+    }
+}
+
+/// A response carrying the `ETag` of the resource it represents.
+pub enum Tagged<R> {
+    Fresh(String, R),
+    NotModified(String),
+}
+
+impl<R> Tagged<R> {
+    pub fn new(etag: String, body: R) -> Self {
+        Tagged::Fresh(etag, body)
+    }
+
+    /// `304 Not Modified` when the client already holds `etag`.
+    pub fn unless_cached(etag: String, body: R, if_none_match: &IfNoneMatch) -> Self {
+        if if_none_match.matches(&etag) {
+            Tagged::NotModified(etag)
+        } else {
+            Tagged::Fresh(etag, body)
+        }
+    }
+}
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for Tagged<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            Tagged::Fresh(etag, body) => Response::build_from(body.respond_to(req)?)
+                .raw_header(ETAG_HEADER, etag)
+                .ok(),
+            Tagged::NotModified(etag) => Response::build()
+                .status(Status::NotModified)
+                .raw_header(ETAG_HEADER, etag)
+                .ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod preconditions_tests {
+    use super::*;
+    use rocket::catchers;
+    use rocket::get;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::put;
+    use rocket::routes;
+
+    #[get("/")]
+    fn read(if_none_match: IfNoneMatch) -> Tagged<&'static str> {
+        Tagged::unless_cached(etag(3), "supply", &if_none_match)
+    }
+
+    #[put("/")]
+    fn write(if_match: IfMatch) -> String {
+        format!("{:?}", if_match.0)
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![read, write])
+            .register("/", catchers![crate::problem::default]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn parses_and_compares_entity_tags() {
+        assert_eq!(EntityTags::parse(" * "), EntityTags::Any);
+        let tags = EntityTags::parse("\"1\", W/\"2\"");
+        assert!(tags.matches("\"1\""));
+        assert!(tags.matches("\"2\""));
+        assert!(!tags.matches("\"3\""));
+    }
+
+    #[test]
+    fn reads_return_etag_or_not_modified() {
+        let client = client();
+
+        let response = client.get("/").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one(ETAG_HEADER), Some("\"3\""));
+
+        let response = client
+            .get("/")
+            .header(Header::new("If-None-Match", "\"3\""))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(response.headers().get_one(ETAG_HEADER), Some("\"3\""));
+        assert!(response.into_string().unwrap_or_default().is_empty());
+
+        let response = client
+            .get("/")
+            .header(Header::new("If-None-Match", "\"2\""))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn updates_require_a_single_version() {
+        let client = client();
+
+        let response = client.put("/").dispatch();
+        assert_eq!(response.status(), Status::PreconditionRequired);
+        let problem = response.into_json::<serde_json::Value>().unwrap();
+        assert!(problem["detail"].as_str().unwrap().contains("If-Match"));
+
+        let response = client
+            .put("/")
+            .header(Header::new("If-Match", "\"4\""))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "Some(4)");
+
+        let response = client
+            .put("/")
+            .header(Header::new("If-Match", "*"))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "None");
+
+        let response = client
+            .put("/")
+            .header(Header::new("If-Match", "W/\"4\""))
+            .dispatch();
+        assert_eq!(response.status(), Status::PreconditionFailed);
+    }
+}
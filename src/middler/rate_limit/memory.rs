@@ -0,0 +1,47 @@
+use super::Decision;
+use super::Limit;
+use super::RateLimitStore;
+use super::TokenBucket;
+use super::IDLE_HOURS;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Buckets kept in process memory. Limits are enforced per replica.
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn prune(buckets: &mut HashMap<String, TokenBucket>, now: DateTime<Utc>) {
+        let idle = Duration::hours(IDLE_HOURS);
+        buckets.retain(|_, bucket| now - bucket.updated_at < idle);
+    }
+}
+
+#[rocket::async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, limit: &Limit, now: DateTime<Utc>) -> anyhow::Result<Decision> {
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            Self::prune(&mut buckets, now);
+        }
+
+        let decision = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::full(limit, now))
+            .take(limit, now);
+
+        Ok(decision)
+    }
+}
@@ -0,0 +1,440 @@
+pub mod memory;
+pub mod postgres;
+
+use self::memory::MemoryStore;
+use self::postgres::PostgresStore;
+use super::caller;
+use crate::infra::db::DbSqlx;
+use crate::problem;
+use crate::problem::Problem;
+use chrono::DateTime;
+use chrono::Utc;
+use rocket::catch;
+use rocket::catchers;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::uri::Origin;
+use rocket::http::Header;
+use rocket::http::Method;
+use rocket::http::Status;
+use rocket::Build;
+use rocket::Data;
+use rocket::Request;
+use rocket::Response;
+use rocket::Rocket;
+use rocket_db_pools::Database;
+use serde::Deserialize;
+use tracing::error;
+
+/// Where limited requests are sent so that no handler runs for them. No route
+/// is mounted there: its 404 catcher answers 429 to the requests the limiter
+/// sent, and 404 to anyone asking for it directly.
+const RATE_LIMITED_PATH: &str = "/__rate_limited";
+
+/// Hours a bucket goes unused before the stores drop it, by then as full as
+/// a new one.
+const IDLE_HOURS: i64 = 1;
+
+/*
+[default.rate_limit]
+backend = "memory"
+capacity = 120
+refill_per_second = 2.0
+
+[[default.rate_limit.groups]]
+name = "posts"
+prefix = "/posts"
+capacity = 30
+refill_per_second = 0.5
+*/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Memory,
+    Postgres,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Limit {
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteGroup {
+    pub name: String,
+    pub prefix: String,
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default = "default_capacity")]
+    pub capacity: u32,
+    #[serde(default = "default_refill_per_second")]
+    pub refill_per_second: f64,
+    #[serde(default)]
+    pub groups: Vec<RouteGroup>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_capacity() -> u32 {
+    120
+}
+
+fn default_refill_per_second() -> f64 {
+    2.0
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            backend: Backend::default(),
+            capacity: default_capacity(),
+            refill_per_second: default_refill_per_second(),
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Returns the group name and limit for `path`, picking the group with the
+    /// longest matching prefix and falling back to the global limit.
+    pub fn limit_for(&self, path: &str) -> (&str, Limit) {
+        self.groups
+            .iter()
+            .filter(|group| path.starts_with(group.prefix.as_str()))
+            .max_by_key(|group| group.prefix.len())
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    Limit {
+                        capacity: group.capacity,
+                        refill_per_second: group.refill_per_second,
+                    },
+                )
+            })
+            .unwrap_or((
+                "default",
+                Limit {
+                    capacity: self.capacity,
+                    refill_per_second: self.refill_per_second,
+                },
+            ))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let limits = std::iter::once(("default", self.capacity, self.refill_per_second)).chain(
+            self.groups
+                .iter()
+                .map(|g| (g.name.as_str(), g.capacity, g.refill_per_second)),
+        );
+        for (name, capacity, refill_per_second) in limits {
+            if capacity == 0 || refill_per_second <= 0.0 {
+                return Err(format!(
+                    "rate limit group '{}' must have a positive capacity and refill rate",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    pub retry_after: u64,
+    pub reset: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBucket {
+    pub tokens: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TokenBucket {
+    pub fn full(limit: &Limit, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: limit.capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since the last update and tries
+    /// to take one token from it.
+    pub fn take(&mut self, limit: &Limit, now: DateTime<Utc>) -> Decision {
+        let capacity = limit.capacity as f64;
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+
+        self.tokens = (self.tokens + elapsed * limit.refill_per_second).min(capacity);
+        self.updated_at = now;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+
+        let retry_after = if allowed {
+            0
+        } else {
+            ((1.0 - self.tokens) / limit.refill_per_second).ceil() as u64
+        };
+
+        Decision {
+            allowed,
+            limit: limit.capacity,
+            remaining: self.tokens.floor() as u32,
+            retry_after,
+            reset: ((capacity - self.tokens) / limit.refill_per_second).ceil() as u64,
+        }
+    }
+}
+
+#[rocket::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn take(&self, key: &str, limit: &Limit, now: DateTime<Utc>) -> anyhow::Result<Decision>;
+}
+
+struct RateLimitState {
+    config: RateLimitConfig,
+    store: Box<dyn RateLimitStore>,
+}
+
+/// Token-bucket rate limiter keyed by API key, authenticated subject or
+/// client IP. Must be attached after `DbSqlx::init()` when the postgres
+/// backend is used.
+pub struct RateLimiter;
+
+#[rocket::async_trait]
+impl Fairing for RateLimiter {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rate limiter",
+            kind: Kind::Ignite | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket
+            .figment()
+            .extract_inner::<RateLimitConfig>("rate_limit")
+        {
+            Ok(config) => config,
+            Err(e) if e.missing() => RateLimitConfig::default(),
+            Err(e) => {
+                error!("invalid rate_limit config: {}", e);
+                return Err(rocket);
+            }
+        };
+
+        if let Err(e) = config.validate() {
+            error!("{}", e);
+            return Err(rocket);
+        }
+
+        let store: Box<dyn RateLimitStore> = match config.backend {
+            Backend::Memory => Box::new(MemoryStore::new()),
+            Backend::Postgres => match DbSqlx::fetch(&rocket) {
+                Some(db) => Box::new(PostgresStore::new((**db).clone())),
+                None => {
+                    error!("rate_limit postgres backend requires the sqlx database");
+                    return Err(rocket);
+                }
+            },
+        };
+
+        Ok(rocket
+            .manage(RateLimitState { config, store })
+            .register(RATE_LIMITED_PATH, catchers![rate_limited]))
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let Some(state) = req.rocket().state::<RateLimitState>() else {
+            return;
+        };
+        if !state.config.enabled {
+            return;
+        }
+        let Some(client) = caller(req) else {
+            return;
+        };
+
+        let (group, limit) = state.config.limit_for(req.uri().path().as_str());
+        let key = format!("{}:{}", group, client);
+
+        let decision = match state.store.take(&key, &limit, Utc::now()).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                error!("rate limit store failed, letting request through: {:?}", e);
+                return;
+            }
+        };
+
+        req.local_cache(|| Some(decision));
+
+        if !decision.allowed {
+            req.set_method(Method::Get);
+            req.set_uri(Origin::parse(RATE_LIMITED_PATH).unwrap());
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(decision) = req.local_cache(|| None::<Decision>) else {
+            return;
+        };
+
+        response.set_header(Header::new("X-RateLimit-Limit", decision.limit.to_string()));
+        response.set_header(Header::new(
+            "X-RateLimit-Remaining",
+            decision.remaining.to_string(),
+        ));
+        response.set_header(Header::new("X-RateLimit-Reset", decision.reset.to_string()));
+
+        if !decision.allowed {
+            response.set_header(Header::new("Retry-After", decision.retry_after.to_string()));
+        }
+    }
+}
+
+#[catch(404)]
+fn rate_limited(req: &Request) -> Problem {
+    match req.local_cache(|| None::<Decision>) {
+        Some(decision) if !decision.allowed => Problem::with_type(
+            Status::TooManyRequests,
+            "rate-limited",
+            "Too many requests",
+            "the rate limit for this client was exceeded, see Retry-After",
+        ),
+        _ => problem::not_found(req),
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::*;
+    use chrono::Duration;
+    use rocket::get;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+
+    fn limit() -> Limit {
+        Limit {
+            capacity: 2,
+            refill_per_second: 0.5,
+        }
+    }
+
+    #[test]
+    fn take_until_empty_and_refill() {
+        let now = Utc::now();
+        let mut bucket = TokenBucket::full(&limit(), now);
+
+        assert!(bucket.take(&limit(), now).allowed);
+        assert!(bucket.take(&limit(), now).allowed);
+
+        let denied = bucket.take(&limit(), now);
+        assert!(!denied.allowed);
+        assert_eq!(denied.remaining, 0);
+        assert_eq!(denied.retry_after, 2);
+        assert_eq!(denied.reset, 4);
+
+        let refilled = bucket.take(&limit(), now + Duration::seconds(2));
+        assert!(refilled.allowed);
+        assert_eq!(refilled.remaining, 0);
+    }
+
+    #[test]
+    fn refill_never_exceeds_capacity() {
+        let now = Utc::now();
+        let mut bucket = TokenBucket::full(&limit(), now);
+
+        let decision = bucket.take(&limit(), now + Duration::hours(1));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+        assert_eq!(bucket.tokens, 1.0);
+    }
+
+    #[test]
+    fn limit_for_picks_longest_prefix() {
+        let group = |name: &str, prefix: &str, capacity| RouteGroup {
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+            capacity,
+            refill_per_second: 1.0,
+        };
+        let config = RateLimitConfig {
+            groups: vec![
+                group("posts", "/posts", 10),
+                group("create", "/posts/create", 1),
+            ],
+            ..RateLimitConfig::default()
+        };
+
+        assert_eq!(config.limit_for("/posts/create").0, "create");
+        assert_eq!(config.limit_for("/posts/1").1.capacity, 10);
+        assert_eq!(config.limit_for("/").0, "default");
+    }
+
+    #[get("/")]
+    fn index() -> &'static str {
+        "root"
+    }
+
+    #[test]
+    fn responds_429_with_rate_limit_headers() {
+        let figment = rocket::Config::figment()
+            .merge(("rate_limit.capacity", 1))
+            .merge(("rate_limit.refill_per_second", 0.01));
+        let rocket = rocket::custom(figment)
+            .attach(RateLimiter)
+            .mount("/", routes![index]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let ok = client
+            .get("/")
+            .header(Header::new("X-Real-IP", "10.0.0.1"))
+            .dispatch();
+        assert_eq!(ok.status(), Status::Ok);
+        assert_eq!(ok.headers().get_one("X-RateLimit-Limit"), Some("1"));
+        assert_eq!(ok.headers().get_one("X-RateLimit-Remaining"), Some("0"));
+
+        let limited = client
+            .get("/")
+            .header(Header::new("X-Real-IP", "10.0.0.1"))
+            .dispatch();
+        assert_eq!(limited.status(), Status::TooManyRequests);
+        assert_eq!(limited.headers().get_one("Retry-After"), Some("100"));
+        assert_eq!(
+            limited.into_json::<serde_json::Value>().unwrap()["type"],
+            "/problems/rate-limited"
+        );
+
+        let direct = client
+            .get(RATE_LIMITED_PATH)
+            .header(Header::new("X-Real-IP", "10.0.0.3"))
+            .dispatch();
+        assert_eq!(direct.status(), Status::NotFound);
+
+        let other = client
+            .get("/")
+            .header(Header::new("X-Real-IP", "10.0.0.2"))
+            .dispatch();
+        assert_eq!(other.status(), Status::Ok);
+    }
+}
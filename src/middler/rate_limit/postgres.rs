@@ -0,0 +1,127 @@
+use super::Decision;
+use super::Limit;
+use super::RateLimitStore;
+use super::TokenBucket;
+use super::IDLE_HOURS;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use rocket_db_pools::sqlx::PgPool;
+use std::sync::Mutex;
+use tracing::error;
+
+/// How often a replica deletes the idle buckets.
+const PRUNE_EVERY_MINUTES: i64 = 10;
+
+/// Buckets stored in the `rate_limit_buckets` table so every replica shares
+/// the same limits. The row is locked while the bucket is refilled, and
+/// idle buckets are deleted now and then, as `MemoryStore` drops them.
+pub struct PostgresStore {
+    pool: PgPool,
+    pruned_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl PostgresStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            pruned_at: Mutex::new(None),
+        }
+    }
+
+    /// Whether idle buckets are due to be deleted at `now`, marking them
+    /// pruned if so.
+    fn prune_due(&self, now: DateTime<Utc>) -> bool {
+        let mut pruned_at = self
+            .pruned_at
+            .lock()
+            .expect("rate limit prune time poisoned");
+        let due = pruned_at.is_none_or(|at| now - at >= Duration::minutes(PRUNE_EVERY_MINUTES));
+        if due {
+            *pruned_at = Some(now);
+        }
+        due
+    }
+
+    async fn prune(&self, now: DateTime<Utc>) -> sqlx::Result<()> {
+        sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < $1")
+            .bind(now - Duration::hours(IDLE_HOURS))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[rocket::async_trait]
+impl RateLimitStore for PostgresStore {
+    async fn take(&self, key: &str, limit: &Limit, now: DateTime<Utc>) -> anyhow::Result<Decision> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rate_limit_buckets (key, tokens, updated_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+        )
+        .bind(key)
+        .bind(limit.capacity as f64)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        let (tokens, updated_at) = sqlx::query_as::<_, (f64, DateTime<Utc>)>(
+            r#"
+            SELECT tokens, updated_at
+            FROM rate_limit_buckets
+            WHERE key = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut bucket = TokenBucket { tokens, updated_at };
+        let decision = bucket.take(limit, now);
+
+        sqlx::query(
+            r#"
+            UPDATE rate_limit_buckets
+            SET tokens = $2, updated_at = $3
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(bucket.tokens)
+        .bind(bucket.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if self.prune_due(now) {
+            if let Err(e) = self.prune(now).await {
+                error!("failed to delete idle rate limit buckets: {}", e);
+            }
+        }
+
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod postgres_store_tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn prunes_at_most_once_per_interval() {
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let store = PostgresStore::new(pool);
+        let now = Utc::now();
+
+        assert!(store.prune_due(now));
+        assert!(!store.prune_due(now + Duration::minutes(PRUNE_EVERY_MINUTES - 1)));
+        assert!(store.prune_due(now + Duration::minutes(PRUNE_EVERY_MINUTES)));
+    }
+}
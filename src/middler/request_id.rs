@@ -0,0 +1,194 @@
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::Header;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::route;
+use rocket::route::Handler;
+use rocket::tokio::task_local;
+use rocket::Data;
+use rocket::Request;
+use rocket::Response;
+use rocket::Route;
+use std::future::Future;
+use tracing::info_span;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+const MAX_LENGTH: usize = 128;
+
+task_local! {
+    static CURRENT: String;
+}
+
+/// Correlation id of the current request, taken from a well-formed
+/// `X-Request-Id` header or generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    /// Accepts ids of up to 128 ASCII letters, digits and `-_.:`, which are
+    /// safe to log and to send as a header.
+    pub fn is_valid(value: &str) -> bool {
+        !value.is_empty()
+            && value.len() <= MAX_LENGTH
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+    }
+
+    /// The id of `req`, resolved once and cached on the request.
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r RequestId {
+        req.local_cache(|| {
+            match req
+                .headers()
+                .get_one(REQUEST_ID_HEADER)
+                .filter(|id| RequestId::is_valid(id))
+            {
+                Some(id) => RequestId(id.to_string()),
+                None => RequestId::generate(),
+            }
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::of(req).to_owned())
+    }
+}
+
+/// The correlation id of the request or message being handled by the current
+/// task, if any.
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.to_owned()).ok()
+}
+
+/// Runs `future` with `id` as the current correlation id.
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    CURRENT.scope(id, future).await
+}
+
+/// Resolves the request id of every request and echoes it in the
+/// `X-Request-Id` response header.
+pub struct RequestIds;
+
+#[rocket::async_trait]
+impl Fairing for RequestIds {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ids",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        RequestId::of(req);
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, response: &mut Response<'r>) {
+        let id = RequestId::of(req);
+        response.set_header(Header::new(REQUEST_ID_HEADER, id.0.to_owned()));
+    }
+}
+
+#[derive(Clone)]
+struct Correlated(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Correlated {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let id = RequestId::of(req).as_str();
+        let span = info_span!(
+            "request",
+            method = %req.method(),
+            uri = %req.uri(),
+            request_id = id,
+        );
+        scope(id.to_string(), self.0.handle(req, data).instrument(span)).await
+    }
+}
+
+/// Runs the handlers of `routes` inside the correlation scope and a `request`
+/// span of their request, so `current` returns the request id and their log
+/// events carry it while they run.
+pub fn correlate(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Correlated(route.handler));
+            route
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod request_id_tests {
+    use super::*;
+    use rocket::get;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+
+    #[get("/")]
+    fn index(id: RequestId) -> String {
+        format!("{} {}", id.as_str(), current().unwrap_or_default())
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(RequestIds)
+            .mount("/", correlate(routes![index]));
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn keeps_valid_incoming_id() {
+        let client = client();
+        let response = client
+            .get("/")
+            .header(Header::new(REQUEST_ID_HEADER, "abc-123"))
+            .dispatch();
+
+        assert_eq!(
+            response.headers().get_one(REQUEST_ID_HEADER),
+            Some("abc-123")
+        );
+        assert_eq!(response.into_string().unwrap(), "abc-123 abc-123");
+    }
+
+    #[test]
+    fn replaces_missing_or_malformed_id() {
+        let client = client();
+        let response = client
+            .get("/")
+            .header(Header::new(REQUEST_ID_HEADER, "bad id\u{7f}"))
+            .dispatch();
+
+        let id = response
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_string();
+        assert!(Uuid::parse_str(&id).is_ok());
+        assert_eq!(response.into_string().unwrap(), format!("{} {}", id, id));
+    }
+
+    #[test]
+    fn current_is_empty_outside_a_scope() {
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,262 @@
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::ContentType;
+use rocket::http::Header;
+use rocket::Build;
+use rocket::Request;
+use rocket::Response;
+use rocket::Rocket;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::error;
+
+/*
+[default.security_headers]
+hsts = "max-age=63072000; includeSubDomains"
+frame_options = "DENY"
+
+[[default.security_headers.overrides]]
+route = "about"
+frame_options = "SAMEORIGIN"
+*/
+
+/// Header values applied to every response. An empty string disables the
+/// header.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SecurityHeadersConfig {
+    #[serde(default = "default_hsts")]
+    pub hsts: String,
+    #[serde(default = "default_content_security_policy")]
+    pub content_security_policy: String,
+    #[serde(default = "default_content_type_options")]
+    pub content_type_options: String,
+    #[serde(default = "default_referrer_policy")]
+    pub referrer_policy: String,
+    #[serde(default = "default_permissions_policy")]
+    pub permissions_policy: String,
+    #[serde(default = "default_frame_options")]
+    pub frame_options: String,
+    #[serde(default)]
+    pub overrides: Vec<HeaderOverride>,
+}
+
+/// Replaces some of the headers for the routes matching `route` (the handler
+/// name) or `prefix` (the request path).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct HeaderOverride {
+    pub route: Option<String>,
+    pub prefix: Option<String>,
+    pub hsts: Option<String>,
+    pub content_security_policy: Option<String>,
+    pub content_type_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+    pub frame_options: Option<String>,
+}
+
+fn default_hsts() -> String {
+    "max-age=63072000; includeSubDomains".to_string()
+}
+
+fn default_content_security_policy() -> String {
+    "default-src 'self'; img-src 'self' data:; object-src 'none'; frame-ancestors 'none'"
+        .to_string()
+}
+
+fn default_content_type_options() -> String {
+    "nosniff".to_string()
+}
+
+fn default_referrer_policy() -> String {
+    "strict-origin-when-cross-origin".to_string()
+}
+
+fn default_permissions_policy() -> String {
+    "camera=(), microphone=(), geolocation=()".to_string()
+}
+
+fn default_frame_options() -> String {
+    "DENY".to_string()
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            hsts: default_hsts(),
+            content_security_policy: default_content_security_policy(),
+            content_type_options: default_content_type_options(),
+            referrer_policy: default_referrer_policy(),
+            permissions_policy: default_permissions_policy(),
+            frame_options: default_frame_options(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl HeaderOverride {
+    fn matches(&self, route: Option<&str>, path: &str) -> bool {
+        if self.route.is_none() && self.prefix.is_none() {
+            return false;
+        }
+        let route_matches = match (&self.route, route) {
+            (Some(expected), Some(route)) => expected == route,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let prefix_matches = match &self.prefix {
+            Some(prefix) => path.starts_with(prefix.as_str()),
+            None => true,
+        };
+        route_matches && prefix_matches
+    }
+}
+
+impl SecurityHeadersConfig {
+    /// Resolves the headers for a route, applying matching overrides in the
+    /// order they are declared.
+    pub fn resolve(&self, route: Option<&str>, path: &str) -> SecurityHeadersConfig {
+        let mut resolved = SecurityHeadersConfig {
+            overrides: Vec::new(),
+            ..self.clone()
+        };
+
+        for o in self.overrides.iter().filter(|o| o.matches(route, path)) {
+            let fields = [
+                (&mut resolved.hsts, &o.hsts),
+                (
+                    &mut resolved.content_security_policy,
+                    &o.content_security_policy,
+                ),
+                (&mut resolved.content_type_options, &o.content_type_options),
+                (&mut resolved.referrer_policy, &o.referrer_policy),
+                (&mut resolved.permissions_policy, &o.permissions_policy),
+                (&mut resolved.frame_options, &o.frame_options),
+            ];
+            for (field, value) in fields {
+                if let Some(value) = value {
+                    *field = value.to_owned();
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+/// Sets HSTS, CSP and the other hardening headers configured under
+/// `security_headers`. HSTS is only sent when TLS is enabled and CSP only on
+/// HTML responses.
+pub struct SecurityHeaders;
+
+#[rocket::async_trait]
+impl Fairing for SecurityHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security headers",
+            kind: Kind::Ignite | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        match rocket
+            .figment()
+            .extract_inner::<SecurityHeadersConfig>("security_headers")
+        {
+            Ok(config) => Ok(rocket.manage(config)),
+            Err(e) if e.missing() => Ok(rocket.manage(SecurityHeadersConfig::default())),
+            Err(e) => {
+                error!("invalid security_headers config: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(config) = req.rocket().state::<SecurityHeadersConfig>() else {
+            return;
+        };
+
+        let route = req.route().and_then(|r| r.name.as_deref());
+        let headers = config.resolve(route, req.uri().path().as_str());
+        let is_html = response.content_type() == Some(ContentType::HTML);
+
+        let mut set = |name: &'static str, value: &str| {
+            if !value.is_empty() {
+                response.set_header(Header::new(name, value.to_owned()));
+            }
+        };
+
+        if req.rocket().config().tls_enabled() {
+            set("Strict-Transport-Security", &headers.hsts);
+        }
+        if is_html {
+            set("Content-Security-Policy", &headers.content_security_policy);
+        }
+        set("X-Content-Type-Options", &headers.content_type_options);
+        set("Referrer-Policy", &headers.referrer_policy);
+        set("Permissions-Policy", &headers.permissions_policy);
+        set("X-Frame-Options", &headers.frame_options);
+    }
+}
+
+#[cfg(test)]
+mod security_headers_tests {
+    use super::*;
+    use rocket::get;
+    use rocket::local::blocking::Client;
+    use rocket::response::content::RawHtml;
+    use rocket::routes;
+
+    #[get("/")]
+    fn index() -> &'static str {
+        "root"
+    }
+
+    #[get("/page")]
+    fn page() -> RawHtml<&'static str> {
+        RawHtml("<p>page</p>")
+    }
+
+    fn client() -> Client {
+        let figment = rocket::Config::figment()
+            .merge((
+                "security_headers.overrides",
+                vec![HeaderOverride {
+                    route: Some("page".to_string()),
+                    frame_options: Some("SAMEORIGIN".to_string()),
+                    ..HeaderOverride::default()
+                }],
+            ))
+            .merge(("security_headers.referrer_policy", ""));
+        let rocket = rocket::custom(figment)
+            .attach(SecurityHeaders)
+            .mount("/", routes![index, page]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    #[test]
+    fn sets_default_headers_without_csp_on_non_html() {
+        let client = client();
+        let response = client.get("/").dispatch();
+        let headers = response.headers();
+
+        assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+        assert_eq!(headers.get_one("X-Frame-Options"), Some("DENY"));
+        assert_eq!(headers.get_one("Content-Security-Policy"), None);
+        assert_eq!(headers.get_one("Referrer-Policy"), None);
+    }
+
+    #[test]
+    fn applies_route_override_and_csp_on_html() {
+        let client = client();
+        let response = client.get("/page").dispatch();
+        let headers = response.headers();
+
+        assert_eq!(headers.get_one("X-Frame-Options"), Some("SAMEORIGIN"));
+        assert_eq!(
+            headers.get_one("Content-Security-Policy"),
+            Some(default_content_security_policy().as_str())
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use super::auth::Claims;
+use crate::domain::tenant_id::TenantId;
+use crate::problem;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::Build;
+use rocket::Request;
+use rocket::Rocket;
+use serde::Deserialize;
+use tracing::error;
+
+/*
+[default.tenancy]
+sources = ["jwt", "subdomain", "header"]
+header = "X-Tenant-Id"
+jwt_claim = "tenant_id"
+tenants_claim = "tenants"
+base_domain = "example.com"
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenantSource {
+    Jwt,
+    Subdomain,
+    Header,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenancyConfig {
+    #[serde(default = "default_sources")]
+    pub sources: Vec<TenantSource>,
+    #[serde(default = "default_header")]
+    pub header: String,
+    #[serde(default = "default_jwt_claim")]
+    pub jwt_claim: String,
+    /// Claim listing the tenants a token may pick with the subdomain or the
+    /// header.
+    #[serde(default = "default_tenants_claim")]
+    pub tenants_claim: String,
+    #[serde(default)]
+    pub base_domain: Option<String>,
+}
+
+fn default_sources() -> Vec<TenantSource> {
+    vec![TenantSource::Jwt, TenantSource::Subdomain]
+}
+
+fn default_header() -> String {
+    "X-Tenant-Id".to_string()
+}
+
+fn default_jwt_claim() -> String {
+    "tenant_id".to_string()
+}
+
+fn default_tenants_claim() -> String {
+    "tenants".to_string()
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            sources: default_sources(),
+            header: default_header(),
+            jwt_claim: default_jwt_claim(),
+            tenants_claim: default_tenants_claim(),
+            base_domain: None,
+        }
+    }
+}
+
+impl TenancyConfig {
+    fn subdomain<'a>(&self, host: &'a str) -> Option<&'a str> {
+        let base_domain = self.base_domain.as_deref()?;
+        let label = host.strip_suffix(base_domain)?.strip_suffix('.')?;
+        (!label.is_empty() && !label.contains('.')).then_some(label)
+    }
+
+    fn resolve(&self, req: &Request<'_>) -> Option<String> {
+        self.sources.iter().find_map(|source| match source {
+            TenantSource::Jwt => req
+                .local_cache(Claims::default)
+                .get_str(&self.jwt_claim)
+                .map(str::to_string),
+            TenantSource::Subdomain => req
+                .host()
+                .and_then(|host| self.subdomain(host.domain().as_str()))
+                .filter(|tenant| self.grants(req, tenant))
+                .map(str::to_string),
+            TenantSource::Header => req
+                .headers()
+                .get_one(&self.header)
+                .filter(|tenant| self.grants(req, tenant))
+                .map(str::to_string),
+        })
+    }
+
+    /// Whether the verified JWT of the request lists `tenant` among the ones
+    /// its subject may act on. The host or header alone proves nothing.
+    fn grants(&self, req: &Request<'_>, tenant: &str) -> bool {
+        self.granted(req.local_cache(Claims::default), tenant)
+    }
+
+    pub fn granted(&self, claims: &Claims, tenant: &str) -> bool {
+        claims.get_strs(&self.tenants_claim).contains(&tenant)
+    }
+
+    /// The tenant a verified token acts on outside of HTTP: its tenant claim,
+    /// else `requested` when the token grants it.
+    pub fn tenant_of(&self, claims: &Claims, requested: Option<&str>) -> Option<String> {
+        claims
+            .get_str(&self.jwt_claim)
+            .or(requested.filter(|tenant| self.granted(claims, tenant)))
+            .map(str::to_string)
+    }
+}
+
+/// Loads the `tenancy` configuration used by the `Tenant` guard.
+pub struct Tenancy;
+
+#[rocket::async_trait]
+impl Fairing for Tenancy {
+    fn info(&self) -> Info {
+        Info {
+            name: "Tenancy",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        match rocket.figment().extract_inner::<TenancyConfig>("tenancy") {
+            Ok(config) => Ok(rocket.manage(config)),
+            Err(e) if e.missing() => Ok(rocket.manage(TenancyConfig::default())),
+            Err(e) => {
+                error!("invalid tenancy config: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+}
+
+/// The tenant of the current request, resolved from the configured sources in
+/// order: a verified JWT claim, the subdomain of the host or a header. The
+/// subdomain and the header are only trusted for a tenant the verified JWT
+/// lists in its `tenants` claim, and the header is not a source unless
+/// configured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tenant(pub TenantId);
+
+/// The tenant the request names, before it is validated. Unlike the guard it
+/// leaves no rejection on the request.
+pub fn resolve(req: &Request<'_>) -> Option<String> {
+    let default = TenancyConfig::default();
+    let config = req.rocket().state::<TenancyConfig>().unwrap_or(&default);
+    config.resolve(req)
+}
+
+#[derive(Debug)]
+pub enum TenantError {
+    Missing,
+    Invalid(String),
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Tenant {
+    type Error = TenantError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match resolve(req) {
+            None => {
+                problem::reject(req, "the request does not identify a tenant");
+                Outcome::Error((Status::BadRequest, TenantError::Missing))
+            }
+            Some(tenant) if TenantId::is_valid(&tenant) => {
+                Outcome::Success(Tenant(TenantId::from_str(&tenant)))
+            }
+            Some(tenant) => {
+                problem::reject(req, &format!("'{}' is not a valid tenant id", tenant));
+                Outcome::Error((Status::BadRequest, TenantError::Invalid(tenant)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tenant_tests {
+    use super::*;
+    use crate::domain::identifier::Identifier;
+    use crate::middler::auth::Authentication;
+    use jsonwebtoken::encode;
+    use jsonwebtoken::EncodingKey;
+    use rocket::get;
+    use rocket::http::uri::Host;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+    use rocket::uri;
+    use serde_json::json;
+
+    #[get("/")]
+    fn tenant(tenant: Tenant) -> String {
+        tenant.0.get_value().to_string()
+    }
+
+    fn client() -> Client {
+        let figment = rocket::Config::figment()
+            .merge(("jwt.secret", "secret"))
+            .merge(("tenancy.base_domain", "example.com"))
+            .merge(("tenancy.sources", ["jwt", "subdomain", "header"]));
+        let rocket = rocket::custom(figment)
+            .attach(Authentication)
+            .attach(Tenancy)
+            .mount("/", routes![tenant]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn token(tenant: &str) -> String {
+        sign(json!({ "sub": "alice", "tenant_id": tenant, "exp": 4102444800u64 }))
+    }
+
+    fn sign(claims: serde_json::Value) -> String {
+        encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_tenant_from_each_source_in_order() {
+        let client = client();
+
+        let mut request = client
+            .get("/")
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", token("jwt-tenant")),
+            ))
+            .header(Header::new("X-Tenant-Id", "header-tenant"));
+        request.set_host(Host::from(uri!("sub-tenant.example.com")));
+        assert_eq!(request.dispatch().into_string().unwrap(), "jwt-tenant");
+
+        let granted = sign(json!({
+            "sub": "alice", "tenants": ["sub-tenant", "header-tenant"], "exp": 4102444800u64
+        }));
+        let mut request = client
+            .get("/")
+            .header(Header::new("Authorization", format!("Bearer {}", granted)))
+            .header(Header::new("X-Tenant-Id", "header-tenant"));
+        request.set_host(Host::from(uri!("sub-tenant.example.com")));
+        assert_eq!(request.dispatch().into_string().unwrap(), "sub-tenant");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Authorization", format!("Bearer {}", granted)))
+            .header(Header::new("X-Tenant-Id", "header-tenant"))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "header-tenant");
+    }
+
+    #[test]
+    fn ignores_a_subdomain_or_header_naming_a_tenant_the_caller_is_not_granted() {
+        let client = client();
+
+        let response = client
+            .get("/")
+            .header(Header::new("X-Tenant-Id", "header-tenant"))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let mut request = client.get("/");
+        request.set_host(Host::from(uri!("sub-tenant.example.com")));
+        assert_eq!(request.dispatch().status(), Status::BadRequest);
+
+        let granted = sign(json!({
+            "sub": "alice", "tenants": ["other-tenant"], "exp": 4102444800u64
+        }));
+        let response = client
+            .get("/")
+            .header(Header::new("Authorization", format!("Bearer {}", granted)))
+            .header(Header::new("X-Tenant-Id", "header-tenant"))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn rejects_missing_invalid_and_forged_tenants() {
+        let client = client();
+
+        assert_eq!(client.get("/").dispatch().status(), Status::BadRequest);
+
+        let response = client
+            .get("/")
+            .header(Header::new("X-Tenant-Id", "Not Valid"))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let forged = format!("{}x", token("jwt-tenant"));
+        let response = client
+            .get("/")
+            .header(Header::new("Authorization", format!("Bearer {}", forged)))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
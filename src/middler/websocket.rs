@@ -0,0 +1,213 @@
+use crate::problem;
+use rocket::data::IoHandler;
+use rocket::data::IoStream;
+use rocket::futures::future::BoxFuture;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::response;
+use rocket::response::Responder;
+use rocket::Request;
+use rocket::Response;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::WebSocketStream;
+
+pub const ACCEPT_HEADER: &str = "Sec-WebSocket-Accept";
+
+/// The connection of a WebSocket session, once the upgrade completed.
+pub type Connection = WebSocketStream<IoStream>;
+
+type Session = Box<dyn FnOnce(Connection) -> BoxFuture<'static, ()> + Send>;
+
+#[derive(Debug)]
+pub enum WebSocketError {
+    NotUpgrade,
+    UnsupportedVersion(String),
+    CrossOrigin(String),
+}
+
+/// A request to upgrade to a WebSocket (RFC 6455), rejected with
+/// `426 Upgrade Required` when it is a plain HTTP request and with
+/// `403 Forbidden` when a page of another site makes it.
+#[derive(Debug, Clone)]
+pub struct WebSocket {
+    key: String,
+}
+
+impl WebSocket {
+    /// Accepts the upgrade, running `session` on the connection once the
+    /// response is sent.
+    pub fn channel<F, Fut>(self, session: F) -> Channel
+    where
+        F: FnOnce(Connection) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Channel {
+            accept: derive_accept_key(self.key.as_bytes()),
+            session: Box::new(move |connection| Box::pin(session(connection))),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebSocket {
+    type Error = WebSocketError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = req.headers();
+        let upgrade = headers
+            .get("Upgrade")
+            .any(|value| value.eq_ignore_ascii_case("websocket"));
+        let (true, Some(key)) = (upgrade, headers.get_one("Sec-WebSocket-Key")) else {
+            problem::reject(req, "this endpoint only accepts WebSocket connections");
+            return Outcome::Error((Status::UpgradeRequired, WebSocketError::NotUpgrade));
+        };
+
+        if let Some(origin) = headers.get_one("Origin") {
+            if !same_origin(origin, headers.get_one("Host")) {
+                problem::reject(
+                    req,
+                    "WebSocket connections are only accepted from this site",
+                );
+                return Outcome::Error((
+                    Status::Forbidden,
+                    WebSocketError::CrossOrigin(origin.to_string()),
+                ));
+            }
+        }
+
+        match headers.get_one("Sec-WebSocket-Version") {
+            Some("13") => Outcome::Success(WebSocket {
+                key: key.to_string(),
+            }),
+            version => {
+                let version = version.unwrap_or_default().to_string();
+                problem::reject(
+                    req,
+                    "only version 13 of the WebSocket protocol is supported",
+                );
+                Outcome::Error((
+                    Status::UpgradeRequired,
+                    WebSocketError::UnsupportedVersion(version),
+                ))
+            }
+        }
+    }
+}
+
+/// Whether `origin` is the site the request was sent to. Upgrades are not
+/// subject to CORS, so without this check a page of any site could open a
+/// session with the cookies of its visitor. Clients other than browsers send
+/// no `Origin`.
+fn same_origin(origin: &str, host: Option<&str>) -> bool {
+    let authority = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"));
+    match (authority, host) {
+        (Some(authority), Some(host)) => authority.eq_ignore_ascii_case(host),
+        _ => false,
+    }
+}
+
+/// The response accepting a WebSocket upgrade. Rocket switches protocols
+/// after sending it and hands the connection to the session.
+pub struct Channel {
+    accept: String,
+    session: Session,
+}
+
+#[rocket::async_trait]
+impl IoHandler for Channel {
+    async fn io(self: Pin<Box<Self>>, io: IoStream) -> io::Result<()> {
+        let channel = Pin::into_inner(self);
+        let connection = WebSocketStream::from_raw_socket(io, Role::Server, None).await;
+        (channel.session)(connection).await;
+        Ok(())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Channel {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .raw_header(ACCEPT_HEADER, self.accept.clone())
+            .raw_header("Sec-WebSocket-Version", "13")
+            .upgrade("websocket", self)
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod websocket_tests {
+    use super::*;
+    use rocket::catchers;
+    use rocket::get;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+
+    #[get("/")]
+    fn echo(ws: WebSocket) -> Channel {
+        ws.channel(|_| async {})
+    }
+
+    #[test]
+    fn accepts_upgrades_only() {
+        let rocket = rocket::build()
+            .mount("/", routes![echo])
+            .register("/", catchers![crate::problem::default]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client.get("/").dispatch();
+        assert_eq!(response.status(), Status::UpgradeRequired);
+
+        let response = client
+            .get("/")
+            .header(Header::new("Connection", "Upgrade"))
+            .header(Header::new("Upgrade", "websocket"))
+            .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .header(Header::new("Sec-WebSocket-Version", "8"))
+            .dispatch();
+        assert_eq!(response.status(), Status::UpgradeRequired);
+
+        // The sample handshake of RFC 6455, section 1.3
+        let response = client
+            .get("/")
+            .header(Header::new("Connection", "Upgrade"))
+            .header(Header::new("Upgrade", "websocket"))
+            .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .header(Header::new("Sec-WebSocket-Version", "13"))
+            .dispatch();
+        assert_eq!(
+            response.headers().get_one(ACCEPT_HEADER),
+            Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+        );
+    }
+
+    #[test]
+    fn rejects_upgrades_from_other_sites() {
+        let rocket = rocket::build()
+            .mount("/", routes![echo])
+            .register("/", catchers![crate::problem::default]);
+        let client = Client::tracked(rocket).unwrap();
+        let upgrade = |origin: &'static str| {
+            client
+                .get("/")
+                .header(Header::new("Host", "supplies.example.com"))
+                .header(Header::new("Origin", origin))
+                .header(Header::new("Connection", "Upgrade"))
+                .header(Header::new("Upgrade", "websocket"))
+                .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+                .header(Header::new("Sec-WebSocket-Version", "13"))
+                .dispatch()
+                .status()
+        };
+
+        assert_eq!(upgrade("https://evil.example.com"), Status::Forbidden);
+        assert_eq!(upgrade("null"), Status::Forbidden);
+        assert_ne!(upgrade("https://supplies.example.com"), Status::Forbidden);
+    }
+}
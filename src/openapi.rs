@@ -0,0 +1,309 @@
+use crate::application::attachment::Attachment;
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditEntry;
+use crate::application::health::ComponentHealth;
+use crate::application::health::HealthReport;
+use crate::application::health::HealthStatus;
+use crate::application::mapping_profile::ColumnMapping;
+use crate::application::mapping_profile::FieldTransforms;
+use crate::application::mapping_profile::MappingProfile;
+use crate::application::mapping_profile::SkipRules;
+use crate::application::mapping_profile::SkipWhen;
+use crate::application::mapping_profile::SupplierProfile;
+use crate::application::mapping_profile::ValueTransform;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::import_supplies::CsvFormat;
+use crate::application::supply::import_supplies::ImportReport;
+use crate::application::supply::import_supplies::ImportStatus;
+use crate::application::supply::import_supplies::ImportedRow;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::SupplyOutput;
+use crate::attachments;
+use crate::audit;
+use crate::create_app;
+use crate::domain::supply::price::Price;
+use crate::graphql;
+use crate::health;
+use crate::mapping_profiles;
+use crate::metrics;
+use crate::posts;
+use crate::posts::Post;
+use crate::problem::FieldError;
+use crate::problem::Problem;
+use crate::supplies;
+use crate::supply_events;
+use crate::supply_rooms;
+use rocket::Route;
+use utoipa::openapi::path::ParameterBuilder;
+use utoipa::openapi::path::ParameterIn;
+use utoipa::openapi::schema::ObjectBuilder;
+use utoipa::openapi::schema::Type;
+use utoipa::openapi::security::ApiKey;
+use utoipa::openapi::security::ApiKeyValue;
+use utoipa::openapi::security::HttpAuthScheme;
+use utoipa::openapi::security::HttpBuilder;
+use utoipa::openapi::security::SecurityScheme;
+use utoipa::openapi::RefOr;
+use utoipa::openapi::Required;
+use utoipa::Modify;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Paths that resolve the tenant of the request.
+const TENANT_SCOPED: [&str; 4] = ["/posts", "/supplies", "/admin", "/graphql"];
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_app::index,
+        health::live,
+        health::ready,
+        metrics::metrics,
+        posts::create,
+        posts::create2,
+        posts::find,
+        posts::find_all,
+        supplies::create,
+        supplies::list,
+        supplies::find,
+        supplies::update,
+        supplies::delete,
+        supplies::import,
+        supplies::export,
+        attachments::upload,
+        attachments::list,
+        attachments::download,
+        attachments::derivative,
+        attachments::delete,
+        mapping_profiles::list,
+        mapping_profiles::find,
+        mapping_profiles::save,
+        mapping_profiles::delete,
+        supply_events::events,
+        supply_rooms::join,
+        audit::find,
+        graphql::execute,
+        graphql::graphiql,
+    ),
+    components(schemas(
+        Post,
+        Price,
+        CreateSupplyInput,
+        UpdateSupplyInput,
+        SupplyOutput,
+        ImportStatus,
+        ImportedRow,
+        ImportReport,
+        CsvFormat,
+        ColumnMapping,
+        ValueTransform,
+        FieldTransforms,
+        SkipWhen,
+        SkipRules,
+        MappingProfile,
+        SupplierProfile,
+        Attachment,
+        SupplyEvent,
+        AuditAction,
+        AuditEntry,
+        Problem,
+        FieldError,
+        HealthStatus,
+        ComponentHealth,
+        HealthReport
+    )),
+    modifiers(&Security, &MessagePack),
+    tags(
+        (name = "posts", description = "Blog posts"),
+        (name = "supplies", description = "Supplies and their prices"),
+        (name = "graphql", description = "GraphQL API over supplies, prices and posts"),
+        (name = "audit", description = "Audit trail of mutations"),
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "metrics", description = "Prometheus metrics")
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the API key and bearer token schemes and the tenant header shared
+/// by the tenant scoped paths.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-api-key"))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+
+        let tenant = ParameterBuilder::new()
+            .name("X-Tenant-Id")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Tenant of the request, among the `tenants` of the token, when it is not given by the token or subdomain",
+            ))
+            .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+            .build();
+
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if TENANT_SCOPED.iter().any(|prefix| path.starts_with(prefix)) {
+                item.parameters
+                    .get_or_insert_with(Vec::new)
+                    .push(tenant.clone());
+            }
+        }
+    }
+}
+
+/// Documents `application/msgpack` next to every `application/json` body, as
+/// `Payload` reads and writes either.
+struct MessagePack;
+
+const JSON: &str = "application/json";
+const MSGPACK: &str = "application/msgpack";
+
+impl Modify for MessagePack {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                if let Some(body) = operation.request_body.as_mut() {
+                    if let Some(json) = body.content.get(JSON).cloned() {
+                        body.content.insert(MSGPACK.to_string(), json);
+                    }
+                }
+                for response in operation.responses.responses.values_mut() {
+                    if let RefOr::T(response) = response {
+                        if let Some(json) = response.content.get(JSON).cloned() {
+                            response.content.insert(MSGPACK.to_string(), json);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Serves the specification at `/openapi.json` and Swagger UI at
+/// `/swagger-ui/`.
+pub fn routes() -> Vec<Route> {
+    SwaggerUi::new("/swagger-ui/<_..>")
+        .url("/openapi.json", ApiDoc::openapi())
+        .into()
+}
+
+#[cfg(test)]
+mod openapi_tests {
+    use super::*;
+    use rocket::local::blocking::Client;
+    use std::collections::BTreeSet;
+    use utoipa::openapi::path::PathItem;
+
+    fn normalize(path: &str) -> String {
+        let path = path
+            .split('/')
+            .map(|segment| match segment.strip_prefix('<') {
+                Some(param) => format!("{{{}}}", param.trim_end_matches('>').trim_end_matches('.')),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        }
+    }
+
+    fn methods(item: &PathItem) -> Vec<&'static str> {
+        [
+            ("get", &item.get),
+            ("put", &item.put),
+            ("post", &item.post),
+            ("delete", &item.delete),
+            ("patch", &item.patch),
+            ("head", &item.head),
+            ("options", &item.options),
+        ]
+        .into_iter()
+        .filter(|(_, operation)| operation.is_some())
+        .map(|(method, _)| method)
+        .collect()
+    }
+
+    #[test]
+    fn spec_matches_mounted_routes() {
+        let rocket = create_app::mount_routes(rocket::build());
+        let mounted = rocket
+            .routes()
+            .map(|route| {
+                (
+                    route.method.as_str().to_lowercase(),
+                    normalize(route.uri.path()),
+                )
+            })
+            .collect::<BTreeSet<_>>();
+
+        let documented = ApiDoc::openapi()
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                methods(item)
+                    .into_iter()
+                    .map(|method| (method.to_string(), normalize(path)))
+            })
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(
+            mounted.difference(&documented).collect::<Vec<_>>(),
+            Vec::<&(String, String)>::new(),
+            "mounted routes missing from the OpenAPI document"
+        );
+        assert_eq!(
+            documented.difference(&mounted).collect::<Vec<_>>(),
+            Vec::<&(String, String)>::new(),
+            "documented paths that are not mounted"
+        );
+    }
+
+    #[test]
+    fn serves_spec_and_swagger_ui() {
+        let client = Client::tracked(rocket::build().mount("/", routes())).unwrap();
+
+        let spec = client
+            .get("/openapi.json")
+            .dispatch()
+            .into_json::<serde_json::Value>()
+            .unwrap();
+        assert_eq!(spec["openapi"], "3.1.0");
+        assert!(spec["components"]["schemas"]["Problem"].is_object());
+        assert!(spec["paths"]["/supplies/{id}"]["get"].is_object());
+        let update = &spec["paths"]["/supplies/{id}"]["put"];
+        assert_eq!(
+            update["requestBody"]["content"]["application/msgpack"],
+            update["requestBody"]["content"]["application/json"]
+        );
+        assert!(update["responses"]["200"]["content"]["application/msgpack"].is_object());
+        assert!(update["responses"]["404"]["content"]["application/msgpack"].is_null());
+
+        let ui = client.get("/swagger-ui/").dispatch();
+        assert_eq!(ui.status(), rocket::http::Status::Ok);
+    }
+}
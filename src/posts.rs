@@ -1,16 +1,31 @@
+use crate::application::audit::AuditAction;
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditEntry;
+use crate::domain::identifier::Identifier;
+use crate::domain::validation::error::CustomError;
 use crate::infra::adapters::rabbitmq_adapter::RabbitMqAdapter;
+use crate::infra::db::audit_gateway;
 use crate::infra::db::schema::posts;
+use crate::infra::db::scope_to_tenant;
 use crate::infra::db::DbSqlx;
+use crate::middler::idempotency::Idempotent;
+use crate::middler::payload::Payload;
+use crate::middler::tenant::Tenant;
+use crate::problem::Problem;
 use diesel::prelude::*;
 use rocket::get;
+use rocket::http::Status;
 use rocket::post;
-use rocket::serde::json::Json;
 use rocket_db_pools::Connection;
 use serde::Deserialize;
 use serde::Serialize;
-use std::ops::DerefMut;
+use sqlx::Connection as _;
+use sqlx::PgConnection;
+use tracing::debug;
+use tracing::error;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable)]
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, ToSchema)]
 #[diesel(table_name = posts)]
 #[derive(sqlx::FromRow)]
 pub struct Post {
@@ -18,85 +33,191 @@ pub struct Post {
     pub title: String,
     pub text: String,
     pub published: bool,
+    #[serde(default)]
+    pub tenant_id: String,
 }
 
-type Result<T, E = rocket::response::Debug<sqlx::Error>> = std::result::Result<T, E>;
+type Result<T, E = Problem> = std::result::Result<T, E>;
+
+const AGGREGATE_TYPE: &str = "post";
+
+/// Audits the creation of `post` in the transaction that inserted it.
+async fn audit_created(
+    conn: &mut PgConnection,
+    context: &AuditContext,
+    post: &Post,
+) -> Result<(), CustomError> {
+    let id = post.id.map(|id| id.to_string()).unwrap_or_default();
+    let entry = AuditEntry::new(
+        context,
+        AuditAction::Create,
+        AGGREGATE_TYPE,
+        &id,
+        None,
+        Some(post),
+    );
+    audit_gateway::insert_all(conn, &context.tenant_id, &[entry]).await
+}
 
+#[utoipa::path(
+    tag = "posts",
+    context_path = "/posts",
+    request_body = Post,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries of the request return the first response")),
+    responses(
+        (status = 200, description = "Post created", body = Post),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with another body", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = Problem, content_type = "application/problem+json")
+    )
+)]
 #[post("/create", data = "<post>")]
-pub async fn create(mut db: Connection<DbSqlx>, post: Json<Post>) -> Result<Json<Post>> {
+pub async fn create(
+    mut db: Connection<DbSqlx>,
+    context: AuditContext,
+    post: Idempotent<Post>,
+) -> Result<Payload<Post>> {
+    let mut tx = db.begin().await?;
+    scope_to_tenant(&mut tx, &context.tenant_id).await?;
+
     let post_new = sqlx::query_as::<_, Post>(
         r#"
-        INSERT INTO posts (title, text, published) 
-        VALUES ($1, $2, $3)
-        RETURNING id, title, text, published
+        INSERT INTO posts (tenant_id, title, text, published)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, tenant_id, title, text, published
         "#,
     )
+    .bind(context.tenant_id.get_value())
     .bind(post.title.to_owned())
     .bind(post.text.to_owned())
     .bind(post.published)
-    .fetch_one(&mut **db)
+    .fetch_one(&mut *tx)
     .await?;
 
-    debug!("post_new: {:?}", post_new);
+    audit_created(&mut tx, &context, &post_new).await?;
+    tx.commit().await?;
 
-    Ok(Json(post_new))
+    debug!(post = ?post_new, "post created");
+
+    Ok(Payload(post_new))
 }
 
+#[utoipa::path(
+    tag = "posts",
+    context_path = "/posts",
+    request_body = Post,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries of the request return the first response")),
+    responses(
+        (status = 200, description = "Post created", body = Post),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with another body", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = Problem, content_type = "application/problem+json")
+    )
+)]
 #[post("/create2", data = "<post>")]
-pub async fn create2(mut db: Connection<DbSqlx>, post: Json<Post>) -> Result<Json<Post>> {
+pub async fn create2(
+    mut db: Connection<DbSqlx>,
+    context: AuditContext,
+    post: Idempotent<Post>,
+) -> Result<Payload<Post>> {
+    let mut tx = db.begin().await?;
+    scope_to_tenant(&mut tx, &context.tenant_id).await?;
+
     let post_new = sqlx::query_as!(
         Post,
         r#"
-        INSERT INTO posts (title, text, published) 
-        VALUES ($1, $2, $3)
-        RETURNING id, title, text, published
+        INSERT INTO posts (tenant_id, title, text, published)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, tenant_id, title, text, published
         "#,
+        context.tenant_id.get_value(),
         post.title,
         post.text,
         post.published
     )
-    .fetch_one(&mut **db)
+    .fetch_one(&mut *tx)
     .await?;
 
-    debug!("post_new: {:?}", post_new);
+    audit_created(&mut tx, &context, &post_new).await?;
+    tx.commit().await?;
 
-    Ok(Json(post_new))
+    debug!(post = ?post_new, "post created");
+
+    Ok(Payload(post_new))
 }
 
+#[utoipa::path(
+    tag = "posts",
+    context_path = "/posts",
+    params(("id" = i64, Path, description = "Post id")),
+    responses(
+        (status = 200, description = "The post, or null when it does not exist", body = Option<Post>),
+        (status = 400, description = "Invalid id or missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
 #[get("/<id>")]
-pub async fn find(id: &str, mut db: Connection<DbSqlx>) -> Result<Json<Option<Post>>> {
+pub async fn find(
+    id: &str,
+    tenant: Tenant,
+    mut db: Connection<DbSqlx>,
+) -> Result<Payload<Option<Post>>> {
+    let id = id
+        .parse::<i64>()
+        .map_err(|_| Problem::new(Status::BadRequest, "'id' must be an integer"))?;
+
+    let mut tx = db.begin().await?;
+    scope_to_tenant(&mut tx, &tenant.0).await?;
+
     let find_post = sqlx::query_as!(
         Post,
         r#"
-        SELECT id, title, text, published
+        SELECT id, tenant_id, title, text, published
         FROM posts
-        WHERE id = $1
+        WHERE id = $1 AND tenant_id = $2
         "#,
-        id.parse::<i64>().unwrap()
+        id,
+        tenant.0.get_value()
     )
-    .fetch_optional(&mut **db)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    debug!("find_post: {:?}", find_post);
+    tx.commit().await?;
 
-    Ok(Json(find_post))
+    debug!(post = ?find_post, "post found");
+
+    Ok(Payload(find_post))
 }
 
+#[utoipa::path(
+    tag = "posts",
+    context_path = "/posts",
+    responses(
+        (status = 200, description = "Posts of the tenant", body = Vec<Post>),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
 #[get("/find_all")]
-pub async fn find_all(mut db: Connection<DbSqlx>) -> Result<Json<Vec<Post>>> {
-    let r = db.deref_mut().deref_mut();
+pub async fn find_all(tenant: Tenant, mut db: Connection<DbSqlx>) -> Result<Payload<Vec<Post>>> {
+    let mut tx = db.begin().await?;
+    scope_to_tenant(&mut tx, &tenant.0).await?;
 
     let find_post = sqlx::query_as!(
         Post,
         r#"
-        SELECT *
+        SELECT id, tenant_id, title, text, published
         FROM posts
+        WHERE tenant_id = $1
         "#,
+        tenant.0.get_value()
     )
-    .fetch_all(r)
+    .fetch_all(&mut *tx)
     .await?;
 
-    debug!("find_post: {:?}", find_post);
+    tx.commit().await?;
+
+    debug!(posts = ?find_post, "posts found");
 
     let rabbitmq = RabbitMqAdapter::new().await;
 
@@ -106,10 +227,10 @@ pub async fn find_all(mut db: Connection<DbSqlx>) -> Result<Json<Vec<Post>>> {
             .publish(data.to_vec().as_ref(), "stream")
             .await
             .map_err(|e| {
-                error!("publish find: {:?}", e);
+                error!(error = ?e, "failed to publish posts");
                 e
             });
     }
 
-    Ok(Json(find_post))
+    Ok(Payload(find_post))
 }
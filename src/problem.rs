@@ -0,0 +1,383 @@
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n::Locale;
+use crate::i18n::Message;
+use crate::middler::locale;
+use crate::middler::request_id::RequestId;
+use rocket::catch;
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::response;
+use rocket::response::Responder;
+use rocket::serde::json::Json;
+use rocket::Request;
+use rocket::Response;
+use serde::Serialize;
+use tracing::error;
+use utoipa::ToSchema;
+
+pub const PROBLEM_TYPE_BASE: &str = "/problems/";
+
+/// An RFC 7807 `application/problem+json` body. `instance` and `request_id`
+/// are filled from the request when the problem is responded, and problems
+/// made of errors are described in its locale.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// The errors the problem was made of, to describe them again in
+    /// another locale.
+    #[serde(skip)]
+    pub causes: Vec<CustomError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl FieldError {
+    /// `error` described in `locale`, with the field it is about.
+    pub fn new(error: &CustomError, locale: Locale) -> Self {
+        Self {
+            field: error.field(),
+            message: error.render(locale),
+        }
+    }
+}
+
+impl Problem {
+    /// A problem without a specific type, described by its status alone.
+    pub fn new(status: Status, detail: &str) -> Self {
+        Self {
+            type_uri: "about:blank".to_string(),
+            title: status.reason_lossy().to_string(),
+            status: status.code,
+            detail: detail.to_string(),
+            instance: None,
+            request_id: None,
+            errors: Vec::new(),
+            causes: Vec::new(),
+        }
+    }
+
+    pub fn with_type(status: Status, type_name: &str, title: &str, detail: &str) -> Self {
+        Self {
+            type_uri: format!("{}{}", PROBLEM_TYPE_BASE, type_name),
+            title: title.to_string(),
+            ..Self::new(status, detail)
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// The validation problem of `errors`, described in `locale`.
+    fn validation(errors: Vec<CustomError>, locale: Locale) -> Self {
+        let fields = errors
+            .iter()
+            .map(|error| FieldError::new(error, locale))
+            .collect::<Vec<_>>();
+        let detail = Message::new("problem-validation-detail").arg("count", fields.len());
+        Problem {
+            causes: errors,
+            ..Problem::with_type(
+                Status::UnprocessableEntity,
+                "validation-error",
+                &locale.text("problem-validation-title"),
+                &detail.render(locale),
+            )
+            .with_errors(fields)
+        }
+    }
+
+    /// The problem of a failed use case, described in `locale`.
+    fn of(errors: Vec<CustomError>, locale: Locale) -> Self {
+        let problem = match errors.first() {
+            Some(CustomError::NotFound(message)) => Problem::with_type(
+                Status::NotFound,
+                "not-found",
+                &locale.text("problem-not-found-title"),
+                &message.render(locale),
+            ),
+            Some(CustomError::RepositoryError(message)) => {
+                return Problem::internal("repository-error", "Repository error", message)
+            }
+            Some(CustomError::VersionConflict(message)) => Problem::with_type(
+                Status::PreconditionFailed,
+                "version-conflict",
+                &locale.text("problem-version-conflict-title"),
+                &message.render(locale),
+            ),
+            Some(CustomError::ApiError(message)) => {
+                return Problem::with_type(
+                    Status::BadGateway,
+                    "upstream-error",
+                    "Upstream service error",
+                    message,
+                )
+            }
+            _ => return Problem::validation(errors, locale),
+        };
+        Problem {
+            causes: errors,
+            ..problem
+        }
+    }
+
+    /// The problem described in `locale`. Problems not made of errors, and
+    /// internal ones, keep their English detail.
+    pub fn localize(self, locale: Locale) -> Self {
+        if self.causes.is_empty() {
+            return self;
+        }
+        Problem {
+            instance: self.instance,
+            request_id: self.request_id,
+            ..Problem::of(self.causes, locale)
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        Status::from_code(self.status).unwrap_or(Status::InternalServerError)
+    }
+
+    fn internal(type_name: &str, title: &str, cause: &str) -> Self {
+        error!("{}: {}", title, cause);
+        Self::with_type(
+            Status::InternalServerError,
+            type_name,
+            title,
+            "the server failed to complete the request",
+        )
+    }
+}
+
+impl From<Notification> for Problem {
+    fn from(notification: Notification) -> Self {
+        Problem::of(notification.errors, Locale::En)
+    }
+}
+
+impl From<CustomError> for Problem {
+    fn from(error: CustomError) -> Self {
+        Problem::from(Notification::with_one_error(error))
+    }
+}
+
+impl From<sqlx::Error> for Problem {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => Problem::with_type(
+                Status::NotFound,
+                "not-found",
+                "Resource not found",
+                "the requested resource was not found",
+            ),
+            e => Problem::internal("database-error", "Database error", &e.to_string()),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Problem {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut problem = self.localize(locale::detect(req));
+        let status = problem.status();
+        problem.instance = Some(req.uri().path().to_string());
+        problem.request_id = Some(RequestId::of(req).as_str().to_string());
+
+        Response::build_from(Json(problem).respond_to(req)?)
+            .status(status)
+            .header(problem_json())
+            .ok()
+    }
+}
+
+pub fn problem_json() -> ContentType {
+    ContentType::new("application", "problem+json")
+}
+
+/// Detail message a request guard leaves for the catcher of its failure.
+#[derive(Debug, Clone, Default)]
+pub struct RejectionDetail(pub Option<String>);
+
+pub fn reject(req: &Request<'_>, detail: &str) {
+    req.local_cache(|| RejectionDetail(Some(detail.to_string())));
+}
+
+pub(crate) fn catcher_detail(status: Status, req: &Request) -> String {
+    if let RejectionDetail(Some(detail)) = req.local_cache(RejectionDetail::default) {
+        return detail.to_owned();
+    }
+    match status.code {
+        404 => format!("I couldn't find '{}'. Try something else?", req.uri()),
+        500 => "internal server error".to_string(),
+        _ => format!("{} ({})", status, req.uri()),
+    }
+}
+
+#[catch(500)]
+pub fn internal_error(req: &Request) -> Problem {
+    Problem::new(
+        Status::InternalServerError,
+        &catcher_detail(Status::InternalServerError, req),
+    )
+}
+
+#[catch(404)]
+pub fn not_found(req: &Request) -> Problem {
+    Problem::new(Status::NotFound, &catcher_detail(Status::NotFound, req))
+}
+
+#[catch(default)]
+pub fn default(status: Status, req: &Request) -> Problem {
+    Problem::new(status, &catcher_detail(status, req))
+}
+
+#[cfg(test)]
+mod problem_tests {
+    use super::*;
+    use rocket::catchers;
+    use rocket::get;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+    use serde_json::Value;
+
+    #[test]
+    fn maps_validation_notification_with_field_errors() {
+        let problem = Problem::from(Notification::with_errors(vec![
+            CustomError::Error("'name' should not be empty".to_string()),
+            CustomError::Error("something is off".to_string()),
+        ]));
+
+        assert_eq!(problem.status, 422);
+        assert_eq!(problem.type_uri, "/problems/validation-error");
+        assert_eq!(problem.errors[0].field.as_deref(), Some("name"));
+        assert_eq!(problem.errors[1].field, None);
+    }
+
+    #[test]
+    fn takes_the_field_of_validation_messages_from_the_message() {
+        let about =
+            |message: Message| FieldError::new(&CustomError::Validation(message), Locale::PtBr);
+
+        let error = about(Message::new("price-empty").for_field("prices"));
+        assert_eq!(error.field.as_deref(), Some("prices"));
+        assert_eq!(error.message, "'price' não deve ficar vazio");
+        assert_eq!(about(Message::new("price-empty")).field, None);
+    }
+
+    #[test]
+    fn maps_not_found_repository_and_database_errors() {
+        let not_found = Problem::from(CustomError::NotFound(
+            Message::new("supply-not-found").arg("id", "s1"),
+        ));
+        assert_eq!(not_found.status, 404);
+        assert_eq!(not_found.detail, "supply with id 's1' was not found");
+        let localized = not_found.localize(Locale::PtBr);
+        assert_eq!(localized.title, "Recurso não encontrado");
+        assert_eq!(localized.detail, "insumo com id 's1' não foi encontrado");
+
+        let repository = Problem::from(CustomError::RepositoryError("secret dsn".to_string()));
+        assert_eq!(repository.status, 500);
+        assert!(!repository.detail.contains("secret"));
+
+        let conflict = Problem::from(CustomError::VersionConflict(
+            Message::new("supplies-changed").arg("count", 2usize),
+        ));
+        assert_eq!(conflict.status, 412);
+        assert_eq!(conflict.type_uri, "/problems/version-conflict");
+
+        assert_eq!(Problem::from(sqlx::Error::RowNotFound).status, 404);
+        assert_eq!(Problem::from(sqlx::Error::PoolTimedOut).status, 500);
+    }
+
+    #[get("/fail")]
+    fn fail() -> Problem {
+        Problem::from(CustomError::Error("'id' is bad".to_string()))
+    }
+
+    #[get("/invalid")]
+    fn invalid() -> Problem {
+        Problem::from(CustomError::Validation(
+            Message::new("name-length")
+                .arg("min", 1usize)
+                .arg("max", 255usize)
+                .for_field("name"),
+        ))
+    }
+
+    #[test]
+    fn responds_problem_json_from_handlers_and_catchers() {
+        let rocket = rocket::build()
+            .register("/", catchers![internal_error, not_found, default])
+            .mount("/", routes![fail, invalid]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client
+            .get("/fail")
+            .header(Header::new("X-Request-Id", "abc"))
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.content_type(), Some(problem_json()));
+        let body = response.into_json::<Value>().unwrap();
+        assert_eq!(body["request_id"], "abc");
+        assert_eq!(body["instance"], "/fail");
+        assert_eq!(body["errors"][0]["field"], "id");
+
+        let response = client.get("/missing").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(problem_json()));
+        let body = response.into_json::<Value>().unwrap();
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["title"], "Not Found");
+        assert_eq!(body["status"], 404);
+    }
+
+    #[test]
+    fn describes_validation_problems_in_the_locale_of_the_request() {
+        let rocket = rocket::build().mount("/", routes![invalid]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let body = client
+            .get("/invalid")
+            .dispatch()
+            .into_json::<Value>()
+            .unwrap();
+        assert_eq!(body["title"], "Validation failed");
+        assert_eq!(body["detail"], "the request has 1 invalid field(s)");
+        assert_eq!(
+            body["errors"][0]["message"],
+            "'name' must be between 1 and 255 characters"
+        );
+
+        let body = client
+            .get("/invalid")
+            .header(Header::new("Accept-Language", "pt-BR,pt;q=0.9,en;q=0.8"))
+            .dispatch()
+            .into_json::<Value>()
+            .unwrap();
+        assert_eq!(body["title"], "Falha na validação");
+        assert_eq!(body["detail"], "a requisição tem 1 campo inválido");
+        assert_eq!(body["errors"][0]["field"], "name");
+        assert_eq!(
+            body["errors"][0]["message"],
+            "'name' deve ter entre 1 e 255 caracteres"
+        );
+        assert!(body.get("causes").is_none());
+    }
+}
@@ -0,0 +1,396 @@
+use crate::application::audit::AuditContext;
+use crate::application::audit::AuditGateway;
+use crate::application::mapping_profile::get_mapping_profile::GetMappingProfileUseCase;
+use crate::application::mapping_profile::MappingProfile;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::create_supply::CreateSupplyUseCase;
+use crate::application::supply::delete_supply::DeleteSupplyUseCase;
+use crate::application::supply::export_supplies::ExportFilter;
+use crate::application::supply::export_supplies::ExportFormat;
+use crate::application::supply::export_supplies::ExportOptions;
+use crate::application::supply::export_supplies::ExportSuppliesUseCase;
+use crate::application::supply::get_supply::GetSupplyUseCase;
+use crate::application::supply::import_supplies::ImportReport;
+use crate::application::supply::import_supplies::ImportSuppliesUseCase;
+use crate::application::supply::list_supplies::ListSuppliesUseCase;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::update_supply::UpdateSupplyUseCase;
+use crate::application::supply::SupplyOutput;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::tenant_id::TenantId;
+use crate::domain::validation::error::CustomError;
+use crate::i18n::Locale;
+use crate::infra::db::audit_gateway::AuditPostgresGateway;
+use crate::infra::db::supply_gateway::SupplyPostgresGateway;
+use crate::infra::db::DbSqlx;
+use crate::mapping_profiles::mapping_profile_gateway;
+use crate::middler::idempotency::Idempotent;
+use crate::middler::payload::Payload;
+use crate::middler::preconditions::etag;
+use crate::middler::preconditions::IfMatch;
+use crate::middler::preconditions::IfNoneMatch;
+use crate::middler::preconditions::Tagged;
+use crate::middler::tenant::Tenant;
+use crate::problem::Problem;
+use crate::supply_events::SupplyEventStream;
+use rocket::delete;
+use rocket::fs::TempFile;
+use rocket::futures::stream::BoxStream;
+use rocket::futures::StreamExt;
+use rocket::get;
+use rocket::http::ContentType;
+use rocket::http::Header;
+use rocket::http::Status;
+use rocket::post;
+use rocket::put;
+use rocket::response;
+use rocket::response::status::Created;
+use rocket::response::status::NoContent;
+use rocket::response::stream::ByteStream;
+use rocket::response::Responder;
+use rocket::tokio::fs::File;
+use rocket::tokio::io::AsyncReadExt;
+use rocket::Request;
+use rocket::Response;
+use rocket::State;
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+
+type Result<T, E = Problem> = std::result::Result<T, E>;
+
+pub(crate) fn supply_gateway(db: &DbSqlx, tenant_id: &TenantId) -> Arc<dyn SupplyGateway> {
+    Arc::new(SupplyPostgresGateway::new(db.pool(), tenant_id.to_owned()))
+}
+
+pub(crate) fn audit_gateway(db: &DbSqlx, tenant_id: &TenantId) -> Arc<dyn AuditGateway> {
+    Arc::new(AuditPostgresGateway::new(db.pool(), tenant_id.to_owned()))
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    request_body = CreateSupplyInput,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries of the request return the first response")),
+    responses(
+        (status = 201, description = "Supply created", body = SupplyOutput,
+            headers(("ETag" = String, description = "Version of the supply"))),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Validation failed, or Idempotency-Key reused with another body", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[post("/", data = "<input>")]
+pub async fn create(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    context: AuditContext,
+    input: Idempotent<CreateSupplyInput>,
+) -> Result<Tagged<Created<Payload<SupplyOutput>>>> {
+    let tenant_id = &context.tenant_id;
+    let output = CreateSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(&context, input.into_inner())
+        .await
+        .map_err(Problem::from)?;
+
+    Ok(Tagged::new(
+        etag(output.version),
+        Created::new(format!("/supplies/{}", output.id)).body(Payload(output)),
+    ))
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    responses(
+        (status = 200, description = "Supplies of the tenant", body = Vec<SupplyOutput>),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/")]
+pub async fn list(db: &State<DbSqlx>, tenant: Tenant) -> Result<Payload<Vec<SupplyOutput>>> {
+    ListSuppliesUseCase::new(supply_gateway(db, &tenant.0))
+        .execute()
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag the client already holds")
+    ),
+    responses(
+        (status = 200, description = "The supply", body = SupplyOutput,
+            headers(("ETag" = String, description = "Version of the supply"))),
+        (status = 304, description = "The supply still has the ETag sent in If-None-Match"),
+        (status = 404, description = "Supply not found", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/<id>")]
+pub async fn find(
+    db: &State<DbSqlx>,
+    tenant: Tenant,
+    id: &str,
+    if_none_match: IfNoneMatch,
+) -> Result<Tagged<Payload<SupplyOutput>>> {
+    GetSupplyUseCase::new(supply_gateway(db, &tenant.0))
+        .execute(id)
+        .await
+        .map(|output| Tagged::unless_cached(etag(output.version), Payload(output), &if_none_match))
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("id" = String, Path, description = "Supply id"),
+        ("If-Match" = String, Header, description = "ETag of the version being updated, or `*`")
+    ),
+    request_body = UpdateSupplyInput,
+    responses(
+        (status = 200, description = "Supply updated", body = SupplyOutput,
+            headers(("ETag" = String, description = "New version of the supply"))),
+        (status = 404, description = "Supply not found", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The supply changed since it was read", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "Missing If-Match header", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Validation failed", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[put("/<id>", data = "<input>")]
+pub async fn update(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    context: AuditContext,
+    id: &str,
+    if_match: IfMatch,
+    input: Payload<UpdateSupplyInput>,
+) -> Result<Tagged<Payload<SupplyOutput>>> {
+    let tenant_id = &context.tenant_id;
+    UpdateSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(&context, id, if_match.0, input.into_inner())
+        .await
+        .map(|output| Tagged::new(etag(output.version), Payload(output)))
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(("id" = String, Path, description = "Supply id")),
+    responses(
+        (status = 204, description = "Supply deleted"),
+        (status = 404, description = "Supply not found", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    context: AuditContext,
+    id: &str,
+) -> Result<NoContent> {
+    let tenant_id = &context.tenant_id;
+    DeleteSupplyUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(&context, id)
+        .await
+        .map(|_| NoContent)
+        .map_err(Problem::from)
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("profile" = Option<String>, Query, description = "Supplier whose mapping profile reads the file"),
+        ("delimiter" = Option<String>, Query, description = "Column delimiter, overrides the one of the profile"),
+        ("decimal_comma" = Option<bool>, Query, description = "Prices are written as `1.234,56`, overrides the profile"),
+        ("dry_run" = Option<bool>, Query, description = "Reports what would happen without saving anything")
+    ),
+    request_body(content = String, content_type = "text/csv",
+        description = "A header with the columns of the profile, `name`, `unit`, `price` and optionally `id` by default, then one price per row"),
+    responses(
+        (status = 200, description = "What happened, or would happen, to each row", body = ImportReport),
+        (status = 404, description = "No mapping profile for the supplier", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "A supply changed during the import, nothing was saved", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The file or its format are invalid", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+#[post(
+    "/import?<profile>&<delimiter>&<decimal_comma>&<dry_run>",
+    data = "<file>"
+)]
+pub async fn import(
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    context: AuditContext,
+    profile: Option<&str>,
+    delimiter: Option<String>,
+    decimal_comma: Option<bool>,
+    dry_run: Option<bool>,
+    locale: Locale,
+    file: TempFile<'_>,
+) -> Result<Payload<ImportReport>> {
+    let mut csv = Vec::new();
+    let read = match file.open().await {
+        Ok(mut reader) => reader.read_to_end(&mut csv).await,
+        Err(e) => Err(e),
+    };
+    read.map_err(|e| Problem::new(Status::BadRequest, &format!("unreadable file: {}", e)))?;
+
+    let tenant_id = &context.tenant_id;
+    let mut mapping = match profile {
+        Some(supplier) => {
+            GetMappingProfileUseCase::new(mapping_profile_gateway(db, tenant_id))
+                .execute(supplier)
+                .await
+                .map_err(Problem::from)?
+                .profile
+        }
+        None => MappingProfile::default(),
+    };
+    if let Some(delimiter) = delimiter {
+        mapping.format.delimiter = delimiter;
+    }
+    if let Some(decimal_comma) = decimal_comma {
+        mapping.format.decimal_comma = decimal_comma;
+    }
+
+    ImportSuppliesUseCase::new(supply_gateway(db, tenant_id), events.publisher())
+        .execute(
+            &context,
+            &csv,
+            &mapping,
+            dry_run.unwrap_or_default(),
+            locale,
+        )
+        .await
+        .map(Payload)
+        .map_err(Problem::from)
+}
+
+/// An exported file, sent as an attachment.
+pub enum Export {
+    Stream(
+        ByteStream<BoxStream<'static, Vec<u8>>>,
+        ContentType,
+        Header<'static>,
+    ),
+    File(File, ContentType, Header<'static>),
+}
+
+impl<'r> Responder<'r, 'r> for Export {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        let (body, content_type, disposition) = match self {
+            Export::Stream(chunks, content_type, disposition) => {
+                (chunks.respond_to(req)?, content_type, disposition)
+            }
+            Export::File(file, content_type, disposition) => {
+                (file.respond_to(req)?, content_type, disposition)
+            }
+        };
+        Response::build_from(body)
+            .header(content_type)
+            .header(disposition)
+            .ok()
+    }
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("format" = Option<String>, Query, description = "`csv` (default), `ndjson` or `xlsx`"),
+        ("columns" = Option<String>, Query, description = "Comma separated columns among id, name, unit, price, version, created_at and updated_at; all by default"),
+        ("locale" = Option<String>, Query, description = "`en` (default) or `pt-BR`: how CSV numbers are written and delimited"),
+        ("name" = Option<String>, Query, description = "Only supplies whose name contains this"),
+        ("unit" = Option<String>, Query, description = "Only prices of this unit")
+    ),
+    responses(
+        (status = 200, description = "A row per price of the supplies. CSV and NDJSON are streamed as the \
+            supplies are read, and end with a `# export failed: ...` line or an `{\"error\": ...}` object when \
+            reading fails partway; an XLSX workbook is sent once it is complete",
+            content(("text/csv"), ("application/x-ndjson"), ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"))),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid format, columns or locale", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+#[get("/export?<format>&<columns>&<locale>&<name>&<unit>")]
+pub async fn export(
+    db: &State<DbSqlx>,
+    tenant: Tenant,
+    format: Option<&str>,
+    columns: Option<&str>,
+    locale: Option<&str>,
+    name: Option<String>,
+    unit: Option<String>,
+    language: Locale,
+) -> Result<Export> {
+    let filter = ExportFilter { name, unit };
+    let options = ExportOptions::parse(format, columns, locale, filter).map_err(Problem::from)?;
+    let use_case = ExportSuppliesUseCase::new(supply_gateway(db, &tenant.0));
+    let disposition = Header::new(
+        "Content-Disposition",
+        format!(
+            "attachment; filename=\"supplies.{}\"",
+            options.format.extension()
+        ),
+    );
+
+    match options.format {
+        // Not streamed: the workbook is written to a temporary file first,
+        // since an XLSX file is a zip archive that is only valid once complete
+        ExportFormat::Xlsx => {
+            let path = std::env::temp_dir().join(format!("supplies-{}.xlsx", Uuid::new_v4()));
+            let written = use_case.write_xlsx(&options, &path).await;
+            let file = match written {
+                Ok(()) => File::open(&path).await.map_err(|e| e.to_string()),
+                Err(notification) => Err(notification.format_errors_in(language).join(", ")),
+            };
+            // The open file stays readable once its name is gone
+            let _ = rocket::tokio::fs::remove_file(&path).await;
+            let file = file.map_err(|e| Problem::from(CustomError::RepositoryError(e)))?;
+            Ok(Export::File(file, xlsx_content_type(), disposition))
+        }
+        format => {
+            let content_type = match format {
+                ExportFormat::Csv => ContentType::CSV,
+                _ => ContentType::new("application", "x-ndjson"),
+            };
+            // The status is sent by then, so a failure is told in the file
+            let chunks = use_case
+                .stream(options)
+                .scan(false, move |failed, chunk| {
+                    let chunk = match chunk {
+                        _ if *failed => None,
+                        Ok(chunk) => Some(chunk),
+                        Err(notification) => {
+                            error!(errors = ?notification.format_errors(), "supply export stopped");
+                            *failed = true;
+                            Some(format.failure_line(&notification, language))
+                        }
+                    };
+                    std::future::ready(chunk)
+                })
+                .boxed();
+            Ok(Export::Stream(
+                ByteStream(chunks),
+                content_type,
+                disposition,
+            ))
+        }
+    }
+}
+
+fn xlsx_content_type() -> ContentType {
+    ContentType::new(
+        "application",
+        "vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    )
+}
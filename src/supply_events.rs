@@ -0,0 +1,333 @@
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventFilter;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::domain::identifier::Identifier;
+use crate::domain::validation::error::CustomError;
+use crate::infra::adapters::rabbitmq_adapter::Offset;
+use crate::infra::adapters::supply_event_publisher::RabbitMqSupplyEventPublisher;
+use crate::middler::tenant::Tenant;
+use crate::problem;
+use crate::problem::Problem;
+use rabbitmq_stream_client::ConsumerHandle;
+use rocket::fairing::Fairing;
+use rocket::fairing::Info;
+use rocket::fairing::Kind;
+use rocket::get;
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::request::Outcome;
+use rocket::response::stream::Event;
+use rocket::response::stream::EventStream;
+use rocket::tokio;
+use rocket::tokio::sync::mpsc;
+use rocket::tokio::sync::mpsc::error::TrySendError;
+use rocket::Build;
+use rocket::Request;
+use rocket::Rocket;
+use rocket::State;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::error;
+use tracing::warn;
+
+/*
+[default.supply_events]
+stream = "supplies"
+*/
+
+/// Events a subscriber may have waiting before it is disconnected.
+const BUFFERED_EVENTS: usize = 64;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SupplyEventsConfig {
+    #[serde(default = "default_stream")]
+    pub stream: String,
+}
+
+fn default_stream() -> String {
+    "supplies".to_string()
+}
+
+impl Default for SupplyEventsConfig {
+    fn default() -> Self {
+        Self {
+            stream: default_stream(),
+        }
+    }
+}
+
+/// The RabbitMQ stream supply events are published to and read from.
+#[derive(Clone)]
+pub struct SupplyEventStream(Arc<RabbitMqSupplyEventPublisher>);
+
+impl SupplyEventStream {
+    pub fn new(stream: &str) -> Self {
+        Self(Arc::new(RabbitMqSupplyEventPublisher::new(stream)))
+    }
+
+    pub fn publisher(&self) -> Arc<dyn SupplyEventPublisher> {
+        self.0.clone()
+    }
+
+    /// Subscribes to the events matching `filter` from `offset` on.
+    pub async fn subscribe(
+        &self,
+        filter: SupplyEventFilter,
+        offset: Offset,
+    ) -> Result<Subscription, CustomError> {
+        let (sender, receiver) = mpsc::channel::<(u64, SupplyEvent)>(BUFFERED_EVENTS);
+        let sender = Arc::new(Mutex::new(Some(sender)));
+        let handle = self
+            .0
+            .adapter()
+            .await?
+            .try_consumer(self.0.stream(), Some(offset), move |delivery| {
+                let sender = sender.clone();
+                let filter = filter.clone();
+                async move {
+                    let event = delivery
+                        .message()
+                        .data()
+                        .and_then(|data| serde_json::from_slice::<SupplyEvent>(data).ok());
+                    if let Some(event) = event.filter(|event| filter.matches(event)) {
+                        forward(&sender, delivery.offset(), event);
+                    }
+                }
+            })
+            .await
+            .map_err(|e| CustomError::ApiError(e.to_string()))?;
+
+        Ok(Subscription {
+            handle: Some(handle),
+            receiver,
+        })
+    }
+}
+
+/// Hands an event to its subscriber without waiting, so a slow client never
+/// stalls the consumer. A subscriber that falls `BUFFERED_EVENTS` behind is
+/// disconnected instead; its `EventSource` reconnects with `Last-Event-ID`
+/// and resumes from the stream.
+fn forward(
+    sender: &Mutex<Option<mpsc::Sender<(u64, SupplyEvent)>>>,
+    offset: u64,
+    event: SupplyEvent,
+) {
+    let mut slot = sender.lock().unwrap();
+    let Some(subscriber) = slot.as_ref() else {
+        return;
+    };
+    match subscriber.try_send((offset, event)) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            warn!(
+                offset,
+                "supply events subscriber is lagging, disconnecting it"
+            );
+            *slot = None;
+        }
+        Err(TrySendError::Closed(_)) => *slot = None,
+    }
+}
+
+/// Manages the `SupplyEventStream` the supply routes publish to.
+pub struct SupplyEvents;
+
+#[rocket::async_trait]
+impl Fairing for SupplyEvents {
+    fn info(&self) -> Info {
+        Info {
+            name: "Supply events",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let config = match rocket
+            .figment()
+            .extract_inner::<SupplyEventsConfig>("supply_events")
+        {
+            Ok(config) => config,
+            Err(e) if e.missing() => SupplyEventsConfig::default(),
+            Err(e) => {
+                error!("invalid supply_events config: {}", e);
+                return Err(rocket);
+            }
+        };
+
+        Ok(rocket.manage(SupplyEventStream::new(&config.stream)))
+    }
+}
+
+/// The `Last-Event-ID` header of a reconnecting `EventSource`: the stream
+/// offset of the last event it received.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LastEventId(pub Option<u64>);
+
+impl LastEventId {
+    /// Where to resume: right after the last event received, or at the next
+    /// event published for a new subscriber.
+    pub fn offset(&self) -> Offset {
+        match self.0 {
+            Some(offset) => Offset::Offset(offset + 1),
+            None => Offset::Next,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(value) = req.headers().get_one("Last-Event-ID") else {
+            return Outcome::Success(LastEventId(None));
+        };
+        match value.trim().parse::<u64>() {
+            Ok(offset) => Outcome::Success(LastEventId(Some(offset))),
+            Err(_) => {
+                let detail = format!("'{}' is not an event id", value);
+                problem::reject(req, &detail);
+                Outcome::Error((Status::BadRequest, detail))
+            }
+        }
+    }
+}
+
+/// The events of a filter, read from the stream by a dedicated consumer
+/// that is closed once the subscription is dropped.
+pub struct Subscription {
+    handle: Option<ConsumerHandle>,
+    receiver: mpsc::Receiver<(u64, SupplyEvent)>,
+}
+
+impl Subscription {
+    /// The next event and its stream offset.
+    pub async fn next(&mut self) -> Option<(u64, SupplyEvent)> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            tokio::spawn(async move {
+                if let Err(e) = handle.close().await {
+                    error!(error = ?e, "failed to close supply events consumer");
+                }
+            });
+        }
+    }
+}
+
+fn unavailable(detail: &str) -> Problem {
+    Problem::with_type(
+        Status::ServiceUnavailable,
+        "event-stream-unavailable",
+        "Event stream unavailable",
+        detail,
+    )
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(
+        ("supply_id" = Option<String>, Query, description = "Only events of this supply"),
+        ("unit" = Option<String>, Query, description = "Only events pricing this unit"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Id of the last event received, to resume after it")
+    ),
+    responses(
+        (status = 200, description = "Stream of supply events; each event is named after its type and its id is the stream offset",
+            body = SupplyEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Missing tenant or malformed Last-Event-ID", body = Problem, content_type = "application/problem+json"),
+        (status = 503, description = "The event stream cannot be reached", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/events?<supply_id>&<unit>")]
+pub async fn events(
+    stream: &State<SupplyEventStream>,
+    tenant: Tenant,
+    last_event_id: LastEventId,
+    supply_id: Option<String>,
+    unit: Option<String>,
+) -> Result<EventStream![], Problem> {
+    let filter = SupplyEventFilter {
+        tenant_id: tenant.0.get_value().to_string(),
+        supply_id,
+        unit,
+    };
+    let mut subscription = stream
+        .subscribe(filter, last_event_id.offset())
+        .await
+        .map_err(|e| unavailable(&e.to_string()))?;
+
+    Ok(EventStream! {
+        while let Some((offset, event)) = subscription.next().await {
+            yield Event::json(&event).id(offset.to_string()).event(event.name());
+        }
+    })
+}
+
+#[cfg(test)]
+mod supply_events_tests {
+    use super::*;
+    use rocket::catchers;
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+
+    #[get("/")]
+    fn resume(last_event_id: LastEventId) -> String {
+        match last_event_id.offset() {
+            Offset::Offset(offset) => offset.to_string(),
+            _ => "next".to_string(),
+        }
+    }
+
+    #[test]
+    fn resumes_after_last_event_id() {
+        let rocket = rocket::build()
+            .mount("/", routes![resume])
+            .register("/", catchers![crate::problem::default]);
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client.get("/").dispatch();
+        assert_eq!(response.into_string().unwrap(), "next");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Last-Event-ID", "41"))
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), "42");
+
+        let response = client
+            .get("/")
+            .header(Header::new("Last-Event-ID", "abc"))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        let problem = response.into_json::<serde_json::Value>().unwrap();
+        assert!(problem["detail"].as_str().unwrap().contains("abc"));
+    }
+
+    #[rocket::async_test]
+    async fn disconnects_a_lagging_subscriber() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let sender = Mutex::new(Some(sender));
+        let event = || SupplyEvent::SupplyDeleted {
+            supply_id: "1".to_string(),
+            tenant_id: "acme".to_string(),
+            name: "cimento".to_string(),
+            prices: vec![],
+            occurred_at: chrono::Utc::now(),
+        };
+
+        forward(&sender, 1, event());
+        forward(&sender, 2, event());
+        forward(&sender, 3, event());
+
+        assert_eq!(receiver.recv().await.map(|(offset, _)| offset), Some(1));
+        assert!(receiver.recv().await.is_none());
+    }
+}
@@ -0,0 +1,785 @@
+use crate::application::audit::AuditContext;
+use crate::application::supply::create_supply::CreateSupplyInput;
+use crate::application::supply::create_supply::CreateSupplyUseCase;
+use crate::application::supply::delete_supply::DeleteSupplyUseCase;
+use crate::application::supply::events::SupplyEvent;
+use crate::application::supply::events::SupplyEventFilter;
+use crate::application::supply::events::SupplyEventPublisher;
+use crate::application::supply::get_supply::GetSupplyUseCase;
+use crate::application::supply::list_supplies::ListSuppliesUseCase;
+use crate::application::supply::update_supply::UpdateSupplyInput;
+use crate::application::supply::update_supply::UpdateSupplyUseCase;
+use crate::application::supply::SupplyOutput;
+use crate::domain::entity::Entity;
+use crate::domain::identifier::Identifier;
+use crate::domain::supply::supply_gateway::SupplyGateway;
+use crate::domain::supply::supply_id::SupplyId;
+use crate::domain::validation::error::CustomError;
+use crate::domain::validation::notification::Notification;
+use crate::i18n;
+use crate::i18n::Locale;
+use crate::infra::adapters::rabbitmq_adapter::Offset;
+use crate::infra::db::DbSqlx;
+use crate::middler::websocket::Channel;
+use crate::middler::websocket::Connection;
+use crate::middler::websocket::WebSocket;
+use crate::problem::Problem;
+use crate::supplies;
+use crate::supply_events::Subscription;
+use crate::supply_events::SupplyEventStream;
+use json_patch::Patch;
+use rocket::futures::SinkExt;
+use rocket::futures::StreamExt;
+use rocket::get;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::State;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
+use tracing::warn;
+
+const ROOM_CAPACITY: usize = 256;
+
+/// A change requested by a member of a room, run through the supply use
+/// cases like the equivalent REST call. Supplies created in a room are put on
+/// its list; `update` and `delete` only apply to supplies on it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoomCommand {
+    Create {
+        #[serde(flatten)]
+        input: CreateSupplyInput,
+    },
+    Update {
+        id: String,
+        /// The version being edited; `None` overwrites any version.
+        #[serde(default)]
+        version: Option<i64>,
+        #[serde(flatten)]
+        input: UpdateSupplyInput,
+    },
+    /// Deletes the supply from the catalog.
+    Delete { id: String },
+    /// Puts a supply of the catalog on the list.
+    Add { id: String },
+    /// Takes the supply off the list, keeping it in the catalog.
+    Remove { id: String },
+}
+
+/// A command with the reference the client uses to match its outcome.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomRequest {
+    #[serde(rename = "ref", default)]
+    pub reference: Option<String>,
+    #[serde(flatten)]
+    pub command: RoomCommand,
+}
+
+/// What members of a room receive. Patches (RFC 6902) apply to the snapshot,
+/// an object of supplies by id; `by` is `None` for changes made outside the
+/// room, through the API or another room.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoomMessage {
+    Snapshot {
+        supplies: BTreeMap<String, SupplyOutput>,
+    },
+    Patch {
+        by: Option<String>,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+        ops: Patch,
+    },
+    Error {
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+        status: u16,
+        errors: Vec<String>,
+    },
+    Joined {
+        user: String,
+        members: Vec<String>,
+    },
+    Left {
+        user: String,
+        members: Vec<String>,
+    },
+}
+
+impl RoomMessage {
+    pub fn error(reference: Option<String>, notification: Notification, locale: Locale) -> Self {
+        let errors = notification.format_errors_in(locale);
+        RoomMessage::Error {
+            reference,
+            status: Problem::from(notification).status().code,
+            errors,
+        }
+    }
+}
+
+/// The change a command made to a supply: its state after the command, or
+/// `None` once it is deleted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub id: String,
+    pub after: Option<SupplyOutput>,
+}
+
+/// What a room relays to its members.
+#[derive(Debug, Clone)]
+pub enum Broadcast {
+    Message(RoomMessage),
+    Change {
+        by: String,
+        reference: Option<String>,
+        change: Change,
+    },
+}
+
+struct Room {
+    sender: broadcast::Sender<Broadcast>,
+    members: BTreeMap<u64, String>,
+}
+
+impl Room {
+    fn members(&self) -> Vec<String> {
+        self.members.values().cloned().collect()
+    }
+}
+
+/// The open rooms, one per supply list of a tenant. A room shows the
+/// supplies on its list, and changes made to them anywhere else reach its
+/// members through the event stream.
+#[derive(Clone, Default)]
+pub struct SupplyRooms {
+    rooms: Arc<Mutex<HashMap<(String, String), Room>>>,
+    next_member: Arc<AtomicU64>,
+}
+
+impl SupplyRooms {
+    pub fn join(&self, tenant_id: &str, list: &str, user: &str) -> Membership {
+        let key = (tenant_id.to_string(), list.to_string());
+        let id = self.next_member.fetch_add(1, Ordering::Relaxed);
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.entry(key.to_owned()).or_insert_with(|| Room {
+            sender: broadcast::channel(ROOM_CAPACITY).0,
+            members: BTreeMap::new(),
+        });
+        room.members.insert(id, user.to_string());
+        let receiver = room.sender.subscribe();
+        let _ = room.sender.send(Broadcast::Message(RoomMessage::Joined {
+            user: user.to_string(),
+            members: room.members(),
+        }));
+
+        Membership {
+            rooms: self.clone(),
+            key,
+            id,
+            receiver,
+        }
+    }
+
+    fn leave(&self, key: &(String, String), id: u64) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(key) else {
+            return;
+        };
+        let Some(user) = room.members.remove(&id) else {
+            return;
+        };
+        if room.members.is_empty() {
+            rooms.remove(key);
+        } else {
+            let _ = room.sender.send(Broadcast::Message(RoomMessage::Left {
+                user,
+                members: room.members(),
+            }));
+        }
+    }
+
+    fn broadcast(&self, key: &(String, String), message: Broadcast) {
+        if let Some(room) = self.rooms.lock().unwrap().get(key) {
+            let _ = room.sender.send(message);
+        }
+    }
+}
+
+/// A member of a room, who leaves it when dropped.
+pub struct Membership {
+    rooms: SupplyRooms,
+    key: (String, String),
+    id: u64,
+    receiver: broadcast::Receiver<Broadcast>,
+}
+
+impl Membership {
+    pub fn broadcast(&self, message: Broadcast) {
+        self.rooms.broadcast(&self.key, message);
+    }
+
+    pub async fn recv(&mut self) -> Result<Broadcast, RecvError> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        self.rooms.leave(&self.key, self.id);
+    }
+}
+
+/// Runs room commands through the supply use cases of a tenant, on the
+/// supplies of a list.
+pub struct SupplyCommands {
+    supplies: Arc<dyn SupplyGateway>,
+    events: Arc<dyn SupplyEventPublisher>,
+    list: String,
+}
+
+impl SupplyCommands {
+    pub fn new(
+        supplies: Arc<dyn SupplyGateway>,
+        events: Arc<dyn SupplyEventPublisher>,
+        list: &str,
+    ) -> Self {
+        Self {
+            supplies,
+            events,
+            list: list.to_string(),
+        }
+    }
+
+    pub async fn snapshot(&self) -> Result<BTreeMap<String, SupplyOutput>, Notification> {
+        let supplies = ListSuppliesUseCase::new(self.supplies.clone())
+            .in_list(&self.list)
+            .await?;
+        Ok(supplies
+            .into_iter()
+            .map(|supply| (supply.id.to_owned(), supply))
+            .collect())
+    }
+
+    /// Executes `command`, returning the change it made.
+    pub async fn execute(
+        &self,
+        context: &AuditContext,
+        command: RoomCommand,
+    ) -> Result<Change, Notification> {
+        match command {
+            RoomCommand::Create { input } => {
+                let created = CreateSupplyUseCase::new(self.supplies.clone(), self.events.clone())
+                    .execute(context, input)
+                    .await?;
+                self.add(&created.id).await
+            }
+            RoomCommand::Update { id, version, input } => {
+                self.ensure_listed(&id).await?;
+                let after = UpdateSupplyUseCase::new(self.supplies.clone(), self.events.clone())
+                    .execute(context, &id, version, input)
+                    .await?;
+                Ok(Change {
+                    id,
+                    after: Some(after),
+                })
+            }
+            RoomCommand::Delete { id } => {
+                self.ensure_listed(&id).await?;
+                DeleteSupplyUseCase::new(self.supplies.clone(), self.events.clone())
+                    .execute(context, &id)
+                    .await?;
+                Ok(Change { id, after: None })
+            }
+            RoomCommand::Add { id } => self.add(&id).await,
+            RoomCommand::Remove { id } => {
+                self.supplies
+                    .remove_from_list(&self.list, &SupplyId::from_str(&id))
+                    .await
+                    .map_err(Notification::with_one_error)?;
+                Ok(Change { id, after: None })
+            }
+        }
+    }
+
+    async fn add(&self, id: &str) -> Result<Change, Notification> {
+        let added = self
+            .supplies
+            .add_to_list(&self.list, &SupplyId::from_str(id))
+            .await
+            .map_err(Notification::with_one_error)?;
+        match added {
+            Some(supply) => Ok(Change {
+                id: id.to_string(),
+                after: Some(SupplyOutput::from(&supply)),
+            }),
+            None => Err(not_found(id)),
+        }
+    }
+
+    async fn ensure_listed(&self, id: &str) -> Result<(), Notification> {
+        let listed = self
+            .supplies
+            .find_in_list(&self.list)
+            .await
+            .map_err(Notification::with_one_error)?;
+        match listed
+            .iter()
+            .any(|supply| supply.get_id().get_value() == id)
+        {
+            true => Ok(()),
+            false => Err(not_found(id)),
+        }
+    }
+
+    /// The current state of the supply an event is about, read again since
+    /// later changes may have been made meanwhile.
+    pub async fn current(&self, event: &SupplyEvent) -> Result<Change, Notification> {
+        let id = event.supply_id().to_string();
+        match GetSupplyUseCase::new(self.supplies.clone())
+            .execute(&id)
+            .await
+        {
+            Ok(after) => Ok(Change {
+                id,
+                after: Some(after),
+            }),
+            Err(notification)
+                if matches!(notification.errors.first(), Some(CustomError::NotFound(_))) =>
+            {
+                Ok(Change { id, after: None })
+            }
+            Err(notification) => Err(notification),
+        }
+    }
+}
+
+fn not_found(id: &str) -> Notification {
+    Notification::with_one_error(CustomError::NotFound(
+        i18n::Message::new("supply-not-on-list").arg("id", id),
+    ))
+}
+
+/// The supplies a member was last sent, to patch only with changes it has
+/// not seen: a change made in the room also arrives through the event stream.
+#[derive(Debug, Default)]
+pub struct RoomView {
+    supplies: BTreeMap<String, SupplyOutput>,
+}
+
+impl RoomView {
+    /// Starts over from `supplies`, returning their snapshot.
+    pub fn reset(&mut self, supplies: BTreeMap<String, SupplyOutput>) -> RoomMessage {
+        self.supplies = supplies;
+        RoomMessage::Snapshot {
+            supplies: self.supplies.clone(),
+        }
+    }
+
+    /// Whether the view shows the supply `id`, which is then on the list.
+    pub fn contains(&self, id: &str) -> bool {
+        self.supplies.contains_key(id)
+    }
+
+    /// The patch applying `change`, or `None` if the view already has it or
+    /// a later version.
+    pub fn apply(&mut self, change: Change) -> Option<Patch> {
+        let before = self.supplies.get(&change.id);
+        match (before, &change.after) {
+            (None, None) => return None,
+            (Some(before), Some(after)) if before.version >= after.version => return None,
+            _ => {}
+        }
+        let ops = patch(&change.id, before, change.after.as_ref());
+        match change.after {
+            Some(after) => self.supplies.insert(change.id, after),
+            None => self.supplies.remove(&change.id),
+        };
+        Some(ops)
+    }
+}
+
+/// The patch turning the snapshot entry of `id` from `before` into `after`.
+fn patch(id: &str, before: Option<&SupplyOutput>, after: Option<&SupplyOutput>) -> Patch {
+    let entry = |supply: Option<&SupplyOutput>| {
+        let mut entries = serde_json::Map::new();
+        if let Some(supply) = supply {
+            entries.insert(id.to_string(), serde_json::to_value(supply).unwrap());
+        }
+        Value::Object(entries)
+    };
+    json_patch::diff(&entry(before), &entry(after))
+}
+
+async fn send(connection: &mut Connection, message: &RoomMessage) -> bool {
+    let text = serde_json::to_string(message).unwrap();
+    connection.send(Message::Text(text)).await.is_ok()
+}
+
+async fn snapshot(commands: &SupplyCommands, view: &mut RoomView, locale: Locale) -> RoomMessage {
+    match commands.snapshot().await {
+        Ok(supplies) => view.reset(supplies),
+        Err(notification) => RoomMessage::error(None, notification, locale),
+    }
+}
+
+/// The next event of `subscription`, or never without one.
+async fn next_event(subscription: &mut Option<Subscription>) -> Option<(u64, SupplyEvent)> {
+    match subscription {
+        Some(subscription) => subscription.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn session(
+    mut connection: Connection,
+    commands: SupplyCommands,
+    context: AuditContext,
+    mut membership: Membership,
+    mut subscription: Option<Subscription>,
+    locale: Locale,
+) {
+    let mut view = RoomView::default();
+    let message = snapshot(&commands, &mut view, locale).await;
+    if !send(&mut connection, &message).await {
+        return;
+    }
+
+    loop {
+        let message = select! {
+            incoming = connection.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let request = match serde_json::from_str::<RoomRequest>(&text) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        let error = RoomMessage::Error {
+                            reference: None,
+                            status: 400,
+                            errors: vec![e.to_string()],
+                        };
+                        if !send(&mut connection, &error).await {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                match commands.execute(&context, request.command).await {
+                    Ok(change) => {
+                        membership.broadcast(Broadcast::Change {
+                            by: context.actor.to_owned(),
+                            reference: request.reference,
+                            change,
+                        });
+                        continue;
+                    }
+                    Err(notification) => RoomMessage::error(request.reference, notification, locale),
+                }
+            }
+            broadcast = membership.recv() => match broadcast {
+                Ok(Broadcast::Message(message)) => message,
+                // Sent even when the event stream got the change here first,
+                // so that its author sees the reference acknowledged
+                Ok(Broadcast::Change { by, reference, change }) => RoomMessage::Patch {
+                    by: Some(by),
+                    reference,
+                    ops: view.apply(change).unwrap_or_default(),
+                },
+                // Patches were missed, so start over from a fresh snapshot
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!(skipped, "room member lagged behind");
+                    snapshot(&commands, &mut view, locale).await
+                }
+                Err(RecvError::Closed) => break,
+            },
+            event = next_event(&mut subscription) => {
+                let Some((_, event)) = event else {
+                    warn!("supply events ended, room changes made elsewhere are no longer relayed");
+                    subscription = None;
+                    continue;
+                };
+                // Supplies are put on the list through the room, which
+                // relays that itself
+                if !view.contains(event.supply_id()) {
+                    continue;
+                }
+                match commands.current(&event).await {
+                    Ok(change) => match view.apply(change) {
+                        Some(ops) => RoomMessage::Patch {
+                            by: None,
+                            reference: None,
+                            ops,
+                        },
+                        None => continue,
+                    },
+                    Err(notification) => RoomMessage::error(None, notification, locale),
+                }
+            }
+        };
+        if !send(&mut connection, &message).await {
+            break;
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "supplies",
+    context_path = "/supplies",
+    params(("list" = String, Path, description = "The supply list: members of the same list see each other and its supplies")),
+    responses(
+        (status = 101, description = "Joined the room of the list. The server sends a `snapshot` of the supplies on the list, \
+            then `patch`es (RFC 6902) of it for changes made in the room or through the API, and `joined`/`left` \
+            presence messages; clients send `create`, `update` and `delete` commands and `add`/`remove` to put \
+            supplies of the catalog on the list or take them off, whose validation errors come back as `error` messages"),
+        (status = 400, description = "Missing tenant", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Upgrade requested by a page of another site", body = Problem, content_type = "application/problem+json"),
+        (status = 426, description = "Not a WebSocket upgrade", body = Problem, content_type = "application/problem+json")
+    )
+)]
+#[get("/rooms/<list>")]
+pub async fn join(
+    ws: WebSocket,
+    db: &State<DbSqlx>,
+    events: &State<SupplyEventStream>,
+    rooms: &State<SupplyRooms>,
+    context: AuditContext,
+    locale: Locale,
+    list: &str,
+) -> Channel {
+    let tenant_id = &context.tenant_id;
+    let commands = SupplyCommands::new(
+        supplies::supply_gateway(db, tenant_id),
+        events.publisher(),
+        list,
+    );
+    let filter = SupplyEventFilter {
+        tenant_id: tenant_id.get_value().to_string(),
+        ..SupplyEventFilter::default()
+    };
+    // Subscribed before the snapshot is read, so no change falls in between
+    let subscription = match events.subscribe(filter, Offset::Next).await {
+        Ok(subscription) => Some(subscription),
+        Err(e) => {
+            warn!("room changes made elsewhere won't be relayed: {}", e);
+            None
+        }
+    };
+    let membership = rooms.join(tenant_id.get_value(), list, &context.actor);
+
+    ws.channel(move |connection| {
+        session(
+            connection,
+            commands,
+            context,
+            membership,
+            subscription,
+            locale,
+        )
+    })
+}
+
+#[cfg(test)]
+mod supply_rooms_tests {
+    use super::*;
+    use crate::application::testing::InMemorySupplyEventPublisher;
+    use crate::application::testing::InMemorySupplyGateway;
+    use crate::domain::tenant_id::TenantId;
+    use serde_json::json;
+
+    fn presence(message: Broadcast) -> (String, Vec<String>) {
+        match message {
+            Broadcast::Message(RoomMessage::Joined { user, members }) => {
+                (format!("+{}", user), members)
+            }
+            Broadcast::Message(RoomMessage::Left { user, members }) => {
+                (format!("-{}", user), members)
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn members_are_told_who_joins_and_leaves() {
+        let rooms = SupplyRooms::default();
+        let mut alice = rooms.join("acme", "obra-1", "alice");
+        let bob = rooms.join("acme", "obra-1", "bob");
+        let _other = rooms.join("acme", "obra-2", "carol");
+
+        assert_eq!(presence(alice.recv().await.unwrap()).0, "+alice");
+        assert_eq!(
+            presence(alice.recv().await.unwrap()),
+            (
+                "+bob".to_string(),
+                vec!["alice".to_string(), "bob".to_string()]
+            )
+        );
+
+        drop(bob);
+        assert_eq!(
+            presence(alice.recv().await.unwrap()),
+            ("-bob".to_string(), vec!["alice".to_string()])
+        );
+    }
+
+    #[rocket::async_test]
+    async fn commands_patch_the_snapshot_or_return_errors() {
+        let commands = SupplyCommands::new(
+            Arc::new(InMemorySupplyGateway::default()),
+            Arc::new(InMemorySupplyEventPublisher::default()),
+            "obra-1",
+        );
+        let mut view = RoomView::default();
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+        let request = |value: Value| serde_json::from_value::<RoomRequest>(value).unwrap();
+
+        let create = request(json!({
+            "type": "create", "ref": "1", "name": "cimento",
+            "prices": [{"unit": "sc", "value": 250.0}]
+        }));
+        assert_eq!(create.reference.as_deref(), Some("1"));
+        let change = commands.execute(&context, create.command).await.unwrap();
+        let id = change.id.to_owned();
+        let created = view.apply(change).unwrap();
+        let ops = serde_json::to_value(&created).unwrap();
+        assert_eq!(ops[0]["op"], "add");
+        assert_eq!(ops[0]["path"], format!("/{}", id));
+
+        let update = request(json!({
+            "type": "update", "id": id, "version": 1,
+            "prices": [{"unit": "sc", "value": 270.0}]
+        }));
+        let change = commands.execute(&context, update.command).await.unwrap();
+        let updated = view.apply(change.clone()).unwrap();
+        assert_eq!(view.apply(change), None);
+
+        let mut document = json!({});
+        json_patch::patch(&mut document, &created).unwrap();
+        json_patch::patch(&mut document, &updated).unwrap();
+        assert_eq!(document[&id]["name"], "cimento");
+        assert_eq!(document[&id]["prices"][0]["value"], 270.0);
+        assert_eq!(document[&id]["version"], 2);
+
+        let stale =
+            request(json!({"type": "update", "id": id, "version": 1, "name": "cimento cp2"}));
+        let error = commands.execute(&context, stale.command).await.unwrap_err();
+        assert!(matches!(
+            RoomMessage::error(Some("3".to_string()), error, Locale::En),
+            RoomMessage::Error { status: 412, .. }
+        ));
+
+        let invalid = request(json!({"type": "create", "name": "", "prices": []}));
+        let error = commands
+            .execute(&context, invalid.command)
+            .await
+            .unwrap_err();
+        match RoomMessage::error(None, error, Locale::PtBr) {
+            RoomMessage::Error { status, errors, .. } => {
+                assert_eq!(status, 422);
+                assert!(errors.contains(&"'name' não deve ficar vazio".to_string()));
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn changes_made_elsewhere_patch_the_view_once() {
+        let supplies = Arc::new(InMemorySupplyGateway::default());
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let commands = SupplyCommands::new(supplies.clone(), events.clone(), "obra-1");
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+        let mut view = RoomView::default();
+
+        let input = serde_json::from_value(json!({
+            "name": "cimento", "prices": [{"unit": "sc", "value": 250.0}]
+        }))
+        .unwrap();
+        let created = CreateSupplyUseCase::new(supplies.clone(), events.clone())
+            .execute(&context, input)
+            .await
+            .unwrap();
+        DeleteSupplyUseCase::new(supplies.clone(), events.clone())
+            .execute(&context, &created.id)
+            .await
+            .unwrap();
+        let published = events.events();
+        assert_eq!(published[1].name(), "SupplyDeleted");
+
+        // Both events are read after the delete, which the create event now reports
+        let change = commands.current(&published[0]).await.unwrap();
+        assert_eq!(change.after, None);
+        assert_eq!(view.apply(change), None);
+
+        view.reset(BTreeMap::from([(created.id.to_owned(), created.clone())]));
+        let change = commands.current(&published[1]).await.unwrap();
+        let ops = serde_json::to_value(view.apply(change.clone()).unwrap()).unwrap();
+        assert_eq!(ops[0]["op"], "remove");
+        assert_eq!(ops[0]["path"], format!("/{}", created.id));
+        assert_eq!(view.apply(change), None);
+    }
+
+    #[rocket::async_test]
+    async fn rooms_show_and_edit_the_supplies_of_their_list() {
+        let supplies = Arc::new(InMemorySupplyGateway::default());
+        let events = Arc::new(InMemorySupplyEventPublisher::default());
+        let commands = SupplyCommands::new(supplies.clone(), events.clone(), "obra-1");
+        let context = AuditContext::new("alice", TenantId::from_str("acme"), None);
+        let request = |value: Value| serde_json::from_value::<RoomRequest>(value).unwrap();
+
+        let input = serde_json::from_value(json!({
+            "name": "areia", "prices": [{"unit": "m3", "value": 120.0}]
+        }))
+        .unwrap();
+        let elsewhere = CreateSupplyUseCase::new(supplies.clone(), events.clone())
+            .execute(&context, input)
+            .await
+            .unwrap();
+        let create = request(json!({
+            "type": "create", "name": "cimento", "prices": [{"unit": "sc", "value": 250.0}]
+        }));
+        let created = commands.execute(&context, create.command).await.unwrap();
+
+        let snapshot = commands.snapshot().await.unwrap();
+        assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec![&created.id]);
+
+        let update = request(json!({"type": "update", "id": elsewhere.id, "name": "areia fina"}));
+        let error = commands
+            .execute(&context, update.command)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            RoomMessage::error(None, error, Locale::En),
+            RoomMessage::Error { status: 404, .. }
+        ));
+
+        let add = request(json!({"type": "add", "id": elsewhere.id}));
+        let added = commands.execute(&context, add.command).await.unwrap();
+        assert_eq!(added.after.unwrap().name, "areia");
+        assert_eq!(commands.snapshot().await.unwrap().len(), 2);
+
+        let remove = request(json!({"type": "remove", "id": created.id}));
+        let removed = commands.execute(&context, remove.command).await.unwrap();
+        assert_eq!(removed.after, None);
+        assert_eq!(
+            commands
+                .snapshot()
+                .await
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&elsewhere.id]
+        );
+        assert_eq!(supplies.len(), 2);
+    }
+}